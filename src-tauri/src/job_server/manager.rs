@@ -0,0 +1,188 @@
+use crate::utils::progress::{new_job_id, ProgressEvent, ProgressReporter};
+use futures::FutureExt;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// 任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+  ShapefileToGeojson,
+  ShapefileToRecord,
+  CreateServer,
+  ValidateShapefile,
+  RepairShapefile,
+  OgrToGeojson,
+  VectorTranslate,
+}
+
+/// 任务列表与状态查询返回的摘要
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSummary {
+  pub job_id: String,
+  pub kind: JobKind,
+  pub created_at: u64,
+  pub finished: bool,
+  pub progress: ProgressEvent,
+}
+
+struct Job {
+  kind: JobKind,
+  created_at: u64,
+  progress: ProgressReporter,
+  result: Option<Result<serde_json::Value, String>>,
+}
+
+impl Job {
+  fn summary(&self) -> JobSummary {
+    let progress = self.progress.snapshot();
+    JobSummary {
+      job_id: progress.job_id.clone(),
+      kind: self.kind,
+      created_at: self.created_at,
+      finished: self.result.is_some(),
+      progress,
+    }
+  }
+}
+
+/// 后台任务管理器，通过 `app.manage` 注册为全局状态
+///
+/// 取消是协作式的：只设置取消标记，由任务在循环中检查后自行退出
+#[derive(Default)]
+pub struct JobManager {
+  jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl JobManager {
+  /// 在后台运行任务并立即返回任务 id，结果通过 `result` 查询
+  ///
+  /// 前端可以预先生成 `job_id` 以便在任务开始前监听进度，已存在的 id 会被拒绝
+  pub fn spawn<F, Fut>(
+    &self,
+    app_handle: &AppHandle,
+    kind: JobKind,
+    job_id: Option<String>,
+    task: F,
+  ) -> Result<String, String>
+  where
+    F: FnOnce(ProgressReporter) -> Fut,
+    Fut: Future<Output = Result<serde_json::Value, String>> + Send + 'static,
+  {
+    let job_id = job_id.unwrap_or_else(new_job_id);
+    let progress = ProgressReporter::new(app_handle, job_id.clone());
+    let created_at = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_millis() as u64)
+      .unwrap_or_default();
+    {
+      let mut jobs = self.jobs.lock().map_err(|_| "任务列表不可用".to_string())?;
+      if jobs.contains_key(&job_id) {
+        return Err(format!("任务 id 已存在: {}", job_id));
+      }
+      jobs.insert(
+        job_id.clone(),
+        Job {
+          kind,
+          created_at,
+          progress: progress.clone(),
+          result: None,
+        },
+      );
+    }
+
+    let future = task(progress.clone());
+    let app_handle = app_handle.clone();
+    let task_job_id = job_id.clone();
+    tauri::async_runtime::spawn(async move {
+      // 任务 panic 时同样记录结果，否则查询方会一直等待
+      let result = AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|panic| Err(format!("任务异常终止: {}", panic_message(&*panic))));
+      if let Err(e) = &result {
+        progress.fail(e.clone());
+      }
+      app_handle
+        .state::<JobManager>()
+        .complete(&task_job_id, result);
+    });
+    Ok(job_id)
+  }
+
+  fn complete(&self, job_id: &str, result: Result<serde_json::Value, String>) {
+    if let Ok(mut jobs) = self.jobs.lock() {
+      if let Some(job) = jobs.get_mut(job_id) {
+        job.result = Some(result);
+      }
+    }
+  }
+
+  /// 按创建时间排序的全部任务
+  pub fn list(&self) -> Vec<JobSummary> {
+    let mut summaries: Vec<JobSummary> = self
+      .jobs
+      .lock()
+      .map(|jobs| jobs.values().map(Job::summary).collect())
+      .unwrap_or_default();
+    summaries.sort_by_key(|summary| summary.created_at);
+    summaries
+  }
+
+  pub fn status(&self, job_id: &str) -> Result<JobSummary, String> {
+    self.with_job(job_id, |job| job.summary())
+  }
+
+  /// 任务结果，未完成时返回错误
+  pub fn result(&self, job_id: &str) -> Result<serde_json::Value, String> {
+    self.with_job(job_id, |job| match &job.result {
+      Some(result) => result.clone(),
+      None => Err("任务尚未完成".to_string()),
+    })?
+  }
+
+  pub fn cancel(&self, job_id: &str) -> Result<JobSummary, String> {
+    self.with_job(job_id, |job| {
+      if job.result.is_none() {
+        job.progress.cancel();
+      }
+      job.summary()
+    })
+  }
+
+  /// 移除已结束的任务，释放保存的结果
+  pub fn clear_finished(&self) -> usize {
+    self
+      .jobs
+      .lock()
+      .map(|mut jobs| {
+        let before = jobs.len();
+        jobs.retain(|_, job| job.result.is_none());
+        before - jobs.len()
+      })
+      .unwrap_or_default()
+  }
+
+  fn with_job<T>(&self, job_id: &str, f: impl FnOnce(&Job) -> T) -> Result<T, String> {
+    let jobs = self.jobs.lock().map_err(|_| "任务列表不可用".to_string())?;
+    jobs
+      .get(job_id)
+      .map(f)
+      .ok_or_else(|| format!("任务不存在: {}", job_id))
+  }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+  panic
+    .downcast_ref::<&str>()
+    .map(|message| message.to_string())
+    .or_else(|| panic.downcast_ref::<String>().cloned())
+    .unwrap_or_else(|| "未知错误".to_string())
+}
@@ -1,7 +1,20 @@
 use std::path;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use job_server::manager::{JobKind, JobManager};
+use map_server::tiling::TilingProfile;
+use map_server::translate::TranslateOptions;
+use shapefile_server::archive::ShapefileInput;
+use shapefile_server::dbf_edit::RecordEdit;
+use shapefile_server::dbf_schema::FieldChange;
+use shapefile_server::field_stats::FieldStatsOptions;
+use shapefile_server::geojson_to_shapefile::ShapefileExportOptions;
+use shapefile_server::geojson_writer::OutputOptions;
+use shapefile_server::geometry::ConvertOptions;
+use shapefile_server::record_query::RecordQuery;
+use shapefile_server::repair::RepairOptions;
 use utils::response::create_response;
+mod job_server;
 mod map_server;
 mod shapefile_server;
 mod utils;
@@ -11,42 +24,484 @@ fn disk_read_dir(path: Option<&str>) -> Result<serde_json::Value, String> {
   utils::disk::disk_read_dir(path)
 }
 
+// tauri 命令的参数即前端传入的字段，不合并为结构体
 #[tauri::command]
-async fn shapefile_to_geojson(shapefile_path: &str) -> Result<serde_json::Value, String> {
-  shapefile_server::utilities::shapefile_to_geojson(shapefile_path).await
+#[allow(clippy::too_many_arguments)]
+fn shapefile_to_geojson(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  shapefile_path: String,
+  inner_path: Option<String>,
+  options: Option<ConvertOptions>,
+  encoding: Option<String>,
+  target_crs: Option<String>,
+  output: Option<OutputOptions>,
+) -> Result<serde_json::Value, String> {
+  let options = options.unwrap_or_default();
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::ShapefileToGeojson,
+    job_id,
+    |progress| async move {
+      let input =
+        ShapefileInput::open(&shapefile_path, inner_path.as_deref()).map_err(|e| e.to_string())?;
+      let shapefile_path = input.path_str().map_err(|e| e.to_string())?;
+      match output {
+        Some(output) => {
+          shapefile_server::utilities::shapefile_to_geojson_file(
+            shapefile_path,
+            options,
+            encoding.as_deref(),
+            target_crs.as_deref(),
+            output,
+            &progress,
+          )
+          .await
+        }
+        None => {
+          shapefile_server::utilities::shapefile_to_geojson(
+            shapefile_path,
+            options,
+            encoding.as_deref(),
+            target_crs.as_deref(),
+            &progress,
+          )
+          .await
+        }
+      }
+    },
+  )?;
+  Ok(job_created_response(job_id))
 }
 
 #[tauri::command]
-async fn shapefile_to_record(shapefile_path: &str) -> Result<serde_json::Value, String> {
-  shapefile_server::utilities::shapefile_to_record(shapefile_path)
-    .await
-    .map_err(|e| e.to_string())
+fn shapefile_to_record(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  shapefile_path: String,
+  inner_path: Option<String>,
+  options: Option<ConvertOptions>,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  let options = options.unwrap_or_default();
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::ShapefileToRecord,
+    job_id,
+    |progress| async move {
+      let input =
+        ShapefileInput::open(&shapefile_path, inner_path.as_deref()).map_err(|e| e.to_string())?;
+      shapefile_server::utilities::shapefile_to_record(
+        input.path_str().map_err(|e| e.to_string())?,
+        options,
+        encoding.as_deref(),
+        &progress,
+      )
+      .await
+      .map_err(|e| e.to_string())
+    },
+  )?;
+  Ok(job_created_response(job_id))
 }
 
 #[tauri::command]
-async fn create_server(app_handle: tauri::AppHandle, input_path: &str) -> Result<serde_json::Value, String> {
-  let input_path = path::Path::new(input_path);
-  if !input_path.exists() {
+fn shapefile_validate(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  shapefile_path: String,
+  inner_path: Option<String>,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::ValidateShapefile,
+    job_id,
+    |progress| async move {
+      let input =
+        ShapefileInput::open(&shapefile_path, inner_path.as_deref()).map_err(|e| e.to_string())?;
+      shapefile_server::utilities::shapefile_validate(
+        input.path_str().map_err(|e| e.to_string())?,
+        encoding.as_deref(),
+        &progress,
+      )
+      .await
+    },
+  )?;
+  Ok(job_created_response(job_id))
+}
+
+#[tauri::command]
+fn shapefile_repair(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  shapefile_path: String,
+  inner_path: Option<String>,
+  options: Option<RepairOptions>,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::RepairShapefile,
+    job_id,
+    |progress| async move {
+      let input =
+        ShapefileInput::open(&shapefile_path, inner_path.as_deref()).map_err(|e| e.to_string())?;
+      shapefile_server::utilities::shapefile_repair(
+        input.path_str().map_err(|e| e.to_string())?,
+        options.unwrap_or_default(),
+        encoding.as_deref(),
+        &progress,
+      )
+      .await
+    },
+  )?;
+  Ok(job_created_response(job_id))
+}
+
+#[tauri::command]
+async fn ogr_list_layers(path: &str) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::ogr_list_layers(path).await
+}
+
+#[tauri::command]
+async fn dataset_info(path: &str) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dataset_info_summary(path).await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn ogr_to_geojson(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  path: String,
+  layer: Option<String>,
+  options: Option<ConvertOptions>,
+  source_crs: Option<String>,
+  target_crs: Option<String>,
+) -> Result<serde_json::Value, String> {
+  let options = options.unwrap_or_default();
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::OgrToGeojson,
+    job_id,
+    |progress| async move {
+      shapefile_server::utilities::ogr_to_geojson(
+        &path,
+        layer.as_deref(),
+        options,
+        source_crs.as_deref(),
+        target_crs.as_deref(),
+        &progress,
+      )
+      .await
+    },
+  )?;
+  Ok(job_created_response(job_id))
+}
+
+#[tauri::command]
+async fn shapefile_record_page(
+  shapefile_path: &str,
+  query: Option<RecordQuery>,
+  options: Option<ConvertOptions>,
+  encoding: Option<&str>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::shapefile_record_page(
+    shapefile_path,
+    query.unwrap_or_default(),
+    options.unwrap_or_default(),
+    encoding,
+  )
+  .await
+  .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn shapefile_field_stats(
+  shapefile_path: &str,
+  options: Option<FieldStatsOptions>,
+  encoding: Option<&str>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::shapefile_field_stats(
+    shapefile_path,
+    options.unwrap_or_default(),
+    encoding,
+  )
+  .await
+  .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn shapefile_schema(
+  shapefile_path: &str,
+  encoding: Option<&str>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::shapefile_schema(shapefile_path, encoding).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn shapefile_bbox_query(
+  shapefile_path: &str,
+  bbox: [f64; 4],
+  zoom: Option<f64>,
+  options: Option<ConvertOptions>,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::shapefile_bbox_query(
+    shapefile_path,
+    bbox,
+    zoom,
+    options.unwrap_or_default(),
+    encoding,
+    target_crs,
+  )
+  .await
+}
+
+#[tauri::command]
+async fn geojson_to_shapefile(
+  geojson: Option<serde_json::Value>,
+  geojson_path: Option<String>,
+  options: Option<ShapefileExportOptions>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::geojson_to_shapefile(
+    geojson,
+    geojson_path,
+    options.unwrap_or_default(),
+  )
+  .await
+}
+
+#[tauri::command]
+async fn dbf_edit_begin(
+  shapefile_path: String,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dbf_edit_begin(shapefile_path, encoding).await
+}
+
+#[tauri::command]
+async fn dbf_edit_update(
+  session_id: String,
+  edits: Vec<RecordEdit>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dbf_edit_update(session_id, edits).await
+}
+
+#[tauri::command]
+async fn dbf_edit_commit(session_id: String) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dbf_edit_commit(session_id).await
+}
+
+#[tauri::command]
+async fn dbf_edit_rollback(session_id: String) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dbf_edit_rollback(session_id).await
+}
+
+#[tauri::command]
+fn dbf_edit_sessions() -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dbf_edit_sessions()
+}
+
+#[tauri::command]
+async fn dbf_change_fields(
+  shapefile_path: String,
+  changes: Vec<FieldChange>,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  shapefile_server::utilities::dbf_change_fields(shapefile_path, changes, encoding).await
+}
+
+#[tauri::command]
+fn create_server(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  input_path: String,
+  inner_path: Option<String>,
+  profile: Option<TilingProfile>,
+  profile_name: Option<String>,
+) -> Result<serde_json::Value, String> {
+  if !path::Path::new(&input_path).exists() {
     return Err("文件不存在".to_string());
   }
-  let file_name = input_path
-    .file_stem()
-    .and_then(|name| name.to_str())
-    .ok_or_else(|| "无法获取文件名".to_string())?;
+  // 显式传入的配置优先于保存的配置
+  let profile = match (profile, profile_name) {
+    (Some(profile), _) => profile,
+    (None, Some(name)) => map_server::tiling::load_profile(&name)?,
+    (None, None) => TilingProfile::default(),
+  };
+  profile.validate()?;
+  map_server::translate::check_vector_driver(profile.driver_name()).map_err(|e| e.to_string())?;
+  let task_app_handle = app_handle.clone();
 
-  let mbtiles_path = utils::files::get_mbtiles_path();
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::CreateServer,
+    job_id,
+    |progress| async move {
+      let input =
+        ShapefileInput::open(&input_path, inner_path.as_deref()).map_err(|e| e.to_string())?;
+      let file_name = input
+        .path()
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| "无法获取文件名".to_string())?;
 
-  let output_path = mbtiles_path.join(format!("{}.mbtiles", file_name));
+      let mbtiles_path = utils::files::get_mbtiles_path();
 
-  if let Err(e) = map_server::command::create_server(input_path, &output_path).await {
-    return Ok(create_response::<()>(false, None, e.to_string()));
-  }
+      let output_path = mbtiles_path.join(profile.output_file_name(file_name));
+      map_server::command::create_server(input.path(), &output_path, &profile, &progress).await?;
+      map_server::command::start_server(&task_app_handle)?;
+      Ok(create_response(
+        true,
+        Some(serde_json::json!({ "jobId": progress.job_id(), "outputPath": output_path })),
+        "成功".to_string(),
+      ))
+    },
+  )?;
+  Ok(job_created_response(job_id))
+}
+
+#[tauri::command]
+fn tiling_profile_list() -> Result<serde_json::Value, String> {
+  let profiles = map_server::tiling::list_profiles()?;
+  Ok(create_response(true, Some(profiles), "成功".to_string()))
+}
+
+#[tauri::command]
+fn tiling_profile_save(name: &str, profile: TilingProfile) -> Result<serde_json::Value, String> {
+  let saved = map_server::tiling::save_profile(name, profile)?;
+  Ok(create_response(true, Some(saved), "成功".to_string()))
+}
 
-  if let Err(e) = map_server::command::start_server(&app_handle)   {
-    return Ok(create_response::<()>(false, None, e.to_string())); 
+#[tauri::command]
+fn tiling_profile_delete(name: &str) -> Result<serde_json::Value, String> {
+  map_server::tiling::delete_profile(name)?;
+  Ok(create_response(
+    true,
+    Some(serde_json::json!({ "name": name })),
+    "成功".to_string(),
+  ))
+}
+
+#[tauri::command]
+fn vector_translate(
+  app_handle: tauri::AppHandle,
+  jobs: tauri::State<'_, JobManager>,
+  job_id: Option<String>,
+  input_path: String,
+  inner_path: Option<String>,
+  output_path: String,
+  options: TranslateOptions,
+) -> Result<serde_json::Value, String> {
+  if !path::Path::new(&input_path).exists() {
+    return Err("文件不存在".to_string());
   }
+  map_server::translate::check_vector_driver(&options.format).map_err(|e| e.to_string())?;
+  let output_path =
+    map_server::translate::workspace_output_path(&output_path).map_err(|e| e.to_string())?;
+
+  let job_id = jobs.spawn(
+    &app_handle,
+    JobKind::VectorTranslate,
+    job_id,
+    |progress| async move {
+      let input =
+        ShapefileInput::open(&input_path, inner_path.as_deref()).map_err(|e| e.to_string())?;
+      let input_path = input
+        .path()
+        .to_str()
+        .ok_or_else(|| "无法转换 input_path".to_string())?
+        .to_string();
+      let task_progress = progress.clone();
+      let task_output_path = output_path.clone();
+      tokio::task::spawn_blocking(move || {
+        map_server::translate::vector_translate(
+          &input_path,
+          &task_output_path,
+          &options,
+          &task_progress,
+        )
+      })
+      .await
+      .map_err(|e| format!("转换任务异常退出: {}", e))?
+      .map_err(|e| e.to_string())?;
+      Ok(create_response(
+        true,
+        Some(serde_json::json!({ "jobId": progress.job_id(), "outputPath": output_path })),
+        "成功".to_string(),
+      ))
+    },
+  )?;
+  Ok(job_created_response(job_id))
+}
+
+#[tauri::command]
+fn zip_list_datasets(zip_path: &str) -> Result<serde_json::Value, String> {
+  let datasets = shapefile_server::archive::list_zip_datasets(path::Path::new(zip_path))
+    .map_err(|e| e.to_string())?;
+  Ok(create_response(true, Some(datasets), "成功".to_string()))
+}
+
+#[tauri::command]
+fn job_list(jobs: tauri::State<'_, JobManager>) -> Result<serde_json::Value, String> {
+  Ok(create_response(true, Some(jobs.list()), "成功".to_string()))
+}
+
+#[tauri::command]
+fn job_status(
+  jobs: tauri::State<'_, JobManager>,
+  job_id: &str,
+) -> Result<serde_json::Value, String> {
+  Ok(create_response(
+    true,
+    Some(jobs.status(job_id)?),
+    "成功".to_string(),
+  ))
+}
+
+#[tauri::command]
+fn job_result(
+  jobs: tauri::State<'_, JobManager>,
+  job_id: &str,
+) -> Result<serde_json::Value, String> {
+  jobs.result(job_id)
+}
+
+#[tauri::command]
+fn job_cancel(
+  jobs: tauri::State<'_, JobManager>,
+  job_id: &str,
+) -> Result<serde_json::Value, String> {
+  Ok(create_response(
+    true,
+    Some(jobs.cancel(job_id)?),
+    "成功".to_string(),
+  ))
+}
+
+#[tauri::command]
+fn job_clear(jobs: tauri::State<'_, JobManager>) -> Result<serde_json::Value, String> {
+  Ok(create_response(
+    true,
+    Some(jobs.clear_finished()),
+    "成功".to_string(),
+  ))
+}
 
-  Ok(create_response::<()>(true, None, "成功".to_string()))
+fn job_created_response(job_id: String) -> serde_json::Value {
+  create_response(
+    true,
+    Some(serde_json::json!({ "jobId": job_id })),
+    "任务已创建".to_string(),
+  )
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -60,9 +515,36 @@ pub fn run() {
     .invoke_handler(tauri::generate_handler![
       disk_read_dir,
       shapefile_to_record,
+      shapefile_record_page,
+      ogr_list_layers,
+      ogr_to_geojson,
+      dataset_info,
+      shapefile_field_stats,
+      shapefile_schema,
+      shapefile_validate,
+      shapefile_repair,
+      shapefile_bbox_query,
+      zip_list_datasets,
       create_server,
-      shapefile_to_geojson
+      vector_translate,
+      tiling_profile_list,
+      tiling_profile_save,
+      tiling_profile_delete,
+      shapefile_to_geojson,
+      geojson_to_shapefile,
+      dbf_edit_begin,
+      dbf_edit_update,
+      dbf_edit_commit,
+      dbf_edit_rollback,
+      dbf_edit_sessions,
+      dbf_change_fields,
+      job_list,
+      job_status,
+      job_result,
+      job_cancel,
+      job_clear
     ])
+    .manage(JobManager::default())
     .setup(|app| {
       utils::window::init_window_config(&app.handle())?;
       utils::files::init_workspace();
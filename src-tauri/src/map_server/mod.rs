@@ -1 +1,3 @@
 pub mod command;
+pub mod tiling;
+pub mod translate;
@@ -0,0 +1,245 @@
+use super::translate::TranslateOptions;
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// MVT 驱动支持的最大级别
+const MAX_ZOOM: u8 = 22;
+
+/// 瓦片输出方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TileFormat {
+  /// 单个 MBTiles 文件
+  #[default]
+  Mbtiles,
+  /// `{z}/{x}/{y}` 瓦片目录
+  Directory,
+}
+
+/// 瓦片文件扩展名，仅瓦片目录使用
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TileExtension {
+  #[default]
+  Pbf,
+  Mvt,
+}
+
+/// 切片配置，对应 GDAL MBTiles/MVT 驱动的创建选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TilingProfile {
+  pub min_zoom: u8,
+  pub max_zoom: u8,
+  /// 瓦片中的图层名，缺省沿用文件名
+  pub layer_name: Option<String>,
+  /// 保留的属性字段，缺省保留全部字段
+  pub attributes: Option<Vec<String>>,
+  /// 低于最大级别时的简化容差，单位为瓦片像素
+  pub simplification: Option<f64>,
+  /// 最大级别的简化容差，缺省与 `simplification` 相同
+  pub simplification_max_zoom: Option<f64>,
+  /// 单个瓦片的最大字节数，超出时丢弃部分要素，缺省 500000
+  pub max_size: Option<u32>,
+  /// 单个瓦片的最大要素数，缺省 200000
+  pub max_features: Option<u32>,
+  pub format: TileFormat,
+  pub tile_extension: TileExtension,
+  /// 是否对瓦片进行 gzip 压缩
+  pub compress: bool,
+  /// 元数据中的范围 `[minX, minY, maxX, maxY]`，经纬度，仅 MBTiles 写入，缺省按数据计算
+  pub bounds: Option<[f64; 4]>,
+  pub name: Option<String>,
+  pub description: Option<String>,
+}
+
+impl Default for TilingProfile {
+  fn default() -> Self {
+    TilingProfile {
+      min_zoom: 1,
+      max_zoom: 18,
+      layer_name: None,
+      attributes: None,
+      simplification: None,
+      simplification_max_zoom: None,
+      max_size: None,
+      max_features: None,
+      format: TileFormat::Mbtiles,
+      tile_extension: TileExtension::Pbf,
+      compress: true,
+      bounds: None,
+      name: None,
+      description: None,
+    }
+  }
+}
+
+/// 保存的切片配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedTilingProfile {
+  pub name: String,
+  pub profile: TilingProfile,
+}
+
+impl TilingProfile {
+  pub fn validate(&self) -> Result<(), String> {
+    if self.max_zoom > MAX_ZOOM || self.min_zoom > self.max_zoom {
+      return Err(format!(
+        "级别范围无效: {}-{}，应在 0-{} 之间且最小级别不大于最大级别",
+        self.min_zoom, self.max_zoom, MAX_ZOOM
+      ));
+    }
+    for tolerance in [self.simplification, self.simplification_max_zoom]
+      .into_iter()
+      .flatten()
+    {
+      if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err(format!("简化容差无效: {}", tolerance));
+      }
+    }
+    if self.max_size == Some(0) || self.max_features == Some(0) {
+      return Err("瓦片大小与要素数上限必须大于 0".to_string());
+    }
+    if let Some([min_x, min_y, max_x, max_y]) = self.bounds {
+      let valid = (-180.0..=180.0).contains(&min_x)
+        && (-180.0..=180.0).contains(&max_x)
+        && (-90.0..=90.0).contains(&min_y)
+        && (-90.0..=90.0).contains(&max_y)
+        && min_x < max_x
+        && min_y < max_y;
+      if !valid {
+        return Err("范围无效，应为经纬度 [minX, minY, maxX, maxY]".to_string());
+      }
+    }
+    Ok(())
+  }
+
+  /// 瓦片目录不使用扩展名，MBTiles 为 `.mbtiles`
+  pub fn output_file_name(&self, stem: &str) -> String {
+    match self.format {
+      TileFormat::Mbtiles => format!("{}.mbtiles", stem),
+      TileFormat::Directory => stem.to_string(),
+    }
+  }
+
+  /// 输出使用的 GDAL 驱动
+  pub fn driver_name(&self) -> &'static str {
+    match self.format {
+      TileFormat::Mbtiles => "MBTiles",
+      TileFormat::Directory => "MVT",
+    }
+  }
+
+  pub fn to_translate_options(&self) -> TranslateOptions {
+    let mut options = vec![
+      format!("MINZOOM={}", self.min_zoom),
+      format!("MAXZOOM={}", self.max_zoom),
+      format!("COMPRESS={}", if self.compress { "YES" } else { "NO" }),
+    ];
+    if let Some(simplification) = self.simplification {
+      options.push(format!("SIMPLIFICATION={}", simplification));
+    }
+    if let Some(simplification) = self.simplification_max_zoom {
+      options.push(format!("SIMPLIFICATION_MAX_ZOOM={}", simplification));
+    }
+    if let Some(max_size) = self.max_size {
+      options.push(format!("MAX_SIZE={}", max_size));
+    }
+    if let Some(max_features) = self.max_features {
+      options.push(format!("MAX_FEATURES={}", max_features));
+    }
+    if let Some(name) = &self.name {
+      options.push(format!("NAME={}", name));
+    }
+    if let Some(description) = &self.description {
+      options.push(format!("DESCRIPTION={}", description));
+    }
+    match self.format {
+      TileFormat::Mbtiles => {
+        if let Some([min_x, min_y, max_x, max_y]) = self.bounds {
+          options.push(format!("BOUNDS={},{},{},{}", min_x, min_y, max_x, max_y));
+        }
+      }
+      TileFormat::Directory => {
+        let extension = match self.tile_extension {
+          TileExtension::Pbf => "pbf",
+          TileExtension::Mvt => "mvt",
+        };
+        options.push(format!("TILE_EXTENSION={}", extension));
+      }
+    }
+
+    TranslateOptions {
+      format: self.driver_name().to_string(),
+      target_crs: Some("EPSG:3857".to_string()),
+      layer_name: self.layer_name.clone(),
+      select_fields: self.attributes.clone(),
+      dataset_options: options,
+      overwrite: true,
+      ..TranslateOptions::default()
+    }
+  }
+}
+
+/// 列出保存的切片配置，按名称排序
+pub fn list_profiles() -> Result<Vec<SavedTilingProfile>, String> {
+  let root = utils::files::get_tiling_profile_path();
+  if !root.exists() {
+    return Ok(Vec::new());
+  }
+  let mut profiles: Vec<SavedTilingProfile> = fs::read_dir(&root)
+    .map_err(|e| format!("读取切片配置目录失败: {}", e))?
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+    .filter_map(|entry| fs::read(entry.path()).ok())
+    .filter_map(|content| serde_json::from_slice(&content).ok())
+    .collect();
+  profiles.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(profiles)
+}
+
+pub fn load_profile(name: &str) -> Result<TilingProfile, String> {
+  let content = fs::read(profile_file(name)?).map_err(|_| format!("切片配置不存在: {}", name))?;
+  let saved: SavedTilingProfile =
+    serde_json::from_slice(&content).map_err(|e| format!("切片配置已损坏: {}", e))?;
+  Ok(saved.profile)
+}
+
+/// 同名配置会被覆盖
+pub fn save_profile(name: &str, profile: TilingProfile) -> Result<SavedTilingProfile, String> {
+  profile.validate()?;
+  let path = profile_file(name)?;
+  fs::create_dir_all(utils::files::get_tiling_profile_path())
+    .map_err(|e| format!("创建切片配置目录失败: {}", e))?;
+  let saved = SavedTilingProfile {
+    name: name.trim().to_string(),
+    profile,
+  };
+  let content = serde_json::to_vec_pretty(&saved).map_err(|e| e.to_string())?;
+  // 先写临时文件再重命名，配置文件不会处于写了一半的状态
+  let temp_path = path.with_extension("json.tmp");
+  fs::write(&temp_path, content).map_err(|e| format!("保存切片配置失败: {}", e))?;
+  fs::rename(&temp_path, &path).map_err(|e| format!("保存切片配置失败: {}", e))?;
+  Ok(saved)
+}
+
+pub fn delete_profile(name: &str) -> Result<(), String> {
+  fs::remove_file(profile_file(name)?).map_err(|_| format!("切片配置不存在: {}", name))
+}
+
+/// 配置名直接用作文件名，不允许路径分隔符等字符
+fn profile_file(name: &str) -> Result<PathBuf, String> {
+  let name = name.trim();
+  let valid = !name.is_empty()
+    && !name.starts_with('.')
+    && !name
+      .chars()
+      .any(|c| c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'));
+  if !valid {
+    return Err(format!("切片配置名称无效: {}", name));
+  }
+  Ok(utils::files::get_tiling_profile_path().join(format!("{}.json", name)))
+}
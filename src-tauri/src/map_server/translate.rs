@@ -0,0 +1,322 @@
+use crate::utils;
+use crate::utils::progress::{ProgressPhase, ProgressReporter};
+use gdal::{Dataset, DatasetOptions, DriverManager, GdalOpenFlags, Metadata};
+use serde::Deserialize;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+/// 矢量转换失败的原因
+#[derive(Debug, thiserror::Error)]
+pub enum TranslateError {
+  #[error("当前 GDAL 未包含 {0} 驱动")]
+  DriverMissing(String),
+  #[error("{0} 驱动不支持写入矢量数据")]
+  DriverNotWritable(String),
+  #[error("无法打开矢量数据 {path}: {message}")]
+  Open { path: String, message: String },
+  #[error("转换参数无效: {0}")]
+  InvalidOptions(String),
+  #[error("目标文件已存在: {0}")]
+  OutputExists(String),
+  #[error("目标路径是目录，只会删除瓦片目录下的 MVT 输出: {0}")]
+  OutputNotRemovable(String),
+  #[error("转换失败: {0}")]
+  Translate(String),
+  #[error("任务已取消")]
+  Cancelled,
+}
+
+/// 矢量格式转换参数，对应 ogr2ogr 的常用选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslateOptions {
+  /// 输出驱动名称，如 `MBTiles`、`GPKG`、`GeoJSON`、`FlatGeobuf`
+  pub format: String,
+  /// 目标坐标系，缺省保持原坐标系
+  pub target_crs: Option<String>,
+  /// 源坐标系，对应 `-s_srs`，指定后覆盖源数据自带的坐标系
+  pub source_crs: Option<String>,
+  /// 只转换指定图层，缺省转换全部图层
+  #[serde(default)]
+  pub layers: Vec<String>,
+  /// 输出图层名，缺省沿用源图层名
+  pub layer_name: Option<String>,
+  /// 保留的属性字段，缺省保留全部字段，空列表表示不保留属性
+  pub select_fields: Option<Vec<String>>,
+  /// 数据集创建选项，如 `MINZOOM=0`
+  #[serde(default)]
+  pub dataset_options: Vec<String>,
+  /// 图层创建选项
+  #[serde(default)]
+  pub layer_options: Vec<String>,
+  /// 目标文件已存在时覆盖
+  #[serde(default)]
+  pub overwrite: bool,
+}
+
+/// 确认驱动存在且支持创建矢量数据
+pub fn check_vector_driver(format: &str) -> Result<(), TranslateError> {
+  let driver = DriverManager::get_driver_by_name(format)
+    .map_err(|_| TranslateError::DriverMissing(format.to_string()))?;
+  let capable = |key: &str| {
+    driver
+      .metadata_item(key, "")
+      .is_some_and(|value| value == "YES")
+  };
+  if !capable("DCAP_VECTOR") || !(capable("DCAP_CREATE") || capable("DCAP_CREATECOPY")) {
+    return Err(TranslateError::DriverNotWritable(format.to_string()));
+  }
+  Ok(())
+}
+
+/// 在进程内执行 GDALVectorTranslate
+///
+/// 先输出到目标旁的临时目录，成功后再移动到目标位置，失败或取消时已有的输出保持不变。
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn vector_translate(
+  input_path: &str,
+  output_path: &Path,
+  options: &TranslateOptions,
+  progress: &ProgressReporter,
+) -> Result<(), TranslateError> {
+  check_vector_driver(&options.format)?;
+  if progress.is_cancelled() {
+    return Err(TranslateError::Cancelled);
+  }
+  if output_path.exists() && !options.overwrite {
+    return Err(TranslateError::OutputExists(
+      output_path.display().to_string(),
+    ));
+  }
+  let file_name = output_path
+    .file_name()
+    .ok_or_else(|| TranslateError::InvalidOptions("输出路径缺少文件名".to_string()))?;
+
+  let source = Dataset::open_ex(
+    input_path,
+    DatasetOptions {
+      open_flags: GdalOpenFlags::GDAL_OF_VECTOR | GdalOpenFlags::GDAL_OF_READONLY,
+      ..DatasetOptions::default()
+    },
+  )
+  .map_err(|e| TranslateError::Open {
+    path: input_path.to_string(),
+    message: e.to_string(),
+  })?;
+
+  // MBTiles 与 MVT 在写入时切片
+  let phase = if ["MBTiles", "MVT"]
+    .iter()
+    .any(|name| name.eq_ignore_ascii_case(&options.format))
+  {
+    ProgressPhase::Tiling
+  } else {
+    ProgressPhase::Converting
+  };
+  let temp_dir = output_path.with_file_name(format!(".{}.part", file_name.to_string_lossy()));
+  let _ = fs::remove_dir_all(&temp_dir);
+  fs::create_dir_all(&temp_dir).map_err(|e| TranslateError::Translate(e.to_string()))?;
+  progress.start_phase(phase, Some(100));
+  let result = run_translate(&source, &temp_dir.join(file_name), options, progress)
+    .and_then(|_| persist_output(&temp_dir, output_path, options));
+  let _ = fs::remove_dir_all(&temp_dir);
+  result?;
+  progress.finish();
+  Ok(())
+}
+
+/// 输出路径必须位于工作空间内，避免覆盖或删除工作空间以外的用户文件
+pub fn workspace_output_path(output_path: &str) -> Result<PathBuf, TranslateError> {
+  let invalid =
+    || TranslateError::InvalidOptions(format!("输出路径必须位于工作空间内: {}", output_path));
+  let path = Path::new(output_path);
+  let file_name = path.file_name().ok_or_else(invalid)?;
+  let parent = path
+    .parent()
+    .filter(|parent| !parent.as_os_str().is_empty())
+    .and_then(|parent| fs::canonicalize(parent).ok())
+    .ok_or_else(invalid)?;
+  let workspace = fs::canonicalize(utils::files::get_workspace_path()).map_err(|_| invalid())?;
+  if !parent.starts_with(&workspace) {
+    return Err(invalid());
+  }
+  Ok(parent.join(file_name))
+}
+
+/// 将临时目录中的输出移动到目标位置，Shapefile 等驱动会生成多个文件
+fn persist_output(
+  temp_dir: &Path,
+  output_path: &Path,
+  options: &TranslateOptions,
+) -> Result<(), TranslateError> {
+  let io_error = |e: std::io::Error| TranslateError::Translate(e.to_string());
+  let parent = output_path.parent().unwrap_or(Path::new(""));
+  for entry in fs::read_dir(temp_dir).map_err(io_error)? {
+    let entry = entry.map_err(io_error)?;
+    let target = parent.join(entry.file_name());
+    if target.exists() {
+      if !options.overwrite {
+        return Err(TranslateError::OutputExists(target.display().to_string()));
+      }
+      remove_output(&target, options)?;
+    }
+    fs::rename(entry.path(), &target).map_err(io_error)?;
+  }
+  Ok(())
+}
+
+fn run_translate(
+  source: &Dataset,
+  output_path: &Path,
+  options: &TranslateOptions,
+  progress: &ProgressReporter,
+) -> Result<(), TranslateError> {
+  let output = output_path
+    .to_str()
+    .and_then(|path| CString::new(path).ok())
+    .ok_or_else(|| TranslateError::InvalidOptions("无法转换输出路径".to_string()))?;
+  let args = translate_args(options)
+    .into_iter()
+    .map(CString::new)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| TranslateError::InvalidOptions(e.to_string()))?;
+  let mut argv: Vec<*mut c_char> = args.iter().map(|arg| arg.as_ptr() as *mut c_char).collect();
+  argv.push(ptr::null_mut());
+
+  // SAFETY: `argv` 以空指针结尾，其中的指针指向 `args` 中的 CString，二者在整个块内有效；
+  // GDAL 只读取参数并复制到选项对象中
+  unsafe {
+    let translate_options =
+      gdal_sys::GDALVectorTranslateOptionsNew(argv.as_mut_ptr(), ptr::null_mut());
+    if translate_options.is_null() {
+      return Err(TranslateError::InvalidOptions(last_error_message()));
+    }
+    // SAFETY: 回调只在下面同步执行的 GDALVectorTranslate 中调用，`progress` 的借用比这次调用更长；
+    // 回调只通过共享引用读取，`ProgressReporter` 的方法可在任意线程调用
+    gdal_sys::GDALVectorTranslateOptionsSetProgress(
+      translate_options,
+      Some(report_progress),
+      progress as *const ProgressReporter as *mut c_void,
+    );
+
+    // SAFETY: `source` 在本函数内一直被借用，句柄在调用期间有效，GDAL 不会关闭输入数据集；
+    // `output` 在块内有效，`translate_options` 只在调用后释放一次，之后不再使用
+    let mut source_handle = source.c_dataset();
+    let mut usage_error: c_int = 0;
+    gdal_sys::CPLErrorReset();
+    let dataset = gdal_sys::GDALVectorTranslate(
+      output.as_ptr(),
+      ptr::null_mut(),
+      1,
+      &mut source_handle,
+      translate_options,
+      &mut usage_error,
+    );
+    gdal_sys::GDALVectorTranslateOptionsFree(translate_options);
+
+    if progress.is_cancelled() {
+      if !dataset.is_null() {
+        gdal_sys::GDALClose(dataset);
+      }
+      return Err(TranslateError::Cancelled);
+    }
+    if dataset.is_null() {
+      let message = last_error_message();
+      return Err(if usage_error != 0 {
+        TranslateError::InvalidOptions(message)
+      } else {
+        TranslateError::Translate(message)
+      });
+    }
+    // 关闭时才会写完缓存的瓦片和元数据
+    // SAFETY: 输出数据集由 GDALVectorTranslate 创建并归本函数所有，只关闭一次
+    gdal_sys::GDALClose(dataset);
+  }
+
+  // SAFETY: 只读取当前线程的错误状态，没有参数
+  if unsafe { gdal_sys::CPLGetLastErrorType() } >= gdal_sys::CPLErr::CE_Failure {
+    return Err(TranslateError::Translate(last_error_message()));
+  }
+  Ok(())
+}
+
+/// 拼装 ogr2ogr 风格的参数
+fn translate_args(options: &TranslateOptions) -> Vec<String> {
+  let mut args = vec!["-f".to_string(), options.format.clone()];
+  if let Some(target_crs) = &options.target_crs {
+    args.extend(["-t_srs".to_string(), target_crs.clone()]);
+  }
+  if let Some(source_crs) = &options.source_crs {
+    args.extend(["-s_srs".to_string(), source_crs.clone()]);
+  }
+  if let Some(layer_name) = &options.layer_name {
+    args.extend(["-nln".to_string(), layer_name.clone()]);
+  }
+  if let Some(fields) = &options.select_fields {
+    args.extend(["-select".to_string(), fields.join(",")]);
+  }
+  for option in &options.dataset_options {
+    args.extend(["-dsco".to_string(), option.clone()]);
+  }
+  for option in &options.layer_options {
+    args.extend(["-lco".to_string(), option.clone()]);
+  }
+  // 图层名必须放在最后
+  args.extend(options.layers.iter().cloned());
+  args
+}
+
+/// MVT 驱动输出的是瓦片目录，只删除瓦片目录下的 MVT 输出，其他目录一律拒绝，避免误删用户数据
+fn remove_output(output_path: &Path, options: &TranslateOptions) -> Result<(), TranslateError> {
+  let removed = if output_path.is_dir() {
+    let tile_root = fs::canonicalize(utils::files::get_mbtiles_path()).ok();
+    let inside_tile_root = tile_root
+      .zip(fs::canonicalize(output_path).ok())
+      .is_some_and(|(root, path)| path != root && path.starts_with(&root));
+    if !options.format.eq_ignore_ascii_case("MVT") || !inside_tile_root {
+      return Err(TranslateError::OutputNotRemovable(
+        output_path.display().to_string(),
+      ));
+    }
+    fs::remove_dir_all(output_path)
+  } else {
+    fs::remove_file(output_path)
+  };
+  removed.map_err(|e| TranslateError::Translate(e.to_string()))
+}
+
+/// GDAL 进度回调，返回 0 时 GDAL 中止转换
+///
+/// # Safety
+///
+/// `data` 必须指向有效的 `ProgressReporter`，且在回调期间不被释放
+unsafe extern "C" fn report_progress(
+  complete: f64,
+  _message: *const c_char,
+  data: *mut c_void,
+) -> c_int {
+  // SAFETY: `data` 由 `run_translate` 设置为 `progress` 的地址，GDALVectorTranslate 返回前一直有效
+  let progress = &*(data as *const ProgressReporter);
+  if progress.is_cancelled() {
+    return 0;
+  }
+  progress.set_processed(((complete * 100.0) as u64).min(100));
+  1
+}
+
+fn last_error_message() -> String {
+  // SAFETY: CPLGetLastErrorMsg 总是返回以 0 结尾的字符串（没有错误时为空串），由 GDAL 按线程持有，
+  // 在下一次 GDAL 调用前有效，这里立即复制
+  let message = unsafe { CStr::from_ptr(gdal_sys::CPLGetLastErrorMsg()) }
+    .to_string_lossy()
+    .trim()
+    .to_string();
+  if message.is_empty() {
+    "GDAL 未返回错误信息".to_string()
+  } else {
+    message
+  }
+}
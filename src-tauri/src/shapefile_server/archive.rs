@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::shapefile_to_geojson::CustomError;
+use crate::utils;
+
+// 组成一个可读数据集的必需文件
+const REQUIRED_EXTENSIONS: [&str; 3] = ["shp", "shx", "dbf"];
+
+impl From<zip::result::ZipError> for CustomError {
+  fn from(err: zip::result::ZipError) -> Self {
+    CustomError(format!("读取压缩包失败: {}", err))
+  }
+}
+
+/// 压缩包内的一个 shapefile 数据集
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipDataset {
+  /// `.shp` 在压缩包内的路径，作为 `innerPath` 传入
+  pub inner_path: String,
+  pub name: String,
+  /// 同名的其他文件扩展名（小写）
+  pub members: Vec<String>,
+  /// 缺少的必需文件
+  pub missing: Vec<String>,
+}
+
+/// 命令的输入文件，压缩包会解压到工作空间临时目录，离开作用域时删除
+pub enum ShapefileInput {
+  Plain(PathBuf),
+  Extracted { dir: PathBuf, shp_path: PathBuf },
+}
+
+impl ShapefileInput {
+  /// `.zip` 按 `inner_path` 选择数据集，压缩包内只有一个数据集时可省略；其他路径原样使用
+  pub fn open(path: &str, inner_path: Option<&str>) -> Result<Self, CustomError> {
+    let path = Path::new(path);
+    if !is_zip(path) {
+      return Ok(ShapefileInput::Plain(path.to_path_buf()));
+    }
+
+    let datasets = list_zip_datasets(path)?;
+    let dataset = match inner_path {
+      Some(inner_path) => datasets
+        .iter()
+        .find(|dataset| same_dataset(&dataset.inner_path, inner_path))
+        .ok_or_else(|| CustomError(format!("压缩包中不存在数据集: {}", inner_path)))?,
+      None => match datasets.as_slice() {
+        [dataset] => dataset,
+        [] => return Err(CustomError("压缩包中没有 shapefile 数据集".to_string())),
+        _ => {
+          let names: Vec<&str> = datasets
+            .iter()
+            .map(|dataset| dataset.inner_path.as_str())
+            .collect();
+          return Err(CustomError(format!(
+            "压缩包包含多个数据集，请指定 innerPath: {}",
+            names.join(", ")
+          )));
+        }
+      },
+    };
+    if !dataset.missing.is_empty() {
+      return Err(CustomError(format!(
+        "数据集 {} 缺少文件: {}",
+        dataset.inner_path,
+        dataset.missing.join(", ")
+      )));
+    }
+
+    let dir = utils::files::get_temp_path().join(utils::progress::new_job_id());
+    fs::create_dir_all(&dir)?;
+    // 先构造输入，解压失败时同样会删除临时目录
+    let input = ShapefileInput::Extracted {
+      shp_path: dir.join(format!("{}.shp", dataset.name)),
+      dir: dir.clone(),
+    };
+    extract_dataset(path, dataset, &dir)?;
+    Ok(input)
+  }
+
+  /// 实际读取的 `.shp` 路径
+  pub fn path(&self) -> &Path {
+    match self {
+      ShapefileInput::Plain(path) => path,
+      ShapefileInput::Extracted { shp_path, .. } => shp_path,
+    }
+  }
+
+  pub fn path_str(&self) -> Result<&str, CustomError> {
+    self
+      .path()
+      .to_str()
+      .ok_or_else(|| CustomError("无法转换输入路径".to_string()))
+  }
+
+  fn dir(&self) -> Option<&Path> {
+    match self {
+      ShapefileInput::Plain(_) => None,
+      ShapefileInput::Extracted { dir, .. } => Some(dir),
+    }
+  }
+}
+
+impl Drop for ShapefileInput {
+  fn drop(&mut self) {
+    if let Some(dir) = self.dir() {
+      if let Err(e) = fs::remove_dir_all(dir) {
+        log::warn!("清理临时目录失败 {}: {}", dir.display(), e);
+      }
+    }
+  }
+}
+
+pub fn is_zip(path: &Path) -> bool {
+  path
+    .extension()
+    .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+/// 按 `.shp` 分组列出压缩包中的数据集，只读取目录不解压
+pub fn list_zip_datasets(zip_path: &Path) -> Result<Vec<ZipDataset>, CustomError> {
+  let archive = zip::ZipArchive::new(File::open(zip_path)?)?;
+  // 键为去掉扩展名的小写路径，值为（原始路径前缀，扩展名列表）
+  let mut groups: BTreeMap<String, (String, Vec<String>)> = BTreeMap::new();
+  for name in archive.file_names() {
+    if name.ends_with('/') {
+      continue;
+    }
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+      continue;
+    };
+    // 扩展名中不应包含路径分隔符
+    if extension.contains('/') {
+      continue;
+    }
+    let (_, members) = groups
+      .entry(stem.to_lowercase())
+      .or_insert_with(|| (stem.to_string(), Vec::new()));
+    members.push(extension.to_lowercase());
+  }
+
+  Ok(
+    groups
+      .into_values()
+      .filter(|(_, members)| members.iter().any(|extension| extension == "shp"))
+      .map(|(stem, mut members)| {
+        members.sort();
+        let missing = REQUIRED_EXTENSIONS
+          .iter()
+          .filter(|required| !members.iter().any(|extension| extension == *required))
+          .map(|required| required.to_string())
+          .collect();
+        let shp_name = archive
+          .file_names()
+          .find(|name| same_dataset(name, &format!("{}.shp", stem)))
+          .map(str::to_string)
+          .unwrap_or_else(|| format!("{}.shp", stem));
+        ZipDataset {
+          name: stem.rsplit(['/', '\\']).next().unwrap_or(&stem).to_string(),
+          inner_path: shp_name,
+          members,
+          missing,
+        }
+      })
+      .collect(),
+  )
+}
+
+fn same_dataset(a: &str, b: &str) -> bool {
+  a.trim_start_matches('/')
+    .eq_ignore_ascii_case(b.trim_start_matches('/'))
+}
+
+/// 将数据集的所有同名文件解压到 `dir`，文件名统一为 `数据集名.小写扩展名`
+fn extract_dataset(zip_path: &Path, dataset: &ZipDataset, dir: &Path) -> Result<(), CustomError> {
+  let mut archive = zip::ZipArchive::new(File::open(zip_path)?)?;
+  let stem = &dataset.inner_path[..dataset.inner_path.len() - ".shp".len()];
+  let names: Vec<String> = archive
+    .file_names()
+    .filter(|name| {
+      name
+        .rsplit_once('.')
+        .is_some_and(|(name_stem, _)| name_stem.eq_ignore_ascii_case(stem))
+    })
+    .map(str::to_string)
+    .collect();
+  for name in names {
+    let extension = name
+      .rsplit_once('.')
+      .map(|(_, extension)| extension.to_lowercase())
+      .unwrap_or_default();
+    let mut member = archive.by_name(&name)?;
+    let mut output = File::create(dir.join(format!("{}.{}", dataset.name, extension)))?;
+    io::copy(&mut member, &mut output)?;
+  }
+  Ok(())
+}
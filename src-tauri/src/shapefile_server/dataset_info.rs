@@ -0,0 +1,152 @@
+use gdal::raster::RasterBand;
+use gdal::{Dataset, DatasetOptions, GdalOpenFlags, Metadata};
+use serde::Serialize;
+
+use super::ogr::{layer_info, wgs84_extent, LayerInfo};
+use super::projection::{crs_from_definition, SourceCrs};
+use super::shapefile_to_geojson::CustomError;
+
+/// 数据集概要，相当于 `ogrinfo -so` 与 `gdalinfo` 的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetInfo {
+  pub path: String,
+  pub driver: String,
+  pub driver_long_name: String,
+  /// 矢量图层，栅格数据为空
+  pub layers: Vec<LayerInfo>,
+  /// 栅格信息，矢量数据为空
+  pub raster: Option<RasterInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RasterInfo {
+  pub width: usize,
+  pub height: usize,
+  /// 没有坐标系或坐标系无法识别时为空
+  pub crs: Option<SourceCrs>,
+  /// 仿射变换参数，与 gdal 的 GeoTransform 顺序一致
+  pub geo_transform: Option<[f64; 6]>,
+  /// `[minX, minY, maxX, maxY]`，没有仿射变换时为空
+  pub extent: Option<[f64; 4]>,
+  pub extent_wgs84: Option<[f64; 4]>,
+  pub bands: Vec<BandInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandInfo {
+  /// 从 1 开始
+  pub index: usize,
+  pub data_type: String,
+  pub color_interpretation: String,
+  pub description: String,
+  pub no_data: Option<f64>,
+  /// 各级金字塔的 `[宽, 高]`
+  pub overviews: Vec<[usize; 2]>,
+}
+
+/// 读取任意 gdal 支持的矢量或栅格数据的概要
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn dataset_info(path: &str) -> Result<DatasetInfo, CustomError> {
+  let dataset = Dataset::open_ex(
+    path,
+    DatasetOptions {
+      open_flags: GdalOpenFlags::GDAL_OF_VECTOR
+        | GdalOpenFlags::GDAL_OF_RASTER
+        | GdalOpenFlags::GDAL_OF_READONLY,
+      ..DatasetOptions::default()
+    },
+  )
+  .map_err(|e| CustomError(format!("无法识别的数据 {}: {}", path, e)))?;
+  let driver = dataset.driver();
+  // 单个图层读取失败时对应项为空，不影响整个数据集
+  let layers = dataset.layers().map(|layer| layer_info(&layer)).collect();
+  let raster = if dataset.raster_count() > 0 {
+    Some(raster_info(&dataset)?)
+  } else {
+    None
+  };
+
+  Ok(DatasetInfo {
+    path: path.to_string(),
+    driver: driver.short_name(),
+    driver_long_name: driver.long_name(),
+    layers,
+    raster,
+  })
+}
+
+fn raster_info(dataset: &Dataset) -> Result<RasterInfo, CustomError> {
+  let (width, height) = dataset.raster_size();
+  let crs = dataset
+    .spatial_ref()
+    .ok()
+    .and_then(|spatial_ref| spatial_ref.to_wkt().ok())
+    .and_then(|wkt| {
+      crs_from_definition(&wkt)
+        .inspect_err(|e| log::warn!("无法识别栅格的坐标系: {}", e))
+        .ok()
+    });
+  let geo_transform = dataset.geo_transform().ok();
+  let extent = geo_transform.map(|transform| raster_extent(&transform, width, height));
+  let extent_wgs84 = match (&crs, extent) {
+    (Some(crs), Some(extent)) => wgs84_extent(crs, extent),
+    _ => None,
+  };
+  let bands = (1..=dataset.raster_count())
+    .map(|index| {
+      let band = dataset.rasterband(index)?;
+      band_info(&band, index)
+    })
+    .collect::<Result<_, CustomError>>()?;
+
+  Ok(RasterInfo {
+    width,
+    height,
+    crs,
+    geo_transform,
+    extent,
+    extent_wgs84,
+    bands,
+  })
+}
+
+fn band_info(band: &RasterBand, index: usize) -> Result<BandInfo, CustomError> {
+  let overviews = (0..band.overview_count()?)
+    .map(|level| {
+      let (width, height) = band.overview(level as usize)?.size();
+      Ok([width, height])
+    })
+    .collect::<Result<_, CustomError>>()?;
+  Ok(BandInfo {
+    index,
+    data_type: band.band_type().name(),
+    color_interpretation: band.color_interpretation().name(),
+    description: band.description().unwrap_or_default(),
+    no_data: band.no_data_value(),
+    overviews,
+  })
+}
+
+/// 按四个角点计算范围，兼容带旋转的仿射变换
+fn raster_extent(transform: &[f64; 6], width: usize, height: usize) -> [f64; 4] {
+  let corners = [
+    (0.0, 0.0),
+    (width as f64, 0.0),
+    (0.0, height as f64),
+    (width as f64, height as f64),
+  ];
+  let mut extent = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+  for (column, row) in corners {
+    let x = transform[0] + column * transform[1] + row * transform[2];
+    let y = transform[3] + column * transform[4] + row * transform[5];
+    extent[0] = extent[0].min(x);
+    extent[1] = extent[1].min(y);
+    extent[2] = extent[2].max(x);
+    extent[3] = extent[3].max(y);
+  }
+  extent
+}
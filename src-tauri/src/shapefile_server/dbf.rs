@@ -0,0 +1,130 @@
+use serde_json::json;
+use shapefile::dbase::{self, FieldValue};
+use std::collections::HashSet;
+
+/// 属性表行中由程序生成的列，DBF 中的同名字段输出时改名
+pub const ROW_RESERVED_COLUMNS: [&str; 5] = ["fid", "m", "wkt", "wkb", "ewkb"];
+/// GeoJSON 要素中由 M 值生成的属性，输出 M 值时 DBF 中的同名字段改名
+pub const FEATURE_RESERVED_PROPERTIES: [&str; 1] = ["m"];
+
+/// 将 dBase 字段值转换为 JSON，空值统一为 null
+///
+/// 数值类型输出为数字，逻辑型输出为布尔值，日期输出为 ISO-8601 字符串
+pub fn field_value_to_json(value: FieldValue) -> serde_json::Value {
+  match value {
+    FieldValue::Character(value) => json!(value),
+    FieldValue::Memo(value) => json!(value),
+    FieldValue::Numeric(value) => finite_number(value),
+    FieldValue::Float(value) => finite_number(value.map(f64::from)),
+    FieldValue::Double(value) | FieldValue::Currency(value) => finite_number(Some(value)),
+    FieldValue::Integer(value) => json!(value),
+    FieldValue::Logical(value) => json!(value),
+    FieldValue::Date(value) => json!(value.map(|date| format_date(&date))),
+    FieldValue::DateTime(value) => json!(format_date_time(&value)),
+  }
+}
+
+/// 将一条 dBase 记录写入 JSON 对象，返回文本是否出现解码替换字符
+pub fn insert_record(
+  properties: &mut serde_json::Map<String, serde_json::Value>,
+  record: dbase::Record,
+) -> bool {
+  let mut lossy = false;
+  for (field, value) in record.into_iter() {
+    if let FieldValue::Character(Some(text)) | FieldValue::Memo(text) = &value {
+      lossy |= text.contains(char::REPLACEMENT_CHARACTER);
+    }
+    properties.insert(field, field_value_to_json(value));
+  }
+  lossy
+}
+
+/// 与 `insert_record` 相同，但与保留列重名的字段按 `renamed_row_fields` 改名
+pub fn insert_row_record(
+  properties: &mut serde_json::Map<String, serde_json::Value>,
+  record: dbase::Record,
+) -> bool {
+  insert_renamed_record(properties, record, &ROW_RESERVED_COLUMNS)
+}
+
+/// 与 `insert_record` 相同，但与 `reserved` 重名的字段按 `renamed_fields` 改名
+pub fn insert_renamed_record(
+  properties: &mut serde_json::Map<String, serde_json::Value>,
+  mut record: dbase::Record,
+  reserved: &[&str],
+) -> bool {
+  let renamed = renamed_fields(record.as_ref().keys().map(String::as_str), reserved);
+  for (name, new_name) in renamed {
+    if let Some(value) = record.remove(&name) {
+      record.insert(new_name, value);
+    }
+  }
+  insert_record(properties, record)
+}
+
+/// 与保留列重名的字段依次改名为 `fid_1`、`fid_2`…，跳过已有的字段名
+///
+/// 返回 `(原字段名, 输出列名)`，按原字段名排序
+pub fn renamed_row_fields<'a>(
+  field_names: impl IntoIterator<Item = &'a str>,
+) -> Vec<(String, String)> {
+  renamed_fields(field_names, &ROW_RESERVED_COLUMNS)
+}
+
+/// 与 `reserved` 重名的字段改名，规则同 `renamed_row_fields`
+pub fn renamed_fields<'a>(
+  field_names: impl IntoIterator<Item = &'a str>,
+  reserved: &[&str],
+) -> Vec<(String, String)> {
+  let mut field_names: Vec<&str> = field_names.into_iter().collect();
+  field_names.sort_unstable();
+  let mut taken: HashSet<String> = field_names
+    .iter()
+    .chain(reserved)
+    .map(|name| name.to_string())
+    .collect();
+  let mut renamed = Vec::new();
+  for name in field_names {
+    if !reserved.contains(&name) {
+      continue;
+    }
+    let new_name = (1..)
+      .map(|suffix| format!("{}_{}", name, suffix))
+      .find(|candidate| !taken.contains(candidate))
+      .unwrap_or_default();
+    taken.insert(new_name.clone());
+    renamed.push((name.to_string(), new_name));
+  }
+  renamed
+}
+
+/// 字段改名的警告信息
+pub fn renamed_field_warnings(renamed: &[(String, String)]) -> Vec<String> {
+  renamed
+    .iter()
+    .map(|(name, new_name)| format!("字段 {} 与生成的列重名，已输出为 {}", name, new_name))
+    .collect()
+}
+
+// NaN 与无穷大无法用 JSON 表示，按空值处理
+fn finite_number(value: Option<f64>) -> serde_json::Value {
+  match value {
+    Some(number) if number.is_finite() => json!(number),
+    _ => serde_json::Value::Null,
+  }
+}
+
+fn format_date(date: &dbase::Date) -> String {
+  format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+fn format_date_time(date_time: &dbase::DateTime) -> String {
+  let time = date_time.time();
+  format!(
+    "{}T{:02}:{:02}:{:02}",
+    format_date(&date_time.date()),
+    time.hours(),
+    time.minutes(),
+    time.seconds()
+  )
+}
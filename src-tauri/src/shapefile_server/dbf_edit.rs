@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::encoding::detect_encoding;
+use super::schema::{field_offsets, read_dbf_header};
+use super::shapefile_to_geojson::CustomError;
+use super::writer::{dbf_update_date, encode_field_value};
+use crate::utils;
+
+// 备份目录中的文件，会话信息在备份完成后写入，存在即表示备份可用
+const BACKUP_FILE: &str = "original.dbf";
+const SESSION_FILE: &str = "session.json";
+
+// 同一时间只执行一个写操作，避免并发写入同一文件
+static EDIT_LOCK: Mutex<()> = Mutex::new(());
+
+/// 属性编辑会话，备份与会话信息保存在工作空间中，程序异常退出后仍可提交或回滚
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditSession {
+  pub session_id: String,
+  pub dbf_path: PathBuf,
+  /// 写入文本使用的编码名称
+  pub encoding: String,
+  pub created_at: u64,
+  /// 已修改的记录数（按次累计）
+  pub edited_count: u64,
+}
+
+/// 一条记录的修改，`values` 的键为字段名
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordEdit {
+  pub index: u32,
+  pub values: serde_json::Map<String, serde_json::Value>,
+}
+
+/// 备份 DBF 并开始编辑，同一数据集只能有一个未结束的会话
+pub fn begin_edit(
+  shapefile_path: &str,
+  encoding: Option<&str>,
+) -> Result<EditSession, CustomError> {
+  let _guard = lock()?;
+  let dbf_path = fs::canonicalize(Path::new(shapefile_path).with_extension("dbf"))?;
+  if let Some(session) = list_sessions()?
+    .into_iter()
+    .find(|session| session.dbf_path == dbf_path)
+  {
+    return Err(CustomError(format!(
+      "该数据集已有未结束的编辑会话: {}",
+      session.session_id
+    )));
+  }
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  // 先确认文件头可读，避免为无效文件建立会话
+  read_dbf_header(&dbf_path, &encoding)?;
+
+  let session = EditSession {
+    session_id: utils::progress::new_job_id(),
+    dbf_path,
+    encoding: encoding.encoding.name().to_string(),
+    created_at: SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_millis() as u64)
+      .unwrap_or_default(),
+    edited_count: 0,
+  };
+  let dir = session_dir(&session.session_id)?;
+  fs::create_dir_all(&dir)?;
+  let result =
+    copy_synced(&session.dbf_path, &dir.join(BACKUP_FILE)).and_then(|_| save_session(&session));
+  if let Err(e) = result {
+    let _ = fs::remove_dir_all(&dir);
+    return Err(CustomError(format!("备份 DBF 失败: {}", e)));
+  }
+  Ok(session)
+}
+
+/// 修改记录的字段值，全部校验通过后才写入文件
+pub fn update_records(session_id: &str, edits: &[RecordEdit]) -> Result<EditSession, CustomError> {
+  let _guard = lock()?;
+  let mut session = load_session(session_id)?;
+  let encoding = detect_encoding(&session.dbf_path, Some(&session.encoding))?;
+  let header = read_dbf_header(&session.dbf_path, &encoding)?;
+  let offsets = field_offsets(&header.fields);
+
+  let mut writes = Vec::new();
+  for edit in edits {
+    if edit.index >= header.record_count {
+      return Err(CustomError(format!(
+        "记录序号 {} 超出范围（共 {} 条）",
+        edit.index, header.record_count
+      )));
+    }
+    let record_offset =
+      u64::from(header.header_length) + u64::from(edit.index) * u64::from(header.record_length);
+    for (name, value) in &edit.values {
+      let (field, offset) = header
+        .fields
+        .iter()
+        .zip(&offsets)
+        .find(|(field, _)| field.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| CustomError(format!("字段不存在: {}", name)))?;
+      let bytes = encode_field_value(field, value, encoding.encoding)
+        .map_err(|e| CustomError(format!("第 {} 条记录: {}", edit.index, e)))?;
+      writes.push((record_offset + *offset as u64, bytes));
+    }
+  }
+
+  let mut file = OpenOptions::new().write(true).open(&session.dbf_path)?;
+  for (offset, bytes) in writes {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&bytes)?;
+  }
+  file.seek(SeekFrom::Start(1))?;
+  file.write_all(&dbf_update_date())?;
+  file.sync_data()?;
+
+  session.edited_count += edits.len() as u64;
+  save_session(&session)?;
+  Ok(session)
+}
+
+/// 保留修改并删除备份
+pub fn commit_edit(session_id: &str) -> Result<EditSession, CustomError> {
+  let _guard = lock()?;
+  let session = load_session(session_id)?;
+  fs::remove_dir_all(session_dir(session_id)?)?;
+  Ok(session)
+}
+
+/// 用备份恢复 DBF 并删除备份，先写入同目录的临时文件再替换，替换前原文件不受影响
+pub fn rollback_edit(session_id: &str) -> Result<EditSession, CustomError> {
+  let _guard = lock()?;
+  let session = load_session(session_id)?;
+  let temp_path = session.dbf_path.with_extension("dbf.rollback");
+  copy_synced(&session_dir(session_id)?.join(BACKUP_FILE), &temp_path)?;
+  fs::rename(&temp_path, &session.dbf_path)?;
+  fs::remove_dir_all(session_dir(session_id)?)?;
+  Ok(session)
+}
+
+/// 列出未结束的会话，包括上次运行中断的会话
+pub fn list_sessions() -> Result<Vec<EditSession>, CustomError> {
+  let root = utils::files::get_dbf_backup_path();
+  if !root.exists() {
+    return Ok(Vec::new());
+  }
+  let mut sessions: Vec<EditSession> = fs::read_dir(root)?
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| fs::read(entry.path().join(SESSION_FILE)).ok())
+    .filter_map(|content| serde_json::from_slice(&content).ok())
+    .collect();
+  sessions.sort_by_key(|session| session.created_at);
+  Ok(sessions)
+}
+
+pub(super) fn lock() -> Result<std::sync::MutexGuard<'static, ()>, CustomError> {
+  EDIT_LOCK
+    .lock()
+    .map_err(|_| CustomError("编辑锁已失效".to_string()))
+}
+
+/// 会话 id 由 `new_job_id` 生成，其它格式一律拒绝，避免删除备份目录以外的路径
+fn session_dir(session_id: &str) -> Result<PathBuf, CustomError> {
+  let valid = session_id
+    .strip_prefix("job-")
+    .and_then(|rest| rest.split_once('-'))
+    .is_some_and(|(millis, counter)| {
+      [millis, counter]
+        .iter()
+        .all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()))
+    });
+  if !valid {
+    return Err(CustomError(format!("无效的编辑会话: {}", session_id)));
+  }
+  Ok(utils::files::get_dbf_backup_path().join(session_id))
+}
+
+fn load_session(session_id: &str) -> Result<EditSession, CustomError> {
+  let content = fs::read(session_dir(session_id)?.join(SESSION_FILE))
+    .map_err(|_| CustomError(format!("编辑会话不存在: {}", session_id)))?;
+  Ok(serde_json::from_slice(&content)?)
+}
+
+/// 先写临时文件再重命名，会话文件不会处于写了一半的状态
+fn save_session(session: &EditSession) -> Result<(), CustomError> {
+  let path = session_dir(&session.session_id)?.join(SESSION_FILE);
+  let temp_path = path.with_extension("json.tmp");
+  fs::write(&temp_path, serde_json::to_vec_pretty(session)?)?;
+  fs::rename(&temp_path, &path)?;
+  Ok(())
+}
+
+fn copy_synced(from: &Path, to: &Path) -> Result<(), CustomError> {
+  fs::copy(from, to)?;
+  OpenOptions::new().write(true).open(to)?.sync_all()?;
+  Ok(())
+}
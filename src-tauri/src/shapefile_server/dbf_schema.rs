@@ -0,0 +1,346 @@
+use encoding_rs::Encoding;
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use super::dbf_edit;
+use super::encoding::{detect_encoding, DetectedEncoding};
+use super::schema::{field_offsets, field_type_name, read_dbf_header, DbfField, DbfHeader};
+use super::shapefile_to_geojson::CustomError;
+use super::writer::{copy_dbf_header_flags, encode_field_value, DbfWriter};
+
+// 字段名的字节数上限，字符字段与数值字段的宽度上限
+const FIELD_NAME_LENGTH: usize = 10;
+const CHARACTER_LENGTH: u8 = 254;
+const NUMERIC_LENGTH: u8 = 24;
+
+/// 字段结构的修改，按顺序依次应用
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum FieldChange {
+  /// 在末尾添加字段，已有记录填入 `value`，缺省为空值
+  #[serde(rename_all = "camelCase")]
+  Add {
+    name: String,
+    field_type: char,
+    length: u8,
+    #[serde(default)]
+    decimal_count: u8,
+    #[serde(default)]
+    value: serde_json::Value,
+  },
+  #[serde(rename_all = "camelCase")]
+  Drop { name: String },
+  #[serde(rename_all = "camelCase")]
+  Rename { name: String, new_name: String },
+  /// 修改类型、宽度或小数位，缺省项沿用原定义
+  #[serde(rename_all = "camelCase")]
+  Alter {
+    name: String,
+    field_type: Option<char>,
+    length: Option<u8>,
+    decimal_count: Option<u8>,
+  },
+}
+
+/// 新字段及其数据来源，`source` 为原字段序号
+struct PlannedField {
+  field: DbfField,
+  source: Option<usize>,
+  default: Vec<u8>,
+}
+
+/// 按修改后的字段列表重写 DBF，校验通过后替换原文件
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn change_fields(
+  shapefile_path: &str,
+  changes: &[FieldChange],
+  encoding: Option<&str>,
+) -> Result<DbfHeader, CustomError> {
+  let _guard = dbf_edit::lock()?;
+  let dbf_path = fs::canonicalize(Path::new(shapefile_path).with_extension("dbf"))?;
+  if let Some(session) = dbf_edit::list_sessions()?
+    .into_iter()
+    .find(|session| session.dbf_path == dbf_path)
+  {
+    return Err(CustomError(format!(
+      "该数据集有未结束的编辑会话，请先提交或回滚: {}",
+      session.session_id
+    )));
+  }
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let header = read_dbf_header(&dbf_path, &encoding)?;
+  let planned = plan_fields(&header.fields, changes, encoding.encoding)?;
+  if planned.is_empty() {
+    return Err(CustomError("至少需要保留一个字段".to_string()));
+  }
+
+  let temp_path = dbf_path.with_extension("dbf.schema");
+  let result = rewrite(&dbf_path, &temp_path, &header, &planned, encoding.encoding)
+    .and_then(|_| verify(&temp_path, &header, &planned, &encoding));
+  match result {
+    Ok(new_header) => {
+      fs::rename(&temp_path, &dbf_path)?;
+      Ok(new_header)
+    }
+    Err(e) => {
+      let _ = fs::remove_file(&temp_path);
+      Err(e)
+    }
+  }
+}
+
+fn plan_fields(
+  fields: &[DbfField],
+  changes: &[FieldChange],
+  encoding: &'static Encoding,
+) -> Result<Vec<PlannedField>, CustomError> {
+  let mut planned: Vec<PlannedField> = fields
+    .iter()
+    .enumerate()
+    .map(|(index, field)| PlannedField {
+      field: field.clone(),
+      source: Some(index),
+      default: Vec::new(),
+    })
+    .collect();
+  let position = |planned: &[PlannedField], name: &str| {
+    planned
+      .iter()
+      .position(|item| item.field.name.eq_ignore_ascii_case(name))
+  };
+  let find = |planned: &[PlannedField], name: &str| {
+    position(planned, name).ok_or_else(|| CustomError(format!("字段不存在: {}", name)))
+  };
+
+  for change in changes {
+    match change {
+      FieldChange::Add {
+        name,
+        field_type,
+        length,
+        decimal_count,
+        value,
+      } => {
+        if position(&planned, name).is_some() {
+          return Err(CustomError(format!("字段已存在: {}", name)));
+        }
+        let field = new_field(name, *field_type, *length, *decimal_count, encoding)?;
+        let default = encode_field_value(&field, value, encoding)?;
+        planned.push(PlannedField {
+          field,
+          source: None,
+          default,
+        });
+      }
+      FieldChange::Drop { name } => {
+        let index = find(&planned, name)?;
+        planned.remove(index);
+      }
+      FieldChange::Rename { name, new_name } => {
+        let index = find(&planned, name)?;
+        let new_name = field_name(new_name, encoding)?;
+        if position(&planned, &new_name).is_some_and(|other| other != index) {
+          return Err(CustomError(format!("字段已存在: {}", new_name)));
+        }
+        planned[index].field.name = new_name;
+      }
+      FieldChange::Alter {
+        name,
+        field_type,
+        length,
+        decimal_count,
+      } => {
+        let index = find(&planned, name)?;
+        let old = &planned[index].field;
+        let field_type = field_type.unwrap_or(old.field_type);
+        let length = length.unwrap_or(match field_type {
+          'L' => 1,
+          'D' => 8,
+          _ => old.length,
+        });
+        let numeric = |field_type: char| matches!(field_type, 'N' | 'F');
+        let decimal_count =
+          decimal_count.unwrap_or(if numeric(field_type) && numeric(old.field_type) {
+            old.decimal_count
+          } else {
+            0
+          });
+        let field = new_field(&old.name, field_type, length, decimal_count, encoding)?;
+        planned[index].field = field;
+      }
+    }
+  }
+  Ok(planned)
+}
+
+/// 校验字段名，按目标编码计算字节数，返回去掉首尾空白的名称
+fn field_name(name: &str, encoding: &'static Encoding) -> Result<String, CustomError> {
+  let name = name.trim();
+  let (bytes, _, _) = encoding.encode(name);
+  if name.is_empty() || bytes.len() > FIELD_NAME_LENGTH {
+    return Err(CustomError(format!(
+      "字段名长度必须为 1-{} 字节: {}",
+      FIELD_NAME_LENGTH, name
+    )));
+  }
+  Ok(name.to_string())
+}
+
+/// 校验新增或修改的字段定义
+fn new_field(
+  name: &str,
+  field_type: char,
+  length: u8,
+  decimal_count: u8,
+  encoding: &'static Encoding,
+) -> Result<DbfField, CustomError> {
+  let name = field_name(name, encoding)?;
+  let field_type = field_type.to_ascii_uppercase();
+  let valid = match field_type {
+    'C' => (1..=CHARACTER_LENGTH).contains(&length) && decimal_count == 0,
+    'N' | 'F' => {
+      (1..=NUMERIC_LENGTH).contains(&length)
+        && (decimal_count == 0 || u16::from(decimal_count) + 2 <= u16::from(length))
+    }
+    'L' => length == 1 && decimal_count == 0,
+    'D' => length == 8 && decimal_count == 0,
+    _ => {
+      return Err(CustomError(format!(
+        "不支持的字段类型 {}，仅支持 C/N/F/L/D",
+        field_type
+      )))
+    }
+  };
+  if !valid {
+    return Err(CustomError(format!(
+      "字段 {} 的定义无效: 类型 {} 长度 {} 小数位 {}",
+      name, field_type, length, decimal_count
+    )));
+  }
+  Ok(DbfField {
+    name,
+    field_type,
+    type_name: field_type_name(field_type),
+    length,
+    decimal_count,
+  })
+}
+
+fn rewrite(
+  dbf_path: &Path,
+  temp_path: &Path,
+  header: &DbfHeader,
+  planned: &[PlannedField],
+  encoding: &'static Encoding,
+) -> Result<(), CustomError> {
+  let mut reader = BufReader::new(File::open(dbf_path)?);
+  let mut original_header = vec![0u8; header.header_length as usize];
+  reader.read_exact(&mut original_header)?;
+  let offsets = field_offsets(&header.fields);
+
+  let fields: Vec<DbfField> = planned.iter().map(|item| item.field.clone()).collect();
+  let mut writer = DbfWriter::create(temp_path, &fields, encoding)?;
+  let mut record = vec![0u8; header.record_length as usize];
+  for index in 0..header.record_count {
+    reader
+      .read_exact(&mut record)
+      .map_err(|_| CustomError(format!("DBF 记录不完整，第 {} 条记录无法读取", index)))?;
+    // 保留删除标记
+    let mut output = vec![record[0]];
+    for item in planned {
+      let Some(source) = item.source else {
+        output.extend(&item.default);
+        continue;
+      };
+      let old = &header.fields[source];
+      let raw = &record[offsets[source]..offsets[source] + old.length as usize];
+      let unchanged = old.field_type == item.field.field_type
+        && old.length == item.field.length
+        && old.decimal_count == item.field.decimal_count;
+      if unchanged {
+        output.extend(raw);
+        continue;
+      }
+      let value = decode_raw(old, raw, item.field.field_type, encoding)
+        .map_err(|e| CustomError(format!("第 {} 条记录: {}", index, e)))?;
+      let bytes = encode_field_value(&item.field, &value, encoding)
+        .map_err(|e| CustomError(format!("第 {} 条记录: {}", index, e)))?;
+      output.extend(bytes);
+    }
+    writer.write_raw(&output)?;
+  }
+  writer.finish()?;
+
+  copy_dbf_header_flags(&original_header, temp_path)
+}
+
+/// 确认新文件的字段、记录数与文件长度符合预期
+fn verify(
+  temp_path: &Path,
+  header: &DbfHeader,
+  planned: &[PlannedField],
+  encoding: &DetectedEncoding,
+) -> Result<DbfHeader, CustomError> {
+  let new_header = read_dbf_header(temp_path, encoding)?;
+  let expected = planned.iter().map(|item| &item.field);
+  let expected_len = u64::from(new_header.header_length)
+    + u64::from(new_header.record_length) * u64::from(new_header.record_count)
+    + 1;
+  if new_header.record_count != header.record_count
+    || !new_header.fields.iter().eq(expected)
+    || fs::metadata(temp_path)?.len() != expected_len
+  {
+    return Err(CustomError(
+      "重写后的 DBF 校验失败，原文件未修改".to_string(),
+    ));
+  }
+  Ok(new_header)
+}
+
+/// 将原始字节按原字段类型解析为 JSON 值，转为文本时保留原文
+fn decode_raw(
+  field: &DbfField,
+  raw: &[u8],
+  target_type: char,
+  encoding: &'static Encoding,
+) -> Result<serde_json::Value, CustomError> {
+  let (text, _, _) = encoding.decode(raw);
+  let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+  if text.is_empty() {
+    return Ok(serde_json::Value::Null);
+  }
+  let value = match field.field_type {
+    // 转为文本时保留原始文本，日期不改写为 YYYY-MM-DD
+    'C' | 'N' | 'F' | 'D' if target_type == 'C' => serde_json::json!(text),
+    // 按字节切片前确认是 ASCII，避免落在多字节字符中间
+    'D' if text.len() == 8 && text.is_ascii() => {
+      serde_json::json!(format!("{}-{}-{}", &text[..4], &text[4..6], &text[6..]))
+    }
+    'L' => match text {
+      "T" | "t" | "Y" | "y" => serde_json::json!(true),
+      "F" | "f" | "N" | "n" => serde_json::json!(false),
+      _ => serde_json::Value::Null,
+    },
+    // 溢出标记 `*****`、逗号小数等无法解析的原值不能静默丢弃
+    'N' | 'F' => match text.parse::<f64>() {
+      Ok(number) if number.is_finite() => serde_json::json!(number),
+      _ => {
+        return Err(CustomError(format!(
+          "字段 {} 的值 {} 不是有效的数字，可先转为文本字段",
+          field.name, text
+        )))
+      }
+    },
+    'C' => serde_json::json!(text),
+    other => {
+      return Err(CustomError(format!(
+        "不支持转换 {} 类型的字段: {}",
+        other, field.name
+      )))
+    }
+  };
+  Ok(value)
+}
@@ -0,0 +1,163 @@
+use encoding_rs::Encoding;
+use serde::Serialize;
+use shapefile::dbase::{self, encoding::EncodingRs};
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use super::shapefile_to_geojson::CustomError;
+
+// DBF 文件头中语言驱动（LDID）字节的偏移
+const LANGUAGE_DRIVER_OFFSET: usize = 29;
+
+/// 编码的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EncodingSource {
+  /// 调用方指定
+  Override,
+  /// `.cpg` 文件
+  Cpg,
+  /// DBF 文件头的语言驱动字节
+  LanguageDriver,
+  /// 未检测到，按 UTF-8 读取
+  Default,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DetectedEncoding {
+  pub encoding: &'static Encoding,
+  pub source: EncodingSource,
+}
+
+impl DetectedEncoding {
+  pub fn to_json(self) -> serde_json::Value {
+    serde_json::json!({
+      "name": self.encoding.name(),
+      "source": self.source,
+    })
+  }
+}
+
+/// 按 指定编码 > `.cpg` > LDID > UTF-8 的顺序确定 DBF 的编码
+pub fn detect_encoding(
+  dbf_path: &Path,
+  override_label: Option<&str>,
+) -> Result<DetectedEncoding, CustomError> {
+  if let Some(label) = override_label {
+    let encoding =
+      encoding_for_label(label).ok_or_else(|| CustomError(format!("不支持的编码: {}", label)))?;
+    return Ok(DetectedEncoding {
+      encoding,
+      source: EncodingSource::Override,
+    });
+  }
+
+  if let Ok(content) = fs::read_to_string(dbf_path.with_extension("cpg")) {
+    match encoding_for_label(&content) {
+      Some(encoding) => {
+        return Ok(DetectedEncoding {
+          encoding,
+          source: EncodingSource::Cpg,
+        })
+      }
+      None => log::warn!("无法识别的 .cpg 编码: {}", content.trim()),
+    }
+  }
+
+  if let Some(encoding) = read_language_driver(dbf_path)?.and_then(encoding_for_language_driver) {
+    return Ok(DetectedEncoding {
+      encoding,
+      source: EncodingSource::LanguageDriver,
+    });
+  }
+
+  Ok(DetectedEncoding {
+    encoding: encoding_rs::UTF_8,
+    source: EncodingSource::Default,
+  })
+}
+
+/// 打开带编码的 shapefile 读取器
+///
+/// `shapefile::Reader::from_path` 只识别 `.cpg`，这里分别打开 `.shp` 与 `.dbf` 以使用指定编码
+pub fn open_reader(
+  shp_path: &Path,
+  encoding: &DetectedEncoding,
+) -> Result<shapefile::Reader<BufReader<File>, BufReader<File>>, shapefile::Error> {
+  let shape_reader = shapefile::ShapeReader::from_path(shp_path)?;
+  let dbf_path = shp_path.with_extension("dbf");
+  if !dbf_path.exists() {
+    return Err(shapefile::Error::MissingDbf);
+  }
+  let dbase_reader =
+    dbase::Reader::from_path_with_encoding(dbf_path, EncodingRs::from(encoding.encoding))?;
+  Ok(shapefile::Reader::new(shape_reader, dbase_reader))
+}
+
+/// 解析编码名称，支持 WHATWG 标签（如 `GBK`、`UTF-8`）与 Windows 代码页（如 `936`、`CP936`、`ANSI 1252`）
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+  let label = label.trim().trim_start_matches('\u{feff}');
+  if let Some(encoding) = Encoding::for_label(label.as_bytes()) {
+    return Some(encoding);
+  }
+
+  let code_page = label
+    .to_ascii_uppercase()
+    .trim_start_matches("ANSI")
+    .trim_start_matches("CP")
+    .trim()
+    .to_string();
+  let encoding = match code_page.as_str() {
+    "65001" | "UTF8" => encoding_rs::UTF_8,
+    "936" | "GB2312" => encoding_rs::GBK,
+    "54936" => encoding_rs::GB18030,
+    "950" => encoding_rs::BIG5,
+    "932" => encoding_rs::SHIFT_JIS,
+    "949" => encoding_rs::EUC_KR,
+    "874" => encoding_rs::WINDOWS_874,
+    "866" => encoding_rs::IBM866,
+    "1250" => encoding_rs::WINDOWS_1250,
+    "1251" => encoding_rs::WINDOWS_1251,
+    "1252" | "88591" => encoding_rs::WINDOWS_1252,
+    "1253" => encoding_rs::WINDOWS_1253,
+    "1254" => encoding_rs::WINDOWS_1254,
+    "1255" => encoding_rs::WINDOWS_1255,
+    "1256" => encoding_rs::WINDOWS_1256,
+    "1257" => encoding_rs::WINDOWS_1257,
+    "1258" => encoding_rs::WINDOWS_1258,
+    _ => return None,
+  };
+  Some(encoding)
+}
+
+fn read_language_driver(dbf_path: &Path) -> Result<Option<u8>, CustomError> {
+  let mut header = [0u8; LANGUAGE_DRIVER_OFFSET + 1];
+  let mut file = File::open(dbf_path)?;
+  match file.read_exact(&mut header) {
+    Ok(()) => Ok(Some(header[LANGUAGE_DRIVER_OFFSET])),
+    Err(_) => Ok(None),
+  }
+}
+
+// 仅列出 encoding_rs 支持的代码页，0x00 表示未设置
+fn encoding_for_language_driver(ldid: u8) -> Option<&'static Encoding> {
+  let encoding = match ldid {
+    0x4D | 0x7A => encoding_rs::GBK,
+    0x4F | 0x78 => encoding_rs::BIG5,
+    0x13 | 0x7B => encoding_rs::SHIFT_JIS,
+    0x4E | 0x79 => encoding_rs::EUC_KR,
+    0x7C => encoding_rs::WINDOWS_874,
+    0x26 | 0x65 => encoding_rs::IBM866,
+    0xC8 => encoding_rs::WINDOWS_1250,
+    0xC9 => encoding_rs::WINDOWS_1251,
+    0x03 | 0x57 | 0x58 | 0x59 => encoding_rs::WINDOWS_1252,
+    0xCB => encoding_rs::WINDOWS_1253,
+    0xCA => encoding_rs::WINDOWS_1254,
+    0x7D => encoding_rs::WINDOWS_1255,
+    0x7E => encoding_rs::WINDOWS_1256,
+    0xCC => encoding_rs::WINDOWS_1257,
+    _ => return None,
+  };
+  Some(encoding)
+}
@@ -0,0 +1,570 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shapefile::dbase::{self, encoding::EncodingRs};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::dbf::insert_record;
+use super::encoding::DetectedEncoding;
+use super::record_query::compare_values;
+use super::schema::read_dbf_header;
+use super::shapefile_to_geojson::CustomError;
+
+// 每个字段最多记录的不同值个数，超出后不再计入新值，避免唯一编号类字段占用过多内存
+const MAX_TRACKED_DISTINCT: usize = 10000;
+// 自然断点法的计算量与值的个数的平方成正比，超过时对排序后的值等距抽样
+const JENKS_MAX_VALUES: usize = 2000;
+// 每个字段最多保留的数值样本个数，用于直方图与分级；均值、标准差与极值仍按全部值计算
+const NUMBER_SAMPLE_SIZE: usize = 100_000;
+const MAX_CLASS_COUNT: usize = 32;
+const MAX_HISTOGRAM_BINS: usize = 1000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FieldStatsOptions {
+  /// 统计的字段，不区分大小写，缺省为全部字段
+  pub fields: Vec<String>,
+  /// 分级数
+  pub class_count: usize,
+  /// 直方图的分组数
+  pub histogram_bins: usize,
+  /// 返回的不同值个数上限，按频数从高到低
+  pub max_distinct: usize,
+}
+
+impl Default for FieldStatsOptions {
+  fn default() -> Self {
+    FieldStatsOptions {
+      fields: Vec::new(),
+      class_count: 5,
+      histogram_bins: 10,
+      max_distinct: 100,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldStats {
+  pub name: String,
+  pub field_type: char,
+  /// 非空值个数
+  pub count: u64,
+  pub null_count: u64,
+  /// 数值按大小、文本与日期按字典序
+  pub min: Option<Value>,
+  pub max: Option<Value>,
+  /// 以下仅数值字段有
+  pub mean: Option<f64>,
+  /// 总体标准差
+  pub std_dev: Option<f64>,
+  /// 数值个数超过抽样上限时，各组个数按样本比例估算
+  pub histogram: Option<Vec<HistogramBin>>,
+  pub breaks: Option<ClassBreaks>,
+  /// 不同值个数，`distinct_truncated` 为 true 时为下限
+  pub distinct_count: usize,
+  pub distinct_values: Vec<DistinctValue>,
+  /// 不同值超出统计上限或返回上限，`distinct_values` 未列全
+  pub distinct_truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DistinctValue {
+  pub value: Value,
+  pub count: u64,
+}
+
+/// 直方图分组，最后一组包含上界
+#[derive(Debug, Clone, Serialize)]
+pub struct HistogramBin {
+  pub min: f64,
+  pub max: f64,
+  pub count: u64,
+}
+
+/// 各分级方法的分级边界，依次为最小值、各级之间的断点、最大值
+///
+/// 重复的边界会被合并，值的种类少于分级数时级数相应减少；
+/// 数值个数超过抽样上限时按样本计算
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassBreaks {
+  pub equal_interval: Vec<f64>,
+  pub quantile: Vec<f64>,
+  /// 自然断点法（Jenks）
+  pub jenks: Vec<f64>,
+  /// 以均值为中心、间隔一个标准差
+  pub std_dev: Vec<f64>,
+}
+
+/// 扫描 DBF，统计各字段的值
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn field_statistics(
+  dbf_path: &Path,
+  options: &FieldStatsOptions,
+  encoding: &DetectedEncoding,
+) -> Result<(u64, Vec<FieldStats>), CustomError> {
+  if !(1..=MAX_CLASS_COUNT).contains(&options.class_count) {
+    return Err(CustomError(format!(
+      "分级数应在 1 到 {} 之间",
+      MAX_CLASS_COUNT
+    )));
+  }
+  if !(1..=MAX_HISTOGRAM_BINS).contains(&options.histogram_bins) {
+    return Err(CustomError(format!(
+      "直方图分组数应在 1 到 {} 之间",
+      MAX_HISTOGRAM_BINS
+    )));
+  }
+  let header = read_dbf_header(dbf_path, encoding)?;
+  let fields = if options.fields.is_empty() {
+    header.fields
+  } else {
+    options
+      .fields
+      .iter()
+      .map(|name| {
+        header
+          .fields
+          .iter()
+          .find(|field| field.name.eq_ignore_ascii_case(name))
+          .cloned()
+          .ok_or_else(|| CustomError(format!("字段不存在: {}", name)))
+      })
+      .collect::<Result<_, _>>()?
+  };
+  let mut accumulators: Vec<FieldAccumulator> = fields
+    .iter()
+    .map(|field| FieldAccumulator::new(&field.name, field.field_type))
+    .collect();
+
+  let mut reader =
+    dbase::Reader::from_path_with_encoding(dbf_path, EncodingRs::from(encoding.encoding))?;
+  let mut record_count = 0;
+  for record in reader.iter_records() {
+    let mut properties = serde_json::Map::new();
+    insert_record(&mut properties, record?);
+    for accumulator in &mut accumulators {
+      accumulator.add(properties.get(&accumulator.name).unwrap_or(&Value::Null));
+    }
+    record_count += 1;
+  }
+
+  let stats = accumulators
+    .into_iter()
+    .map(|accumulator| accumulator.finish(options))
+    .collect();
+  Ok((record_count, stats))
+}
+
+/// 逐条累计一个字段的值
+struct FieldAccumulator {
+  name: String,
+  field_type: char,
+  count: u64,
+  null_count: u64,
+  min: Option<Value>,
+  max: Option<Value>,
+  numbers: NumberSummary,
+  // 以值的 JSON 文本为键
+  distinct: HashMap<String, DistinctValue>,
+  distinct_overflow: bool,
+}
+
+impl FieldAccumulator {
+  fn new(name: &str, field_type: char) -> Self {
+    FieldAccumulator {
+      name: name.to_string(),
+      field_type,
+      count: 0,
+      null_count: 0,
+      min: None,
+      max: None,
+      numbers: NumberSummary::default(),
+      distinct: HashMap::new(),
+      distinct_overflow: false,
+    }
+  }
+
+  fn add(&mut self, value: &Value) {
+    // 空文本与空值同样处理
+    if value.is_null() || value.as_str().is_some_and(|text| text.trim().is_empty()) {
+      self.null_count += 1;
+      return;
+    }
+    self.count += 1;
+    if let Some(number) = value.as_f64() {
+      self.numbers.add(number);
+    }
+    if self
+      .min
+      .as_ref()
+      .is_none_or(|min| compare_values(value, min) == Ordering::Less)
+    {
+      self.min = Some(value.clone());
+    }
+    if self
+      .max
+      .as_ref()
+      .is_none_or(|max| compare_values(value, max) == Ordering::Greater)
+    {
+      self.max = Some(value.clone());
+    }
+
+    let key = value.to_string();
+    if let Some(distinct) = self.distinct.get_mut(&key) {
+      distinct.count += 1;
+    } else if self.distinct.len() < MAX_TRACKED_DISTINCT {
+      self.distinct.insert(
+        key,
+        DistinctValue {
+          value: value.clone(),
+          count: 1,
+        },
+      );
+    } else {
+      self.distinct_overflow = true;
+    }
+  }
+
+  fn finish(self, options: &FieldStatsOptions) -> FieldStats {
+    let distinct_count = self.distinct.len();
+    let mut distinct_values: Vec<DistinctValue> = self.distinct.into_values().collect();
+    // 频数从高到低，相同时按值排序，保证结果稳定
+    distinct_values.sort_by(|a, b| {
+      b.count
+        .cmp(&a.count)
+        .then_with(|| compare_values(&a.value, &b.value))
+    });
+    let distinct_truncated = self.distinct_overflow || distinct_values.len() > options.max_distinct;
+    distinct_values.truncate(options.max_distinct);
+
+    let mut stats = FieldStats {
+      name: self.name,
+      field_type: self.field_type,
+      count: self.count,
+      null_count: self.null_count,
+      min: self.min,
+      max: self.max,
+      mean: None,
+      std_dev: None,
+      histogram: None,
+      breaks: None,
+      distinct_count,
+      distinct_values,
+      distinct_truncated,
+    };
+    if self.numbers.count == 0 {
+      return stats;
+    }
+
+    let count = self.numbers.count;
+    let (mean, std_dev) = self.numbers.mean_std_dev();
+    let values = &self.numbers.into_sorted_sample();
+    let mut bins = histogram(values, options.histogram_bins);
+    if values.len() as u64 != count {
+      let scale = count as f64 / values.len() as f64;
+      for bin in &mut bins {
+        bin.count = (bin.count as f64 * scale).round() as u64;
+      }
+    }
+    stats.mean = Some(mean);
+    stats.std_dev = Some(std_dev);
+    stats.histogram = Some(bins);
+    stats.breaks = Some(ClassBreaks {
+      equal_interval: equal_interval_breaks(values, options.class_count),
+      quantile: quantile_breaks(values, options.class_count),
+      jenks: jenks_breaks(values, options.class_count),
+      std_dev: std_dev_breaks(values, options.class_count, mean, std_dev),
+    });
+    stats
+  }
+}
+
+/// 数值的累计统计与等间隔样本
+///
+/// 样本满时隔一个丢弃一个并将间隔加倍，样本始终是按读取顺序等间隔抽取的值
+struct NumberSummary {
+  count: u64,
+  mean: f64,
+  m2: f64,
+  min: f64,
+  max: f64,
+  sample: Vec<f64>,
+  stride: u64,
+}
+
+impl Default for NumberSummary {
+  fn default() -> Self {
+    NumberSummary {
+      count: 0,
+      mean: 0.0,
+      m2: 0.0,
+      min: f64::INFINITY,
+      max: f64::NEG_INFINITY,
+      sample: Vec::new(),
+      stride: 1,
+    }
+  }
+}
+
+impl NumberSummary {
+  fn add(&mut self, value: f64) {
+    // Welford 算法
+    self.count += 1;
+    let delta = value - self.mean;
+    self.mean += delta / self.count as f64;
+    self.m2 += delta * (value - self.mean);
+    self.min = self.min.min(value);
+    self.max = self.max.max(value);
+
+    let position = self.count - 1;
+    if position % self.stride != 0 {
+      return;
+    }
+    if self.sample.len() == NUMBER_SAMPLE_SIZE {
+      let mut index = 0;
+      self.sample.retain(|_| {
+        index += 1;
+        index % 2 == 1
+      });
+      self.stride *= 2;
+      if position % self.stride != 0 {
+        return;
+      }
+    }
+    self.sample.push(value);
+  }
+
+  /// 均值与总体标准差
+  fn mean_std_dev(&self) -> (f64, f64) {
+    (self.mean, (self.m2 / self.count as f64).sqrt())
+  }
+
+  /// 升序排列的样本，抽样时补上最小值与最大值，使分级边界覆盖全部数据
+  fn into_sorted_sample(self) -> Vec<f64> {
+    let mut sample = self.sample;
+    if self.stride > 1 {
+      sample.extend([self.min, self.max]);
+    }
+    sample.sort_by(f64::total_cmp);
+    sample
+  }
+}
+
+/// 以下函数的 `sorted` 均为升序且非空
+fn histogram(sorted: &[f64], bins: usize) -> Vec<HistogramBin> {
+  let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+  let bins = if min == max { 1 } else { bins };
+  let width = (max - min) / bins as f64;
+  let mut histogram: Vec<HistogramBin> = (0..bins)
+    .map(|bin| HistogramBin {
+      min: min + width * bin as f64,
+      max: if bin + 1 == bins {
+        max
+      } else {
+        min + width * (bin + 1) as f64
+      },
+      count: 0,
+    })
+    .collect();
+  for value in sorted {
+    let bin = if width > 0.0 {
+      (((value - min) / width) as usize).min(bins - 1)
+    } else {
+      0
+    };
+    histogram[bin].count += 1;
+  }
+  histogram
+}
+
+fn equal_interval_breaks(sorted: &[f64], class_count: usize) -> Vec<f64> {
+  let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+  let width = (max - min) / class_count as f64;
+  let inner = (1..class_count).map(|class| min + width * class as f64);
+  with_bounds(sorted, inner)
+}
+
+fn quantile_breaks(sorted: &[f64], class_count: usize) -> Vec<f64> {
+  let inner = (1..class_count).map(|class| sorted[class * sorted.len() / class_count]);
+  with_bounds(sorted, inner)
+}
+
+/// 断点为均值加减 0.5、1.5… 个标准差（级数为奇数）或 0、1… 个标准差（级数为偶数），
+/// 超出数据范围的断点舍去
+fn std_dev_breaks(sorted: &[f64], class_count: usize, mean: f64, std_dev: f64) -> Vec<f64> {
+  let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+  let inner = (1..class_count)
+    .map(|class| mean + (class as f64 - class_count as f64 / 2.0) * std_dev)
+    .filter(|value| *value > min && *value < max);
+  with_bounds(sorted, inner)
+}
+
+/// Fisher-Jenks 自然断点法，使各级内方差之和最小
+fn jenks_breaks(sorted: &[f64], class_count: usize) -> Vec<f64> {
+  let sample: Vec<f64> = if sorted.len() > JENKS_MAX_VALUES {
+    (0..JENKS_MAX_VALUES)
+      .map(|index| sorted[index * (sorted.len() - 1) / (JENKS_MAX_VALUES - 1)])
+      .collect()
+  } else {
+    sorted.to_vec()
+  };
+  let n = sample.len();
+  if n <= class_count {
+    return with_bounds(sorted, sample.into_iter());
+  }
+
+  // lower[l][j]: 前 l 个值分为 j 级时最后一级的起始位置（从 1 开始）
+  let mut lower = vec![vec![0usize; class_count + 1]; n + 1];
+  let mut variance = vec![vec![f64::INFINITY; class_count + 1]; n + 1];
+  for class in 1..=class_count {
+    lower[1][class] = 1;
+    variance[1][class] = 0.0;
+  }
+  for l in 2..=n {
+    let (mut sum, mut sum_squares, mut class_variance) = (0.0, 0.0, 0.0);
+    for m in 1..=l {
+      let start = l - m + 1;
+      let value = sample[start - 1];
+      sum += value;
+      sum_squares += value * value;
+      class_variance = sum_squares - sum * sum / m as f64;
+      if start > 1 {
+        // 前 start - 1 个值至少要能分成 class - 1 级，否则值全部相同时会回溯到不存在的位置
+        for class in 2..=class_count.min(start) {
+          let candidate = class_variance + variance[start - 1][class - 1];
+          if variance[l][class] >= candidate {
+            lower[l][class] = start;
+            variance[l][class] = candidate;
+          }
+        }
+      }
+    }
+    lower[l][1] = 1;
+    variance[l][1] = class_variance;
+  }
+
+  let mut inner = Vec::with_capacity(class_count - 1);
+  let mut end = n;
+  for class in (2..=class_count).rev() {
+    let start = lower[end][class];
+    // 断点取下一级之前最后一个值，即本级的上界
+    inner.push(sample[start - 2]);
+    end = start - 1;
+  }
+  inner.reverse();
+  with_bounds(sorted, inner.into_iter())
+}
+
+/// 在断点两端加上最小值与最大值，并合并重复的边界
+fn with_bounds(sorted: &[f64], inner: impl Iterator<Item = f64>) -> Vec<f64> {
+  let mut breaks = vec![sorted[0]];
+  breaks.extend(inner);
+  breaks.push(sorted[sorted.len() - 1]);
+  breaks.dedup();
+  breaks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mean_std_dev(values: &[f64]) -> (f64, f64) {
+    let mut summary = NumberSummary::default();
+    values.iter().for_each(|value| summary.add(*value));
+    summary.mean_std_dev()
+  }
+
+  // 各级内离差平方和之和
+  fn total_deviation(classes: &[&[f64]]) -> f64 {
+    classes
+      .iter()
+      .map(|class| {
+        let mean = class.iter().sum::<f64>() / class.len() as f64;
+        class
+          .iter()
+          .map(|value| (value - mean).powi(2))
+          .sum::<f64>()
+      })
+      .sum()
+  }
+
+  #[test]
+  fn jenks_separates_clusters() {
+    let values = [
+      1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0, 30.0, 31.0, 32.0, 40.0, 41.0, 42.0,
+    ];
+    assert_eq!(
+      jenks_breaks(&values, 5),
+      vec![1.0, 3.0, 12.0, 22.0, 32.0, 42.0]
+    );
+  }
+
+  #[test]
+  fn jenks_matches_exhaustive_search() {
+    let values = [1.0, 1.5, 2.0, 6.0, 7.0, 7.5, 12.0, 13.0, 13.5, 30.0];
+    let mut best = (f64::INFINITY, Vec::new());
+    for first in 1..values.len() - 1 {
+      for second in first + 1..values.len() {
+        let deviation =
+          total_deviation(&[&values[..first], &values[first..second], &values[second..]]);
+        if deviation < best.0 {
+          best = (
+            deviation,
+            vec![values[0], values[first - 1], values[second - 1], values[9]],
+          );
+        }
+      }
+    }
+    assert_eq!(jenks_breaks(&values, 3), best.1);
+  }
+
+  #[test]
+  fn fewer_values_than_classes() {
+    let values = [1.0, 2.0, 3.0];
+    assert_eq!(jenks_breaks(&values, 5), vec![1.0, 2.0, 3.0]);
+    assert_eq!(quantile_breaks(&values, 5), vec![1.0, 2.0, 3.0]);
+    assert_eq!(quantile_breaks(&[1.0, 2.0], 4), vec![1.0, 2.0]);
+  }
+
+  #[test]
+  fn all_equal_values() {
+    let values = [4.0; 6];
+    let (mean, std_dev) = mean_std_dev(&values);
+    assert_eq!(jenks_breaks(&values, 3), vec![4.0]);
+    assert_eq!(quantile_breaks(&values, 3), vec![4.0]);
+    assert_eq!(equal_interval_breaks(&values, 3), vec![4.0]);
+    assert_eq!(std_dev_breaks(&values, 3, mean, std_dev), vec![4.0]);
+  }
+
+  #[test]
+  fn quantile_breaks_split_by_count() {
+    let values: Vec<f64> = (1..=10).map(f64::from).collect();
+    assert_eq!(quantile_breaks(&values, 4), vec![1.0, 3.0, 6.0, 8.0, 10.0]);
+  }
+
+  #[test]
+  fn std_dev_breaks_by_class_parity() {
+    let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+    let (mean, std_dev) = mean_std_dev(&values);
+    assert_eq!((mean, std_dev), (5.0, 2.0));
+    // 偶数级以均值为断点
+    assert_eq!(
+      std_dev_breaks(&values, 4, mean, std_dev),
+      vec![2.0, 3.0, 5.0, 7.0, 9.0]
+    );
+    // 奇数级以均值为中间一级的中心
+    assert_eq!(
+      std_dev_breaks(&values, 3, mean, std_dev),
+      vec![2.0, 4.0, 6.0, 9.0]
+    );
+    // 与最小值重合的断点舍去
+    assert_eq!(
+      std_dev_breaks(&values, 5, mean, std_dev),
+      vec![2.0, 4.0, 6.0, 8.0, 9.0]
+    );
+  }
+}
@@ -0,0 +1,500 @@
+use encoding_rs::Encoding;
+use gdal::spatial_ref::SpatialRef;
+use geojson::{Feature, FeatureCollection, GeoJson, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::encoding::encoding_for_label;
+use super::projection::{crs_from_definition, Reprojector, DEFAULT_TARGET_CRS};
+use super::schema::{field_type_name, DbfField};
+use super::shapefile_to_geojson::CustomError;
+use super::writer::{DbfWriter, ShapeKind, ShpWriter};
+use crate::utils;
+
+// DBF 字段名与字符字段的长度上限
+const FIELD_NAME_LENGTH: usize = 10;
+const CHARACTER_LENGTH: usize = 254;
+// 整数超过 18 位时按浮点数写入
+const INTEGER_LENGTH: usize = 18;
+// 浮点数使用与 GDAL 一致的 24.15
+const REAL_LENGTH: u8 = 24;
+const REAL_DECIMALS: u8 = 15;
+
+/// 导出 shapefile 的选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShapefileExportOptions {
+  /// 输出文件名，缺省为 `export`
+  pub file_name: Option<String>,
+  /// GeoJSON 的坐标系，缺省为 WGS84
+  pub source_crs: Option<String>,
+  /// 输出坐标系，缺省与源坐标系相同
+  pub target_crs: Option<String>,
+  /// DBF 编码，缺省为 UTF-8，同时写入 `.cpg`
+  pub encoding: Option<String>,
+  /// 覆盖同名文件，缺省时在文件名后追加 `_1`、`_2`…
+  pub overwrite: bool,
+}
+
+/// 写出的一个 shapefile
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedShapefile {
+  pub path: PathBuf,
+  pub shape_type: i32,
+  pub feature_count: u32,
+}
+
+/// 属性名到 DBF 字段名的映射
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldMapping {
+  pub property: String,
+  #[serde(flatten)]
+  pub field: DbfField,
+}
+
+pub struct ShapefileExport {
+  pub files: Vec<ExportedShapefile>,
+  pub fields: Vec<FieldMapping>,
+  pub warnings: Vec<String>,
+}
+
+/// 解析 GeoJSON 文本，支持 FeatureCollection、Feature 与单个几何
+pub fn parse_features(geojson: serde_json::Value) -> Result<Vec<Feature>, CustomError> {
+  let geojson =
+    GeoJson::from_json_value(geojson).map_err(|e| CustomError(format!("无效的 GeoJSON: {}", e)))?;
+  Ok(match geojson {
+    GeoJson::FeatureCollection(FeatureCollection { features, .. }) => features,
+    GeoJson::Feature(feature) => vec![feature],
+    GeoJson::Geometry(geometry) => vec![Feature {
+      bbox: None,
+      geometry: Some(geometry),
+      id: None,
+      properties: None,
+      foreign_members: None,
+    }],
+  })
+}
+
+/// 将要素写入工作空间的 shapefile 目录，多种几何类型时按类型拆分为多个文件
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn export_features_to_shapefile(
+  mut features: Vec<Feature>,
+  options: &ShapefileExportOptions,
+) -> Result<ShapefileExport, CustomError> {
+  let encoding = match &options.encoding {
+    Some(label) => {
+      encoding_for_label(label).ok_or_else(|| CustomError(format!("不支持的编码: {}", label)))?
+    }
+    None => encoding_rs::UTF_8,
+  };
+  let source_crs = options.source_crs.as_deref().unwrap_or(DEFAULT_TARGET_CRS);
+  let target_crs = options.target_crs.as_deref().unwrap_or(source_crs);
+  let source = crs_from_definition(source_crs)?;
+  let reprojector = Reprojector::new(&source, target_crs)?;
+  let prj = esri_wkt(target_crs)?;
+
+  let mut warnings = Vec::new();
+  let mut groups: BTreeMap<ShapeKind, Vec<usize>> = BTreeMap::new();
+  let mut null_indices = Vec::new();
+  let mut collection_count = 0;
+  for (index, feature) in features.iter_mut().enumerate() {
+    let Some(geometry) = feature.geometry.as_mut() else {
+      null_indices.push(index);
+      continue;
+    };
+    match ShapeKind::of(&geometry.value) {
+      Some(kind) => {
+        reprojector.transform_value(&mut geometry.value)?;
+        groups.entry(kind).or_default().push(index);
+      }
+      None => collection_count += 1,
+    }
+  }
+  if collection_count > 0 {
+    warnings.push(format!(
+      "{} 个 GeometryCollection 要素无法写入 shapefile，已跳过",
+      collection_count
+    ));
+  }
+  // 只有一种几何类型时空几何随之写出，否则无法确定归属
+  match groups.len() {
+    0 => return Err(CustomError("没有可导出的几何".to_string())),
+    1 => {
+      if let Some(indices) = groups.values_mut().next() {
+        indices.extend(null_indices);
+        indices.sort_unstable();
+      }
+    }
+    _ if !null_indices.is_empty() => warnings.push(format!(
+      "{} 个空几何要素无法确定几何类型，已跳过",
+      null_indices.len()
+    )),
+    _ => {}
+  }
+
+  let (fields, truncated_count) = infer_fields(&features, encoding);
+  if truncated_count > 0 {
+    warnings.push(format!(
+      "{} 个文本值超过 {} 字节，已截断",
+      truncated_count, CHARACTER_LENGTH
+    ));
+  }
+
+  let output_dir = utils::files::get_shapefile_path();
+  fs::create_dir_all(&output_dir)?;
+  let split = groups.len() > 1;
+  let file_name = |base_name: &str, kind: ShapeKind| match split {
+    true => format!("{}_{}", base_name, kind.suffix()),
+    false => base_name.to_string(),
+  };
+  let requested_name = output_name(options.file_name.as_deref())?;
+  let base_name = if options.overwrite {
+    requested_name
+  } else {
+    let kinds: Vec<ShapeKind> = groups.keys().copied().collect();
+    let name = unused_name(&requested_name, |name| {
+      kinds
+        .iter()
+        .any(|&kind| shapefile_exists(&output_dir, &file_name(name, kind)))
+    });
+    if name != requested_name {
+      warnings.push(format!("{} 已存在，已改为输出到 {}", requested_name, name));
+    }
+    name
+  };
+  let mut files = Vec::new();
+  for (kind, indices) in groups {
+    let shp_path = output_dir.join(format!("{}.shp", file_name(&base_name, kind)));
+    let has_z = indices.iter().any(|&index| {
+      features[index]
+        .geometry
+        .as_ref()
+        .is_some_and(|geometry| has_z(&geometry.value))
+    });
+    let shape_type = kind.shape_type(has_z);
+
+    let mut shp_writer = ShpWriter::create(&shp_path, shape_type)?;
+    let dbf_fields: Vec<DbfField> = fields.iter().map(|mapping| mapping.field.clone()).collect();
+    let mut dbf_writer = DbfWriter::create(&shp_path.with_extension("dbf"), &dbf_fields, encoding)?;
+    for index in indices {
+      let feature = &features[index];
+      shp_writer.write(feature.geometry.as_ref().map(|geometry| &geometry.value))?;
+      let values: Vec<serde_json::Value> = fields
+        .iter()
+        .map(|mapping| {
+          let value = feature
+            .property(&mapping.property)
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+          fit_value(&mapping.field, value, encoding)
+        })
+        .collect();
+      dbf_writer.write(&values)?;
+    }
+    let feature_count = shp_writer.finish()?;
+    dbf_writer.finish()?;
+    fs::write(shp_path.with_extension("prj"), &prj)?;
+    fs::write(shp_path.with_extension("cpg"), encoding.name())?;
+
+    files.push(ExportedShapefile {
+      path: shp_path,
+      shape_type,
+      feature_count,
+    });
+  }
+
+  Ok(ShapefileExport {
+    files,
+    fields,
+    warnings,
+  })
+}
+
+fn output_name(file_name: Option<&str>) -> Result<String, CustomError> {
+  match file_name {
+    // 只取文件名部分，避免写出工作空间
+    Some(name) => Path::new(name)
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .map(str::to_string)
+      .ok_or_else(|| CustomError(format!("无效的输出文件名: {}", name))),
+    None => Ok("export".to_string()),
+  }
+}
+
+/// 依次尝试 `name`、`name_1`、`name_2`…，返回第一个未被占用的名称
+fn unused_name(name: &str, exists: impl Fn(&str) -> bool) -> String {
+  std::iter::once(name.to_string())
+    .chain((1..).map(|suffix| format!("{}_{}", name, suffix)))
+    .find(|candidate| !exists(candidate))
+    .unwrap_or_default()
+}
+
+fn shapefile_exists(dir: &Path, name: &str) -> bool {
+  ["shp", "shx", "dbf", "prj", "cpg"]
+    .iter()
+    .any(|extension| dir.join(format!("{}.{}", name, extension)).exists())
+}
+
+fn esri_wkt(definition: &str) -> Result<String, CustomError> {
+  let spatial_ref = SpatialRef::from_definition(definition)
+    .map_err(|e| CustomError(format!("无法识别坐标系 {}: {}", definition, e)))?;
+  spatial_ref.morph_to_esri()?;
+  Ok(spatial_ref.to_wkt()?)
+}
+
+fn has_z(value: &Value) -> bool {
+  match value {
+    Value::Point(position) => position.len() >= 3,
+    Value::MultiPoint(points) | Value::LineString(points) => {
+      points.iter().any(|position| position.len() >= 3)
+    }
+    Value::MultiLineString(lines) | Value::Polygon(lines) => {
+      lines.iter().flatten().any(|position| position.len() >= 3)
+    }
+    Value::MultiPolygon(polygons) => polygons
+      .iter()
+      .flatten()
+      .flatten()
+      .any(|position| position.len() >= 3),
+    Value::GeometryCollection(geometries) => {
+      geometries.iter().any(|geometry| has_z(&geometry.value))
+    }
+  }
+}
+
+/// 属性值的统计，用于推断字段类型
+#[derive(Default)]
+struct PropertyStats {
+  booleans: usize,
+  integers: usize,
+  reals: usize,
+  dates: usize,
+  texts: usize,
+  others: usize,
+  max_integer_length: usize,
+  max_text_length: usize,
+  // 超过字符字段长度上限的值的个数
+  long_texts: usize,
+}
+
+impl PropertyStats {
+  fn add(&mut self, value: &serde_json::Value, encoding: &'static Encoding) {
+    let text_length = match value {
+      serde_json::Value::Null => return,
+      serde_json::Value::String(text) => encoding.encode(text).0.len(),
+      other => other.to_string().len(),
+    };
+    self.max_text_length = self.max_text_length.max(text_length);
+    if text_length > CHARACTER_LENGTH {
+      self.long_texts += 1;
+    }
+    match value {
+      serde_json::Value::Bool(_) => self.booleans += 1,
+      serde_json::Value::Number(number) => match number.as_i64() {
+        Some(_) if text_length <= INTEGER_LENGTH => {
+          self.integers += 1;
+          self.max_integer_length = self.max_integer_length.max(text_length);
+        }
+        _ => self.reals += 1,
+      },
+      serde_json::Value::String(text) => {
+        if is_iso_date(text) {
+          self.dates += 1;
+        }
+        self.texts += 1;
+      }
+      _ => self.others += 1,
+    }
+  }
+
+  fn field_definition(&self) -> (char, u8, u8) {
+    let numbers = self.integers + self.reals;
+    let total = self.booleans + numbers + self.texts + self.others;
+    if total == 0 {
+      ('C', 1, 0)
+    } else if self.booleans == total {
+      ('L', 1, 0)
+    } else if self.integers == total {
+      ('N', self.max_integer_length.max(1) as u8, 0)
+    } else if numbers == total {
+      ('N', REAL_LENGTH, REAL_DECIMALS)
+    } else if self.dates == total {
+      ('D', 8, 0)
+    } else {
+      // 混合类型统一按文本写出，数字与布尔值转为字符串
+      let length = self.max_text_length.clamp(1, CHARACTER_LENGTH);
+      ('C', length as u8, 0)
+    }
+  }
+}
+
+/// 按属性名首次出现的顺序推断字段，返回字段映射与需截断的文本数量
+fn infer_fields(features: &[Feature], encoding: &'static Encoding) -> (Vec<FieldMapping>, usize) {
+  let mut properties: Vec<String> = Vec::new();
+  let mut stats: HashMap<String, PropertyStats> = HashMap::new();
+  for properties_map in features
+    .iter()
+    .filter_map(|feature| feature.properties.as_ref())
+  {
+    for (key, value) in properties_map {
+      let entry = stats.entry(key.clone()).or_insert_with(|| {
+        properties.push(key.clone());
+        PropertyStats::default()
+      });
+      entry.add(value, encoding);
+    }
+  }
+
+  let mut used_names = HashSet::new();
+  let mut truncated_count = 0;
+  let fields = properties
+    .into_iter()
+    .map(|property| {
+      let property_stats = &stats[&property];
+      let (field_type, length, decimal_count) = property_stats.field_definition();
+      if field_type == 'C' {
+        truncated_count += property_stats.long_texts;
+      }
+      let name = unique_field_name(&property, encoding, &mut used_names);
+      FieldMapping {
+        property,
+        field: DbfField {
+          name,
+          field_type,
+          type_name: field_type_name(field_type),
+          length,
+          decimal_count,
+        },
+      }
+    })
+    .collect();
+  (fields, truncated_count)
+}
+
+/// 截断为不超过 10 字节的字段名，重名时以 `_1`、`_2` 结尾区分（不区分大小写）
+fn unique_field_name(
+  property: &str,
+  encoding: &'static Encoding,
+  used_names: &mut HashSet<String>,
+) -> String {
+  let property = property.trim();
+  let base = if property.is_empty() {
+    "FIELD"
+  } else {
+    property
+  };
+  let mut name = truncate_bytes(base, FIELD_NAME_LENGTH, encoding);
+  let mut suffix = 0;
+  while !used_names.insert(name.to_uppercase()) {
+    suffix += 1;
+    let tail = format!("_{}", suffix);
+    name = format!(
+      "{}{}",
+      truncate_bytes(base, FIELD_NAME_LENGTH - tail.len(), encoding),
+      tail
+    );
+  }
+  name
+}
+
+/// 按编码后的字节数截断，不拆分字符
+fn truncate_bytes(text: &str, max_bytes: usize, encoding: &'static Encoding) -> String {
+  if encoding.encode(text).0.len() <= max_bytes {
+    return text.to_string();
+  }
+  let mut result = String::new();
+  let mut length = 0;
+  let mut buf = [0u8; 4];
+  for c in text.chars() {
+    length += encoding.encode(c.encode_utf8(&mut buf)).0.len();
+    if length > max_bytes {
+      break;
+    }
+    result.push(c);
+  }
+  result
+}
+
+/// 将属性值转换为字段可接受的形式，文本超长时截断
+fn fit_value(
+  field: &DbfField,
+  value: serde_json::Value,
+  encoding: &'static Encoding,
+) -> serde_json::Value {
+  if field.field_type != 'C' || value.is_null() {
+    return value;
+  }
+  let text = match value {
+    serde_json::Value::String(text) => text,
+    other => other.to_string(),
+  };
+  serde_json::Value::String(truncate_bytes(&text, field.length as usize, encoding))
+}
+
+fn is_iso_date(text: &str) -> bool {
+  let bytes = text.as_bytes();
+  bytes.len() == 10
+    && bytes[4] == b'-'
+    && bytes[7] == b'-'
+    && bytes
+      .iter()
+      .enumerate()
+      .all(|(index, byte)| index == 4 || index == 7 || byte.is_ascii_digit())
+    && (1..=12).contains(&text[5..7].parse::<u32>().unwrap_or(0))
+    && (1..=31).contains(&text[8..10].parse::<u32>().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn feature(properties: serde_json::Value) -> Feature {
+    Feature {
+      bbox: None,
+      geometry: None,
+      id: None,
+      properties: properties.as_object().cloned(),
+      foreign_members: None,
+    }
+  }
+
+  #[test]
+  fn truncated_field_names_are_unique() {
+    let features = [feature(json!({
+      "Population_2022": "多",
+      "population_2020": 1,
+      "population_2021": 2.5,
+      "名称名称名称": "a",
+    }))];
+    let (fields, _) = infer_fields(&features, encoding_rs::UTF_8);
+    let names: Vec<&str> = fields
+      .iter()
+      .map(|mapping| mapping.field.name.as_str())
+      .collect();
+    assert_eq!(names, ["Population", "populati_1", "populati_2", "名称名"]);
+    assert!(names.iter().all(|name| name.len() <= FIELD_NAME_LENGTH));
+
+    // GBK 下每个汉字占 2 字节
+    let (fields, _) = infer_fields(&features, encoding_rs::GBK);
+    assert_eq!(fields[3].field.name, "名称名称名");
+  }
+
+  #[test]
+  fn truncated_count_counts_values() {
+    let long = "x".repeat(CHARACTER_LENGTH + 1);
+    let features = [
+      feature(json!({ "a": long, "b": long })),
+      feature(json!({ "a": long, "b": "short" })),
+      feature(json!({ "a": "short" })),
+    ];
+    let (_, truncated_count) = infer_fields(&features, encoding_rs::UTF_8);
+    assert_eq!(truncated_count, 3);
+  }
+}
@@ -0,0 +1,183 @@
+use geojson::{Feature, Position, Value};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::shapefile_to_geojson::CustomError;
+use crate::utils;
+
+/// 输出文件格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputFormat {
+  /// 标准 FeatureCollection
+  #[default]
+  Geojson,
+  /// 每行一个 Feature 的 GeoJSONSeq
+  GeojsonSeq,
+}
+
+impl OutputFormat {
+  pub fn extension(&self) -> &'static str {
+    match self {
+      OutputFormat::Geojson => "geojson",
+      OutputFormat::GeojsonSeq => "geojsonl",
+    }
+  }
+}
+
+/// 写入工作空间文件的输出选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutputOptions {
+  pub format: OutputFormat,
+  /// 输出文件名，缺省时使用输入文件名
+  pub file_name: Option<String>,
+}
+
+impl OutputOptions {
+  /// 输出路径固定在工作空间的 geojson 目录下
+  pub fn output_path(&self, input_path: &Path) -> Result<PathBuf, CustomError> {
+    let file_stem = match &self.file_name {
+      // 只取文件名部分，避免写出工作空间
+      Some(name) => Path::new(name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| CustomError(format!("无效的输出文件名: {}", name)))?,
+      None => input_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| CustomError("无法获取文件名".to_string()))?,
+    };
+    let output_dir = utils::files::get_geojson_path();
+    fs::create_dir_all(&output_dir)?;
+    Ok(output_dir.join(format!("{}.{}", file_stem, self.format.extension())))
+  }
+}
+
+/// 逐个写入要素的 GeoJSON 文件，内存占用与要素数量无关
+///
+/// 先写入同目录的 `.part` 临时文件，`finish` 成功后才重命名为目标文件，
+/// 出错或取消时丢弃即可删除临时文件，已有的同名文件保持不变
+pub struct FeatureSink {
+  // 字段按声明顺序释放，先关闭文件再删除临时文件
+  writer: BufWriter<File>,
+  temp_file: TempFile,
+  path: PathBuf,
+  format: OutputFormat,
+  feature_count: usize,
+  bbox: Option<[f64; 4]>,
+}
+
+impl FeatureSink {
+  pub fn create(path: &Path, format: OutputFormat) -> Result<Self, CustomError> {
+    let temp_path = path.with_extension(format!("{}.part", format.extension()));
+    let mut writer = BufWriter::new(File::create(&temp_path)?);
+    let temp_file = TempFile {
+      path: temp_path,
+      persisted: false,
+    };
+    if format == OutputFormat::Geojson {
+      writer.write_all(br#"{"type":"FeatureCollection","features":["#)?;
+    }
+    Ok(FeatureSink {
+      writer,
+      temp_file,
+      path: path.to_path_buf(),
+      format,
+      feature_count: 0,
+      bbox: None,
+    })
+  }
+
+  pub fn write(&mut self, feature: &Feature) -> Result<(), CustomError> {
+    if let Some(geometry) = &feature.geometry {
+      extend_bbox(&mut self.bbox, &geometry.value);
+    }
+    match self.format {
+      OutputFormat::Geojson => {
+        if self.feature_count > 0 {
+          self.writer.write_all(b",")?;
+        }
+        self.writer.write_all(b"\n")?;
+        serde_json::to_writer(&mut self.writer, feature)?;
+      }
+      OutputFormat::GeojsonSeq => {
+        serde_json::to_writer(&mut self.writer, feature)?;
+        self.writer.write_all(b"\n")?;
+      }
+    }
+    self.feature_count += 1;
+    Ok(())
+  }
+
+  /// 写入结尾并返回要素数量与范围 `[minX, minY, maxX, maxY]`
+  pub fn finish(mut self) -> Result<(usize, Option<[f64; 4]>), CustomError> {
+    if self.format == OutputFormat::Geojson {
+      match self.bbox {
+        Some(bbox) => write!(
+          self.writer,
+          "\n],\"bbox\":{}}}\n",
+          serde_json::to_string(&bbox)?
+        )?,
+        None => self.writer.write_all(b"\n]}\n")?,
+      }
+    }
+    self.writer.flush()?;
+    let FeatureSink {
+      writer,
+      mut temp_file,
+      path,
+      feature_count,
+      bbox,
+      ..
+    } = self;
+    // Windows 下需先关闭文件才能重命名
+    drop(writer);
+    fs::rename(&temp_file.path, &path)?;
+    temp_file.persisted = true;
+    Ok((feature_count, bbox))
+  }
+}
+
+/// 未重命名为目标文件时在释放时删除
+struct TempFile {
+  path: PathBuf,
+  persisted: bool,
+}
+
+impl Drop for TempFile {
+  fn drop(&mut self) {
+    if !self.persisted {
+      let _ = fs::remove_file(&self.path);
+    }
+  }
+}
+
+fn extend_bbox(bbox: &mut Option<[f64; 4]>, value: &Value) {
+  let mut extend = |position: &Position| {
+    if position.len() < 2 || !position[0].is_finite() || !position[1].is_finite() {
+      return;
+    }
+    let (x, y) = (position[0], position[1]);
+    let current = bbox.get_or_insert([x, y, x, y]);
+    current[0] = current[0].min(x);
+    current[1] = current[1].min(y);
+    current[2] = current[2].max(x);
+    current[3] = current[3].max(y);
+  };
+  match value {
+    Value::Point(position) => extend(position),
+    Value::MultiPoint(points) | Value::LineString(points) => points.iter().for_each(extend),
+    Value::MultiLineString(lines) | Value::Polygon(lines) => {
+      lines.iter().flatten().for_each(extend)
+    }
+    Value::MultiPolygon(polygons) => polygons.iter().flatten().flatten().for_each(extend),
+    Value::GeometryCollection(geometries) => {
+      for geometry in geometries {
+        extend_bbox(bbox, &geometry.value);
+      }
+    }
+  }
+}
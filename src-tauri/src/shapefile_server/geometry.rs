@@ -0,0 +1,704 @@
+use geojson::{Position, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shapefile::{Patch, Point, PointM, PointZ, PolygonRing, Shape};
+
+use super::shapefile_to_geojson::CustomError;
+use super::simplify::SimplifyOptions;
+
+// shapefile 规范：小于 -10^38 的 M 值表示“无数据”
+const NO_DATA_THRESHOLD: f64 = -1e38;
+
+/// M 值的输出方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MeasureMode {
+  /// 丢弃 M 值
+  Ignore,
+  /// 以属性 `m` 输出，结构与坐标数组一致
+  #[default]
+  Property,
+  /// 作为坐标第四维输出，仅用于带 Z 且每个顶点都有 M 值的图形，其余图形以属性 `m` 输出
+  Ordinate,
+}
+
+/// 属性表中几何列的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GeometryFormat {
+  #[default]
+  Wkt,
+  /// ISO WKB，十六进制字符串
+  Wkb,
+  /// PostGIS EWKB，十六进制字符串
+  Ewkb,
+}
+
+impl GeometryFormat {
+  pub fn column_name(&self) -> &'static str {
+    match self {
+      GeometryFormat::Wkt => "wkt",
+      GeometryFormat::Wkb => "wkb",
+      GeometryFormat::Ewkb => "ewkb",
+    }
+  }
+}
+
+/// 转换选项
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConvertOptions {
+  pub measure: MeasureMode,
+  /// 仅用于 `shapefile_to_record`
+  pub geometry_format: GeometryFormat,
+  /// 以下仅用于 GeoJSON 输出，在投影转换之后处理
+  pub simplify: Option<SimplifyOptions>,
+  /// 坐标保留的小数位数
+  pub precision: Option<u8>,
+}
+
+/// 单个图形的转换结果
+#[derive(Debug, Default)]
+pub struct ShapeGeometry {
+  /// 空图形（NullShape）为 None
+  pub geometry: Option<Value>,
+  /// 以属性输出的 M 值
+  pub measures: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeOutcome {
+  Converted,
+  Null,
+}
+
+/// 单条记录的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordOutcome {
+  pub shape: ShapeOutcome,
+  /// 属性文本解码有损
+  pub lossy: bool,
+}
+
+/// 空图形、跳过的图形与解码有损的记录计数
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionCounts {
+  pub null_count: usize,
+  pub skipped_count: usize,
+  pub lossy_count: usize,
+}
+
+impl ConversionCounts {
+  /// `None` 表示该记录读取或转换失败
+  pub fn add(&mut self, outcome: Option<RecordOutcome>) {
+    let Some(outcome) = outcome else {
+      self.skipped_count += 1;
+      return;
+    };
+    if outcome.shape == ShapeOutcome::Null {
+      self.null_count += 1;
+    }
+    if outcome.lossy {
+      self.lossy_count += 1;
+    }
+  }
+
+  pub fn warnings(&self) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if self.lossy_count > 0 {
+      warnings.push(format!(
+        "{} 条记录的属性解码有损，请检查 .cpg 文件或指定编码",
+        self.lossy_count
+      ));
+    }
+    warnings
+  }
+}
+
+/// 平面坐标，面积与点在环内的计算在 shapefile 点、GeoJSON 坐标与校验用的坐标对之间共用
+pub trait PlanarPoint {
+  fn xy(&self) -> (f64, f64);
+}
+
+impl PlanarPoint for [f64; 2] {
+  fn xy(&self) -> (f64, f64) {
+    (self[0], self[1])
+  }
+}
+
+impl PlanarPoint for Position {
+  fn xy(&self) -> (f64, f64) {
+    (
+      self.first().copied().unwrap_or_default(),
+      self.get(1).copied().unwrap_or_default(),
+    )
+  }
+}
+
+impl<V: Vertex> PlanarPoint for V {
+  fn xy(&self) -> (f64, f64) {
+    (self.x(), self.y())
+  }
+}
+
+trait Vertex {
+  const HAS_Z: bool;
+  const HAS_M: bool;
+  fn x(&self) -> f64;
+  fn y(&self) -> f64;
+  fn z(&self) -> f64 {
+    0.0
+  }
+  fn m(&self) -> Option<f64> {
+    None
+  }
+}
+
+impl Vertex for Point {
+  const HAS_Z: bool = false;
+  const HAS_M: bool = false;
+  fn x(&self) -> f64 {
+    self.x
+  }
+  fn y(&self) -> f64 {
+    self.y
+  }
+}
+
+impl Vertex for PointM {
+  const HAS_Z: bool = false;
+  const HAS_M: bool = true;
+  fn x(&self) -> f64 {
+    self.x
+  }
+  fn y(&self) -> f64 {
+    self.y
+  }
+  fn m(&self) -> Option<f64> {
+    measure_value(self.m)
+  }
+}
+
+impl Vertex for PointZ {
+  const HAS_Z: bool = true;
+  const HAS_M: bool = true;
+  fn x(&self) -> f64 {
+    self.x
+  }
+  fn y(&self) -> f64 {
+    self.y
+  }
+  fn z(&self) -> f64 {
+    self.z
+  }
+  fn m(&self) -> Option<f64> {
+    measure_value(self.m)
+  }
+}
+
+fn measure_value(m: f64) -> Option<f64> {
+  if m.is_nan() || m < NO_DATA_THRESHOLD {
+    None
+  } else {
+    Some(m)
+  }
+}
+
+fn position<V: Vertex>(vertex: &V, mode: MeasureMode) -> Position {
+  let mut position = vec![vertex.x(), vertex.y()];
+  if V::HAS_Z {
+    position.push(vertex.z());
+    if mode == MeasureMode::Ordinate {
+      position.extend(vertex.m());
+    }
+  }
+  position
+}
+
+fn positions<V: Vertex>(points: &[V], mode: MeasureMode) -> Vec<Position> {
+  points.iter().map(|point| position(point, mode)).collect()
+}
+
+fn measures<V: Vertex>(points: &[V]) -> serde_json::Value {
+  points.iter().map(|point| json!(point.m())).collect()
+}
+
+fn wants_measures<V: Vertex>(mode: MeasureMode) -> bool {
+  V::HAS_M && mode == MeasureMode::Property
+}
+
+fn point_geometry<V: Vertex>(point: &V, mode: MeasureMode) -> ShapeGeometry {
+  ShapeGeometry {
+    geometry: Some(Value::Point(position(point, mode))),
+    measures: wants_measures::<V>(mode).then(|| json!(point.m())),
+  }
+}
+
+fn multipoint_geometry<V: Vertex>(points: &[V], mode: MeasureMode) -> ShapeGeometry {
+  ShapeGeometry {
+    geometry: Some(Value::MultiPoint(positions(points, mode))),
+    measures: wants_measures::<V>(mode).then(|| measures(points)),
+  }
+}
+
+fn polyline_geometry<V: Vertex>(parts: &[Vec<V>], mode: MeasureMode) -> ShapeGeometry {
+  let lines = parts.iter().map(|part| positions(part, mode)).collect();
+  ShapeGeometry {
+    geometry: Some(Value::MultiLineString(lines)),
+    measures: wants_measures::<V>(mode).then(|| parts.iter().map(|part| measures(part)).collect()),
+  }
+}
+
+fn polygon_geometry<V: Vertex>(
+  rings: &[PolygonRing<V>],
+  mode: MeasureMode,
+) -> Result<ShapeGeometry, CustomError> {
+  if rings.is_empty() {
+    return Err(CustomError(
+      "Invalid polygon detected - no rings".to_string(),
+    ));
+  }
+  let polygons = assemble_polygons(rings);
+
+  let mut coordinates: Vec<Vec<Vec<Position>>> = Vec::with_capacity(polygons.len());
+  let mut polygon_measures: Vec<serde_json::Value> = Vec::with_capacity(polygons.len());
+  for polygon in &polygons {
+    let mut ring_coordinates = Vec::with_capacity(polygon.len());
+    let mut ring_measures = Vec::with_capacity(polygon.len());
+    for (position_in_polygon, &ring_index) in polygon.iter().enumerate() {
+      let points = rings[ring_index].points();
+      // RFC 7946：外环逆时针，内环顺时针
+      let counter_clockwise = signed_area(points) > 0.0;
+      let reverse = counter_clockwise != (position_in_polygon == 0);
+      let ring = oriented_ring(points, reverse);
+      ring_coordinates.push(ring.iter().map(|point| position(*point, mode)).collect());
+      ring_measures.push(ring.iter().map(|point| json!(point.m())).collect());
+    }
+    coordinates.push(ring_coordinates);
+    polygon_measures.push(serde_json::Value::Array(ring_measures));
+  }
+
+  let with_measures = wants_measures::<V>(mode);
+  let geometry = if coordinates.len() == 1 {
+    ShapeGeometry {
+      geometry: coordinates.pop().map(Value::Polygon),
+      measures: with_measures.then(|| polygon_measures.swap_remove(0)),
+    }
+  } else {
+    ShapeGeometry {
+      geometry: Some(Value::MultiPolygon(coordinates)),
+      measures: with_measures.then_some(serde_json::Value::Array(polygon_measures)),
+    }
+  };
+  Ok(geometry)
+}
+
+/// 按包含关系将环分组为多边形，每组第一个为外环
+///
+/// 内环归属于包含它且面积最小的外环，找不到外环的内环按独立外环处理
+fn assemble_polygons<V: Vertex>(rings: &[PolygonRing<V>]) -> Vec<Vec<usize>> {
+  let mut polygons: Vec<Vec<usize>> = Vec::new();
+  let mut inners = Vec::new();
+  for (index, ring) in rings.iter().enumerate() {
+    match ring {
+      PolygonRing::Outer(_) => polygons.push(vec![index]),
+      PolygonRing::Inner(_) => inners.push(index),
+    }
+  }
+
+  for inner in inners {
+    let hole = rings[inner].points();
+    let owner = polygons
+      .iter_mut()
+      .filter(|polygon| ring_contains(rings[polygon[0]].points(), hole))
+      .min_by(|a, b| {
+        let area_a = signed_area(rings[a[0]].points()).abs();
+        let area_b = signed_area(rings[b[0]].points()).abs();
+        area_a.total_cmp(&area_b)
+      });
+    match owner {
+      Some(polygon) => polygon.push(inner),
+      None => polygons.push(vec![inner]),
+    }
+  }
+  polygons
+}
+
+/// 鞋带公式计算有向面积，逆时针为正，环是否闭合不影响结果
+pub fn signed_area<P: PlanarPoint>(points: &[P]) -> f64 {
+  let sum: f64 = points
+    .iter()
+    .zip(points.iter().cycle().skip(1))
+    .map(|(a, b)| {
+      let ((ax, ay), (bx, by)) = (a.xy(), b.xy());
+      ax * by - bx * ay
+    })
+    .sum();
+  sum / 2.0
+}
+
+fn ring_contains<V: Vertex>(outer: &[V], inner: &[V]) -> bool {
+  let (min_x, min_y, max_x, max_y) = ring_bounds(outer);
+  inner.iter().any(|point| {
+    let (x, y) = (point.x(), point.y());
+    x >= min_x && x <= max_x && y >= min_y && y <= max_y && point_in_ring(outer, x, y)
+  })
+}
+
+fn ring_bounds<V: Vertex>(points: &[V]) -> (f64, f64, f64, f64) {
+  points.iter().fold(
+    (
+      f64::INFINITY,
+      f64::INFINITY,
+      f64::NEG_INFINITY,
+      f64::NEG_INFINITY,
+    ),
+    |(min_x, min_y, max_x, max_y), point| {
+      (
+        min_x.min(point.x()),
+        min_y.min(point.y()),
+        max_x.max(point.x()),
+        max_y.max(point.y()),
+      )
+    },
+  )
+}
+
+/// 射线法，边界上的点视为不在环内
+pub fn point_in_ring<P: PlanarPoint>(ring: &[P], x: f64, y: f64) -> bool {
+  let mut inside = false;
+  for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+    let ((ax, ay), (bx, by)) = (a.xy(), b.xy());
+    if (ay > y) != (by > y) && x < (bx - ax) * (y - ay) / (by - ay) + ax {
+      inside = !inside;
+    }
+  }
+  inside
+}
+
+/// 返回闭合且按需反转的环
+fn oriented_ring<V: Vertex>(points: &[V], reverse: bool) -> Vec<&V> {
+  let mut ring: Vec<&V> = if reverse {
+    points.iter().rev().collect()
+  } else {
+    points.iter().collect()
+  };
+  if let (Some(&first), Some(&last)) = (ring.first(), ring.last()) {
+    if first.x() != last.x() || first.y() != last.y() {
+      ring.push(first);
+    }
+  }
+  ring
+}
+
+// Multipatch 的三角带、三角扇拆成三角形，环按 Outer/First 开始新多边形、Inner/Ring 作为洞
+fn multipatch_geometry(patches: &[Patch], mode: MeasureMode) -> ShapeGeometry {
+  let mut polygons: Vec<Vec<Vec<PointZ>>> = Vec::new();
+  for patch in patches {
+    match patch {
+      Patch::TriangleStrip(points) => {
+        for window in points.windows(3) {
+          polygons.push(vec![closed_triangle(&window[0], &window[1], &window[2])]);
+        }
+      }
+      Patch::TriangleFan(points) => {
+        if let Some((first, rest)) = points.split_first() {
+          for window in rest.windows(2) {
+            polygons.push(vec![closed_triangle(first, &window[0], &window[1])]);
+          }
+        }
+      }
+      Patch::OuterRing(points) | Patch::FirstRing(points) => polygons.push(vec![points.clone()]),
+      Patch::InnerRing(points) | Patch::Ring(points) => match polygons.last_mut() {
+        Some(polygon) => polygon.push(points.clone()),
+        None => polygons.push(vec![points.clone()]),
+      },
+    }
+  }
+
+  let coordinates = polygons
+    .iter()
+    .map(|polygon| polygon.iter().map(|ring| positions(ring, mode)).collect())
+    .collect();
+  ShapeGeometry {
+    geometry: Some(Value::MultiPolygon(coordinates)),
+    measures: wants_measures::<PointZ>(mode).then(|| {
+      polygons
+        .iter()
+        .map(|polygon| {
+          polygon
+            .iter()
+            .map(|ring| measures(ring))
+            .collect::<serde_json::Value>()
+        })
+        .collect()
+    }),
+  }
+}
+
+fn closed_triangle(a: &PointZ, b: &PointZ, c: &PointZ) -> Vec<PointZ> {
+  vec![*a, *b, *c, *a]
+}
+
+/// 将任意类型的 shapefile 图形转换为 GeoJSON 几何，Z 值作为第三维保留
+pub fn shape_to_geometry(
+  shape: &Shape,
+  options: &ConvertOptions,
+) -> Result<ShapeGeometry, CustomError> {
+  // GeoJSON 坐标的第三维只能是 Z，无法放入第四维的 M 值改为以属性输出
+  let mode = match options.measure {
+    MeasureMode::Ordinate if !has_ordinate_measures(shape) => MeasureMode::Property,
+    mode => mode,
+  };
+  let geometry = match shape {
+    Shape::NullShape => ShapeGeometry::default(),
+    Shape::Point(point) => point_geometry(point, mode),
+    Shape::PointM(point) => point_geometry(point, mode),
+    Shape::PointZ(point) => point_geometry(point, mode),
+    Shape::Multipoint(multipoint) => multipoint_geometry(multipoint.points(), mode),
+    Shape::MultipointM(multipoint) => multipoint_geometry(multipoint.points(), mode),
+    Shape::MultipointZ(multipoint) => multipoint_geometry(multipoint.points(), mode),
+    Shape::Polyline(polyline) => polyline_geometry(polyline.parts(), mode),
+    Shape::PolylineM(polyline) => polyline_geometry(polyline.parts(), mode),
+    Shape::PolylineZ(polyline) => polyline_geometry(polyline.parts(), mode),
+    Shape::Polygon(polygon) => polygon_geometry(polygon.rings(), mode)?,
+    Shape::PolygonM(polygon) => polygon_geometry(polygon.rings(), mode)?,
+    Shape::PolygonZ(polygon) => polygon_geometry(polygon.rings(), mode)?,
+    Shape::Multipatch(multipatch) => multipatch_geometry(multipatch.patches(), mode),
+  };
+  Ok(geometry)
+}
+
+fn has_ordinate_measures(shape: &Shape) -> bool {
+  let has_measure = |point: &PointZ| point.m().is_some();
+  match shape {
+    Shape::PointZ(point) => has_measure(point),
+    Shape::MultipointZ(multipoint) => multipoint.points().iter().all(has_measure),
+    Shape::PolylineZ(polyline) => polyline.parts().iter().flatten().all(has_measure),
+    Shape::PolygonZ(polygon) => polygon
+      .rings()
+      .iter()
+      .flat_map(|ring| ring.points())
+      .all(has_measure),
+    Shape::Multipatch(multipatch) => multipatch
+      .patches()
+      .iter()
+      .flat_map(|patch| patch.points())
+      .all(has_measure),
+    _ => false,
+  }
+}
+
+/// 单部件的 MultiLineString 拆为 LineString，M 值同步展开
+pub fn collapse_single_line(shape_geometry: ShapeGeometry) -> ShapeGeometry {
+  match shape_geometry.geometry {
+    Some(Value::MultiLineString(mut lines)) if lines.len() == 1 => ShapeGeometry {
+      geometry: lines.pop().map(Value::LineString),
+      measures: shape_geometry.measures.map(|measures| match measures {
+        serde_json::Value::Array(mut parts) if parts.len() == 1 => parts.swap_remove(0),
+        other => other,
+      }),
+    },
+    geometry => ShapeGeometry {
+      geometry,
+      ..shape_geometry
+    },
+  }
+}
+
+/// 将 GeoJSON 几何输出为 WKT，三维坐标输出为 `Z`，四维输出为 `ZM`
+pub fn geometry_to_wkt(value: &Value) -> String {
+  let (name, body) = match value {
+    Value::Point(position) => ("POINT", wkt_position(position)),
+    Value::MultiPoint(points) => (
+      "MULTIPOINT",
+      wkt_list(points, |p| format!("({})", wkt_position(p))),
+    ),
+    Value::LineString(line) => ("LINESTRING", wkt_positions(line)),
+    Value::MultiLineString(lines) => ("MULTILINESTRING", wkt_list(lines, |l| wkt_positions(l))),
+    Value::Polygon(rings) => ("POLYGON", wkt_list(rings, |r| wkt_positions(r))),
+    Value::MultiPolygon(polygons) => (
+      "MULTIPOLYGON",
+      wkt_list(polygons, |rings| wkt_list(rings, |r| wkt_positions(r))),
+    ),
+    Value::GeometryCollection(geometries) => {
+      let members: Vec<String> = geometries
+        .iter()
+        .map(|g| geometry_to_wkt(&g.value))
+        .collect();
+      if members.is_empty() {
+        return "GEOMETRYCOLLECTION EMPTY".to_string();
+      }
+      return format!("GEOMETRYCOLLECTION ({})", members.join(", "));
+    }
+  };
+
+  let dimension = match coordinate_dimension(value) {
+    3 => " Z",
+    4 => " ZM",
+    _ => "",
+  };
+  if body.is_empty() {
+    format!("{} EMPTY", name)
+  } else if matches!(value, Value::Point(_)) {
+    format!("{}{} ({})", name, dimension, body)
+  } else {
+    format!("{}{} {}", name, dimension, body)
+  }
+}
+
+fn wkt_position(position: &Position) -> String {
+  position
+    .iter()
+    .map(|ordinate| ordinate.to_string())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn wkt_positions(positions: &[Position]) -> String {
+  wkt_list(positions, wkt_position)
+}
+
+fn wkt_list<T, F>(items: &[T], format_item: F) -> String
+where
+  F: Fn(&T) -> String,
+{
+  if items.is_empty() {
+    return String::new();
+  }
+  let items: Vec<String> = items.iter().map(format_item).collect();
+  format!("({})", items.join(", "))
+}
+
+/// 坐标维数，由第一个坐标决定，空几何为 2
+pub fn coordinate_dimension(value: &Value) -> usize {
+  first_position(value).map_or(2, |position| position.len().max(2))
+}
+
+fn first_position(value: &Value) -> Option<&Position> {
+  match value {
+    Value::Point(position) => Some(position),
+    Value::MultiPoint(points) | Value::LineString(points) => points.first(),
+    Value::MultiLineString(lines) | Value::Polygon(lines) => lines.iter().flatten().next(),
+    Value::MultiPolygon(polygons) => polygons.iter().flatten().flatten().next(),
+    Value::GeometryCollection(_) => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // shapefile 中外环为顺时针，内环为逆时针
+  fn square(min: f64, max: f64, clockwise: bool) -> Vec<Point> {
+    let mut points = vec![
+      Point::new(min, min),
+      Point::new(max, min),
+      Point::new(max, max),
+      Point::new(min, max),
+      Point::new(min, min),
+    ];
+    if clockwise {
+      points.reverse();
+    }
+    points
+  }
+
+  fn position_area(ring: &[Position]) -> f64 {
+    let points: Vec<Point> = ring.iter().map(|p| Point::new(p[0], p[1])).collect();
+    signed_area(&points)
+  }
+
+  // 不经过 `Polygon::with_rings`，它会按环类型调整方向
+  fn polygon_value(rings: &[PolygonRing<Point>]) -> Value {
+    polygon_geometry(rings, MeasureMode::Ignore)
+      .unwrap()
+      .geometry
+      .unwrap()
+  }
+
+  #[test]
+  fn hole_belongs_to_enclosing_outer() {
+    let rings = vec![
+      PolygonRing::Inner(square(2.0, 4.0, false)),
+      PolygonRing::Outer(square(0.0, 10.0, true)),
+    ];
+    assert_eq!(assemble_polygons(&rings), vec![vec![1, 0]]);
+  }
+
+  #[test]
+  fn disjoint_outers_become_multipolygon() {
+    let rings = vec![
+      PolygonRing::Outer(square(0.0, 1.0, true)),
+      PolygonRing::Outer(square(5.0, 6.0, true)),
+      PolygonRing::Inner(square(5.2, 5.8, false)),
+    ];
+    assert_eq!(assemble_polygons(&rings), vec![vec![0], vec![1, 2]]);
+
+    match polygon_value(&rings) {
+      Value::MultiPolygon(polygons) => {
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0].len(), 1);
+        assert_eq!(polygons[1].len(), 2);
+      }
+      value => panic!("应为 MultiPolygon: {:?}", value),
+    }
+  }
+
+  #[test]
+  fn island_inside_hole_is_separate_polygon() {
+    let rings = vec![
+      PolygonRing::Outer(square(0.0, 10.0, true)),
+      PolygonRing::Outer(square(4.0, 6.0, true)),
+      PolygonRing::Inner(square(2.0, 8.0, false)),
+    ];
+    assert!(ring_contains(rings[0].points(), rings[2].points()));
+    assert!(!ring_contains(rings[1].points(), rings[2].points()));
+    assert_eq!(assemble_polygons(&rings), vec![vec![0, 2], vec![1]]);
+  }
+
+  #[test]
+  fn hole_uses_smallest_containing_outer() {
+    let rings = vec![
+      PolygonRing::Outer(square(0.0, 10.0, true)),
+      PolygonRing::Inner(square(1.0, 9.0, false)),
+      PolygonRing::Outer(square(2.0, 8.0, true)),
+      PolygonRing::Inner(square(3.0, 7.0, false)),
+    ];
+    assert_eq!(assemble_polygons(&rings), vec![vec![0, 1], vec![2, 3]]);
+  }
+
+  #[test]
+  fn reversed_winding_is_normalized() {
+    // 外环逆时针、内环顺时针，与 shapefile 规范相反
+    let rings = vec![
+      PolygonRing::Outer(square(0.0, 10.0, false)),
+      PolygonRing::Inner(square(2.0, 4.0, true)),
+    ];
+    match polygon_value(&rings) {
+      Value::Polygon(polygon) => {
+        assert_eq!(polygon.len(), 2);
+        assert!(position_area(&polygon[0]) > 0.0);
+        assert!(position_area(&polygon[1]) < 0.0);
+      }
+      value => panic!("应为 Polygon: {:?}", value),
+    }
+
+    let clockwise = square(0.0, 10.0, true);
+    assert!(signed_area(&clockwise) < 0.0);
+    let reversed: Vec<Point> = oriented_ring(&clockwise, true)
+      .into_iter()
+      .copied()
+      .collect();
+    assert!(signed_area(&reversed) > 0.0);
+  }
+
+  #[test]
+  fn oriented_ring_closes_open_ring() {
+    let open = &square(0.0, 1.0, false)[..4];
+    let ring = oriented_ring(open, false);
+    assert_eq!(ring.len(), 5);
+    assert_eq!(ring.first(), ring.last());
+  }
+}
@@ -1,2 +1,23 @@
+pub mod archive;
+pub mod dataset_info;
+mod dbf;
+pub mod dbf_edit;
+pub mod dbf_schema;
+mod encoding;
+pub mod field_stats;
+pub mod geojson_to_shapefile;
+pub mod geojson_writer;
+pub mod geometry;
+mod ogr;
+mod projection;
+mod raw_shape;
+pub mod record_query;
+pub mod repair;
+mod schema;
 mod shapefile_to_geojson;
+pub mod simplify;
+mod spatial_index;
 pub mod utilities;
+pub mod validation;
+mod wkb;
+mod writer;
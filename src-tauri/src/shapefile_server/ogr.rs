@@ -0,0 +1,239 @@
+use gdal::vector::{field_type_to_name, geometry_type_to_name, FieldValue, Layer, LayerAccess};
+use gdal::{Dataset, DatasetOptions, GdalOpenFlags};
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection, Geometry};
+use serde::Serialize;
+use serde_json::json;
+
+use super::encoding::{DetectedEncoding, EncodingSource};
+use super::geometry::{ConversionCounts, ConvertOptions, RecordOutcome, ShapeOutcome};
+use super::projection::{crs_from_definition, Reprojector, SourceCrs, DEFAULT_TARGET_CRS};
+use super::shapefile_to_geojson::{CustomError, GeojsonConversion};
+use super::simplify::simplify_features;
+use crate::utils::progress::{ProgressPhase, ProgressReporter};
+
+/// 数据源中的图层
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerInfo {
+  pub name: String,
+  /// 驱动无法快速统计时为空
+  pub feature_count: Option<u64>,
+  pub geometry_type: String,
+  pub fields: Vec<LayerField>,
+  /// 图层没有坐标系或坐标系无法识别时为空
+  pub crs: Option<SourceCrs>,
+  /// `[minX, minY, maxX, maxY]`，驱动无法快速计算时为空
+  pub extent: Option<[f64; 4]>,
+  /// 转换到经纬度后的范围，缺少坐标系或无法转换时为空
+  pub extent_wgs84: Option<[f64; 4]>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerField {
+  pub name: String,
+  pub field_type: String,
+  pub width: i32,
+  pub precision: i32,
+}
+
+/// 以只读方式打开 OGR 支持的矢量数据源，如 GeoPackage、FileGDB、KML、GPX、CSV、DXF
+pub fn open_vector(path: &str) -> Result<Dataset, CustomError> {
+  Dataset::open_ex(
+    path,
+    DatasetOptions {
+      open_flags: GdalOpenFlags::GDAL_OF_VECTOR | GdalOpenFlags::GDAL_OF_READONLY,
+      ..DatasetOptions::default()
+    },
+  )
+  .map_err(|e| CustomError(format!("无法打开矢量数据 {}: {}", path, e)))
+}
+
+/// 列出数据源的驱动与图层
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn list_layers(path: &str) -> Result<(String, Vec<LayerInfo>), CustomError> {
+  let dataset = open_vector(path)?;
+  let driver = dataset.driver().short_name();
+  let layers = dataset.layers().map(|layer| layer_info(&layer)).collect();
+  Ok((driver, layers))
+}
+
+/// 图层的概要，无法读取的项为空，不返回错误
+pub(super) fn layer_info(layer: &Layer) -> LayerInfo {
+  let defn = layer.defn();
+  let fields = defn
+    .fields()
+    .map(|field| LayerField {
+      name: field.name(),
+      field_type: field_type_to_name(field.field_type()),
+      width: field.width(),
+      precision: field.precision(),
+    })
+    .collect();
+  let geometry_type = defn
+    .geom_fields()
+    .next()
+    .map(|field| geometry_type_to_name(field.field_type()))
+    .unwrap_or_else(|| "None".to_string());
+  // 单个图层的坐标系无法识别时不影响其他图层
+  let crs = layer
+    .spatial_ref()
+    .and_then(|spatial_ref| spatial_ref.to_wkt().ok())
+    .and_then(|wkt| {
+      crs_from_definition(&wkt)
+        .inspect_err(|e| log::warn!("无法识别图层 {} 的坐标系: {}", layer.name(), e))
+        .ok()
+    });
+  let extent = layer
+    .try_get_extent()
+    .ok()
+    .flatten()
+    .map(|envelope| [envelope.MinX, envelope.MinY, envelope.MaxX, envelope.MaxY]);
+  let extent_wgs84 = match (&crs, extent) {
+    (Some(crs), Some(extent)) => wgs84_extent(crs, extent),
+    _ => None,
+  };
+
+  LayerInfo {
+    name: layer.name(),
+    feature_count: layer.try_feature_count(),
+    geometry_type,
+    fields,
+    crs,
+    extent,
+    extent_wgs84,
+  }
+}
+
+/// 将数据坐标系下的范围转换为经纬度
+pub(super) fn wgs84_extent(crs: &SourceCrs, extent: [f64; 4]) -> Option<[f64; 4]> {
+  Reprojector::new(crs, DEFAULT_TARGET_CRS)
+    .and_then(|reprojector| reprojector.transform_bbox(extent))
+    .ok()
+}
+
+/// 读取图层并转换为 GeoJSON，缺省读取第一个图层
+///
+/// 图层有几何但没有坐标系时需指定 `source_crs`。OGR 导出的几何不含 M 值，`options.measure` 不起作用。
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn convert_layer_to_geojson(
+  path: &str,
+  layer_name: Option<&str>,
+  options: ConvertOptions,
+  source_crs: Option<&str>,
+  target_crs: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<GeojsonConversion, CustomError> {
+  let dataset = open_vector(path)?;
+  let mut layer = match layer_name {
+    Some(name) => dataset
+      .layer_by_name(name)
+      .map_err(|_| CustomError(format!("图层不存在: {}", name)))?,
+    None => dataset
+      .layer(0)
+      .map_err(|_| CustomError("数据源中没有图层".to_string()))?,
+  };
+  let target_crs = target_crs.unwrap_or(DEFAULT_TARGET_CRS).to_string();
+  let has_geometry = layer.defn().geom_fields().next().is_some();
+  let source_crs = match (source_crs, layer.spatial_ref()) {
+    (Some(definition), _) => crs_from_definition(definition)?,
+    (None, Some(spatial_ref)) => crs_from_definition(&spatial_ref.to_wkt()?)?,
+    // 纯属性表（如不含坐标列的 CSV）不需要投影转换，按目标坐标系处理
+    (None, None) if !has_geometry => crs_from_definition(&target_crs)?,
+    (None, None) => {
+      return Err(CustomError(format!(
+        "图层 {} 没有坐标系，请指定源坐标系",
+        layer.name()
+      )))
+    }
+  };
+
+  progress.start_phase(ProgressPhase::Converting, layer.try_feature_count());
+  let mut features = Vec::new();
+  let mut counts = ConversionCounts::default();
+  for (index, feature) in layer.features().enumerate() {
+    if progress.is_cancelled() {
+      return Err(CustomError("任务已取消".to_string()));
+    }
+    progress.inc(1);
+    let geometry = match feature.geometry() {
+      Some(geometry) => {
+        let parsed = geometry
+          .json()
+          .ok()
+          .and_then(|json| serde_json::from_str::<Geometry>(&json).ok());
+        let Some(parsed) = parsed else {
+          log::warn!("跳过无法转换的图形: 要素 {}", index);
+          counts.add(None);
+          continue;
+        };
+        Some(parsed)
+      }
+      None => None,
+    };
+
+    let mut properties = serde_json::Map::new();
+    for (name, value) in feature.fields() {
+      properties.insert(name, field_value_to_json(value));
+    }
+    counts.add(Some(RecordOutcome {
+      shape: match geometry {
+        Some(_) => ShapeOutcome::Converted,
+        None => ShapeOutcome::Null,
+      },
+      lossy: false,
+    }));
+    features.push(Feature {
+      bbox: None,
+      geometry,
+      // 没有 FID 的驱动使用从 0 开始的序号
+      id: Some(Id::Number(feature.fid().unwrap_or(index as u64).into())),
+      properties: Some(properties),
+      foreign_members: None,
+    });
+  }
+
+  progress.start_phase(ProgressPhase::Reprojecting, Some(features.len() as u64));
+  Reprojector::new(&source_crs, &target_crs)?.transform_features(&mut features)?;
+  let vertex_counts = simplify_features(&mut features, &options)?;
+  progress.finish();
+
+  Ok(GeojsonConversion {
+    feature_collection: FeatureCollection {
+      bbox: None,
+      features,
+      foreign_members: None,
+    },
+    counts,
+    vertex_counts,
+    // OGR 读取时已将文本转换为 UTF-8
+    encoding: DetectedEncoding {
+      encoding: encoding_rs::UTF_8,
+      source: EncodingSource::Default,
+    },
+    source_crs,
+    target_crs,
+  })
+}
+
+/// 日期输出为 ISO-8601 字符串，NaN 与无穷大按空值处理
+fn field_value_to_json(value: Option<FieldValue>) -> serde_json::Value {
+  let Some(value) = value else {
+    return serde_json::Value::Null;
+  };
+  match value {
+    FieldValue::IntegerValue(value) => json!(value),
+    FieldValue::IntegerListValue(values) => json!(values),
+    FieldValue::Integer64Value(value) => json!(value),
+    FieldValue::Integer64ListValue(values) => json!(values),
+    FieldValue::StringValue(value) => json!(value),
+    FieldValue::StringListValue(values) => json!(values),
+    FieldValue::RealValue(value) if value.is_finite() => json!(value),
+    FieldValue::RealValue(_) => serde_json::Value::Null,
+    FieldValue::RealListValue(values) => json!(values),
+    FieldValue::DateValue(date) => json!(date.to_string()),
+    FieldValue::DateTimeValue(date_time) => json!(date_time.to_rfc3339()),
+  }
+}
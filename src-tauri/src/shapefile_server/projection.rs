@@ -0,0 +1,182 @@
+use gdal::spatial_ref::{AxisMappingStrategy, CoordTransform, SpatialRef};
+use geojson::{Feature, Position, Value};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use super::shapefile_to_geojson::CustomError;
+
+/// 地图使用的默认坐标系
+pub const DEFAULT_TARGET_CRS: &str = "EPSG:4326";
+
+// 转换范围时每条边的采样段数
+const BBOX_DENSIFY_STEPS: usize = 20;
+
+/// `.prj` 中读取的源坐标系
+///
+/// 只保存文本，gdal 的 `SpatialRef` 不能跨线程传递，需要时再解析
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceCrs {
+  pub name: Option<String>,
+  pub epsg: Option<i32>,
+  pub wkt: String,
+}
+
+impl From<gdal::errors::GdalError> for CustomError {
+  fn from(err: gdal::errors::GdalError) -> Self {
+    CustomError(err.to_string())
+  }
+}
+
+/// 读取并识别 `.prj`，文件缺失时返回错误
+pub fn read_source_crs(prj_path: &Path) -> Result<SourceCrs, CustomError> {
+  let definition = fs::read_to_string(prj_path).map_err(|_| {
+    CustomError(format!(
+      "缺少 .prj 文件，无法确定坐标系: {}",
+      prj_path.display()
+    ))
+  })?;
+  crs_from_definition(definition.trim())
+    .map_err(|e| CustomError(format!("无法解析 .prj 文件: {}", e)))
+}
+
+/// 识别 `EPSG:xxxx`、WKT、PROJ 字符串等 gdal 可识别的定义
+pub fn crs_from_definition(definition: &str) -> Result<SourceCrs, CustomError> {
+  let mut spatial_ref = parse_definition(definition)?;
+  // 识别失败时仍可使用原始定义进行转换
+  let _ = spatial_ref.auto_identify_epsg();
+
+  Ok(SourceCrs {
+    name: spatial_ref.name().ok(),
+    epsg: spatial_ref.auth_code().ok(),
+    wkt: spatial_ref.to_wkt()?,
+  })
+}
+
+/// 坐标转换器，源与目标坐标系相同时不做任何处理
+pub struct Reprojector {
+  transform: Option<CoordTransform>,
+}
+
+impl Reprojector {
+  /// `target` 支持 `EPSG:xxxx`、WKT、PROJ 字符串等 gdal 可识别的定义
+  pub fn new(source: &SourceCrs, target: &str) -> Result<Self, CustomError> {
+    let source_ref = SpatialRef::from_wkt(&source.wkt)?;
+    Self::from_refs(source_ref, parse_definition(target)?)
+  }
+
+  /// 从 `target` 转回源坐标系，用于将查询范围转换到数据坐标系
+  pub fn inverse(source: &SourceCrs, target: &str) -> Result<Self, CustomError> {
+    let source_ref = SpatialRef::from_wkt(&source.wkt)?;
+    Self::from_refs(parse_definition(target)?, source_ref)
+  }
+
+  fn from_refs(
+    mut source_ref: SpatialRef,
+    mut target_ref: SpatialRef,
+  ) -> Result<Self, CustomError> {
+    if source_ref == target_ref {
+      return Ok(Reprojector { transform: None });
+    }
+    // GeoJSON 始终为 经度/纬度 顺序
+    source_ref.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    target_ref.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    Ok(Reprojector {
+      transform: Some(CoordTransform::new(&source_ref, &target_ref)?),
+    })
+  }
+
+  /// 转换范围 `[minX, minY, maxX, maxY]`，沿四条边加密采样，保证弯曲的边界仍被包含
+  pub fn transform_bbox(&self, bbox: [f64; 4]) -> Result<[f64; 4], CustomError> {
+    let Some(transform) = &self.transform else {
+      return Ok(bbox);
+    };
+    let (mut xs, mut ys) = (Vec::new(), Vec::new());
+    for step in 0..=BBOX_DENSIFY_STEPS {
+      let t = step as f64 / BBOX_DENSIFY_STEPS as f64;
+      let x = bbox[0] + (bbox[2] - bbox[0]) * t;
+      let y = bbox[1] + (bbox[3] - bbox[1]) * t;
+      xs.extend([x, x, bbox[0], bbox[2]]);
+      ys.extend([bbox[1], bbox[3], y, y]);
+    }
+    transform.transform_coords(&mut xs, &mut ys, &mut [])?;
+
+    let mut result: Option<[f64; 4]> = None;
+    for (x, y) in xs.into_iter().zip(ys) {
+      if !x.is_finite() || !y.is_finite() {
+        continue;
+      }
+      let current = result.get_or_insert([x, y, x, y]);
+      current[0] = current[0].min(x);
+      current[1] = current[1].min(y);
+      current[2] = current[2].max(x);
+      current[3] = current[3].max(y);
+    }
+    result.ok_or_else(|| CustomError("查询范围无法转换到数据坐标系".to_string()))
+  }
+
+  pub fn transform_features(&self, features: &mut [Feature]) -> Result<(), CustomError> {
+    if self.transform.is_none() {
+      return Ok(());
+    }
+    for feature in features.iter_mut() {
+      if let Some(geometry) = feature.geometry.as_mut() {
+        self.transform_value(&mut geometry.value)?;
+      }
+    }
+    Ok(())
+  }
+
+  pub fn transform_value(&self, value: &mut Value) -> Result<(), CustomError> {
+    let Some(transform) = &self.transform else {
+      return Ok(());
+    };
+    let mut positions = Vec::new();
+    collect_positions(value, &mut positions);
+    positions.retain(|position| position.len() >= 2);
+    if positions.is_empty() {
+      return Ok(());
+    }
+
+    let has_z = positions.iter().all(|position| position.len() >= 3);
+    let mut xs: Vec<f64> = positions.iter().map(|position| position[0]).collect();
+    let mut ys: Vec<f64> = positions.iter().map(|position| position[1]).collect();
+    let mut zs: Vec<f64> = if has_z {
+      positions.iter().map(|position| position[2]).collect()
+    } else {
+      Vec::new()
+    };
+    transform.transform_coords(&mut xs, &mut ys, &mut zs)?;
+
+    for (index, position) in positions.into_iter().enumerate() {
+      position[0] = xs[index];
+      position[1] = ys[index];
+      if has_z {
+        position[2] = zs[index];
+      }
+    }
+    Ok(())
+  }
+}
+
+fn parse_definition(definition: &str) -> Result<SpatialRef, CustomError> {
+  SpatialRef::from_definition(definition)
+    .map_err(|e| CustomError(format!("无法识别坐标系 {}: {}", definition, e)))
+}
+
+fn collect_positions<'a>(value: &'a mut Value, positions: &mut Vec<&'a mut Position>) {
+  match value {
+    Value::Point(position) => positions.push(position),
+    Value::MultiPoint(points) | Value::LineString(points) => positions.extend(points.iter_mut()),
+    Value::MultiLineString(lines) | Value::Polygon(lines) => {
+      positions.extend(lines.iter_mut().flatten())
+    }
+    Value::MultiPolygon(polygons) => positions.extend(polygons.iter_mut().flatten().flatten()),
+    Value::GeometryCollection(geometries) => {
+      for geometry in geometries.iter_mut() {
+        collect_positions(&mut geometry.value, positions);
+      }
+    }
+  }
+}
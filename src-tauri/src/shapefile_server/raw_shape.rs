@@ -0,0 +1,241 @@
+use std::ops::Range;
+
+/// 直接从 `.shp` 记录内容解析的图形，坐标保持原样，用于检查与修复
+///
+/// `z`、`m` 为空表示该类型没有对应的值
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawShape {
+  pub shape_type: i32,
+  /// 记录中保存的范围，点类型没有
+  pub bbox: Option<[f64; 4]>,
+  /// 每个部件第一个点的序号
+  pub parts: Vec<i32>,
+  /// Multipatch 的部件类型
+  pub part_types: Vec<i32>,
+  pub points: Vec<[f64; 2]>,
+  pub z: Vec<f64>,
+  pub m: Vec<f64>,
+}
+
+impl RawShape {
+  /// 解析一条记录的内容（不含 8 字节记录头）
+  pub fn parse(content: &[u8]) -> Result<Self, String> {
+    let mut reader = ContentReader { content, offset: 0 };
+    let shape_type = reader.i32()?;
+    let mut shape = RawShape {
+      shape_type,
+      bbox: None,
+      parts: Vec::new(),
+      part_types: Vec::new(),
+      points: Vec::new(),
+      z: Vec::new(),
+      m: Vec::new(),
+    };
+    match shape_type {
+      0 => {}
+      1 | 11 | 21 => {
+        shape.points.push([reader.f64()?, reader.f64()?]);
+        if shape_type == 11 {
+          shape.z.push(reader.f64()?);
+        }
+        // PointZ 的 M 值可省略
+        if shape_type == 21 || (shape_type == 11 && reader.remaining() >= 8) {
+          shape.m.push(reader.f64()?);
+        }
+      }
+      8 | 18 | 28 => {
+        shape.bbox = Some(reader.bbox()?);
+        let num_points = reader.count(16)?;
+        shape.points = reader.points(num_points)?;
+        shape.read_measures(&mut reader, num_points)?;
+      }
+      3 | 5 | 13 | 15 | 23 | 25 | 31 => {
+        shape.bbox = Some(reader.bbox()?);
+        let num_parts = reader.count(4)?;
+        let num_points = reader.count(16)?;
+        shape.parts = (0..num_parts)
+          .map(|_| reader.i32())
+          .collect::<Result<_, _>>()?;
+        if shape_type == 31 {
+          shape.part_types = (0..num_parts)
+            .map(|_| reader.i32())
+            .collect::<Result<_, _>>()?;
+        }
+        shape.points = reader.points(num_points)?;
+        shape.read_measures(&mut reader, num_points)?;
+      }
+      other => return Err(format!("未知的图形类型: {}", other)),
+    }
+    Ok(shape)
+  }
+
+  fn read_measures(&mut self, reader: &mut ContentReader, num_points: usize) -> Result<(), String> {
+    if matches!(self.shape_type, 13 | 15 | 18 | 31) {
+      reader.skip(16)?;
+      self.z = (0..num_points)
+        .map(|_| reader.f64())
+        .collect::<Result<_, _>>()?;
+    }
+    // Z 类型的 M 值可省略
+    let has_m = matches!(self.shape_type, 23 | 25 | 28)
+      || (matches!(self.shape_type, 13 | 15 | 18 | 31)
+        && reader.remaining() >= 16 + num_points * 8);
+    if has_m {
+      reader.skip(16)?;
+      self.m = (0..num_points)
+        .map(|_| reader.f64())
+        .collect::<Result<_, _>>()?;
+    }
+    Ok(())
+  }
+
+  pub fn is_null(&self) -> bool {
+    self.shape_type == 0
+  }
+
+  /// 各部件的点序号范围，部件序号越界时截断到有效范围
+  pub fn part_ranges(&self) -> Vec<Range<usize>> {
+    let len = self.points.len();
+    let starts: Vec<usize> = self
+      .parts
+      .iter()
+      .map(|&start| (start.max(0) as usize).min(len))
+      .collect();
+    starts
+      .iter()
+      .enumerate()
+      .map(|(index, &start)| {
+        let end = starts.get(index + 1).copied().unwrap_or(len).max(start);
+        start..end
+      })
+      .collect()
+  }
+
+  /// 编码为记录内容，范围按当前坐标重新计算
+  pub fn encode(&self) -> Vec<u8> {
+    let mut content = Vec::new();
+    content.extend(self.shape_type.to_le_bytes());
+    let put = |content: &mut Vec<u8>, values: &[f64]| {
+      for value in values {
+        content.extend(value.to_le_bytes());
+      }
+    };
+    match self.shape_type {
+      0 => {}
+      1 | 11 | 21 => {
+        put(
+          &mut content,
+          self
+            .points
+            .first()
+            .map_or(&[0.0; 2][..], |point| &point[..]),
+        );
+        put(&mut content, self.z.get(..1).unwrap_or_default());
+        put(&mut content, self.m.get(..1).unwrap_or_default());
+      }
+      _ => {
+        put(&mut content, &self.computed_bbox().unwrap_or_default());
+        let is_multipoint = matches!(self.shape_type, 8 | 18 | 28);
+        if !is_multipoint {
+          content.extend((self.parts.len() as i32).to_le_bytes());
+        }
+        content.extend((self.points.len() as i32).to_le_bytes());
+        if !is_multipoint {
+          for part in self.parts.iter().chain(&self.part_types) {
+            content.extend(part.to_le_bytes());
+          }
+        }
+        for point in &self.points {
+          put(&mut content, point);
+        }
+        for values in [&self.z, &self.m] {
+          if !values.is_empty() {
+            put(&mut content, &value_range(values));
+            put(&mut content, values);
+          }
+        }
+      }
+    }
+    content
+  }
+
+  /// 由坐标计算的范围，没有点时返回 `None`
+  pub fn computed_bbox(&self) -> Option<[f64; 4]> {
+    self.points.iter().fold(None, |bbox, point| {
+      let [x, y] = *point;
+      Some(match bbox {
+        None => [x, y, x, y],
+        Some([min_x, min_y, max_x, max_y]) => {
+          [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+        }
+      })
+    })
+  }
+}
+
+struct ContentReader<'a> {
+  content: &'a [u8],
+  offset: usize,
+}
+
+impl ContentReader<'_> {
+  fn take(&mut self, len: usize) -> Result<&[u8], String> {
+    let end = self.offset + len;
+    let bytes = self
+      .content
+      .get(self.offset..end)
+      .ok_or_else(|| "记录内容不完整".to_string())?;
+    self.offset = end;
+    Ok(bytes)
+  }
+
+  fn remaining(&self) -> usize {
+    self.content.len().saturating_sub(self.offset)
+  }
+
+  fn skip(&mut self, len: usize) -> Result<(), String> {
+    self.take(len).map(|_| ())
+  }
+
+  fn i32(&mut self) -> Result<i32, String> {
+    let bytes = self.take(4)?;
+    Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+  }
+
+  fn f64(&mut self) -> Result<f64, String> {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(self.take(8)?);
+    Ok(f64::from_le_bytes(buf))
+  }
+
+  fn bbox(&mut self) -> Result<[f64; 4], String> {
+    Ok([self.f64()?, self.f64()?, self.f64()?, self.f64()?])
+  }
+
+  /// 读取数量，并确认剩余内容至少能容纳 `item_size` 字节的对应项，避免按损坏的数量分配内存
+  fn count(&mut self, item_size: usize) -> Result<usize, String> {
+    let count = self.i32()?;
+    if count < 0 || (count as usize).saturating_mul(item_size) > self.remaining() {
+      return Err(format!("无效的数量: {}", count));
+    }
+    Ok(count as usize)
+  }
+
+  fn points(&mut self, count: usize) -> Result<Vec<[f64; 2]>, String> {
+    (0..count).map(|_| Ok([self.f64()?, self.f64()?])).collect()
+  }
+}
+
+// M 值小于 -1e38 表示无数据，不参与范围计算
+fn value_range(values: &[f64]) -> [f64; 2] {
+  values
+    .iter()
+    .filter(|value| **value >= -1e38)
+    .fold(None, |range: Option<[f64; 2]>, &value| {
+      Some(match range {
+        None => [value, value],
+        Some([min, max]) => [min.min(value), max.max(value)],
+      })
+    })
+    .unwrap_or_default()
+}
@@ -0,0 +1,272 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use shapefile::dbase::{self, encoding::EncodingRs};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::dbf::{insert_row_record, renamed_row_fields};
+use super::encoding::{open_reader, DetectedEncoding};
+use super::geometry::ConvertOptions;
+use super::schema::read_shx_record_count;
+use super::shapefile_to_geojson::CustomError;
+use super::utilities::shape_record_to_row;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// 记录序号字段，可用于排序与过滤，DBF 中的同名字段改名输出
+const FID_FIELD: &str = "fid";
+
+/// 属性表查询条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordQuery {
+  pub offset: usize,
+  pub limit: usize,
+  pub sort: Option<RecordSort>,
+  /// 多个条件之间为“且”的关系
+  pub filters: Vec<RecordFilter>,
+  /// 不输出几何列，属性表浏览时可显著减少数据量
+  pub omit_geometry: bool,
+}
+
+impl Default for RecordQuery {
+  fn default() -> Self {
+    RecordQuery {
+      offset: 0,
+      limit: DEFAULT_PAGE_SIZE,
+      sort: None,
+      filters: Vec::new(),
+      omit_geometry: false,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordSort {
+  pub field: String,
+  #[serde(default)]
+  pub descending: bool,
+}
+
+/// 过滤条件，`op` 区分类型
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum RecordFilter {
+  /// 值相等，数字按数值比较
+  #[serde(rename_all = "camelCase")]
+  Equals { field: String, value: Value },
+  /// 闭区间，缺省的一端不限制
+  #[serde(rename_all = "camelCase")]
+  Range {
+    field: String,
+    min: Option<Value>,
+    max: Option<Value>,
+  },
+  /// 文本包含
+  #[serde(rename_all = "camelCase")]
+  Contains {
+    field: String,
+    value: String,
+    #[serde(default)]
+    ignore_case: bool,
+  },
+}
+
+impl RecordFilter {
+  fn field(&self) -> &str {
+    match self {
+      RecordFilter::Equals { field, .. }
+      | RecordFilter::Range { field, .. }
+      | RecordFilter::Contains { field, .. } => field,
+    }
+  }
+
+  fn matches(&self, properties: &serde_json::Map<String, Value>) -> bool {
+    let value = properties.get(self.field()).unwrap_or(&Value::Null);
+    match self {
+      RecordFilter::Equals {
+        value: expected, ..
+      } => compare_values(value, expected) == Ordering::Equal,
+      RecordFilter::Range { min, max, .. } => {
+        !value.is_null()
+          && min
+            .as_ref()
+            .is_none_or(|min| compare_values(value, min) != Ordering::Less)
+          && max
+            .as_ref()
+            .is_none_or(|max| compare_values(value, max) != Ordering::Greater)
+      }
+      RecordFilter::Contains {
+        value: pattern,
+        ignore_case,
+        ..
+      } => match value {
+        Value::String(text) if *ignore_case => {
+          text.to_lowercase().contains(&pattern.to_lowercase())
+        }
+        Value::String(text) => text.contains(pattern.as_str()),
+        _ => false,
+      },
+    }
+  }
+}
+
+/// 一页查询结果
+pub struct RecordPage {
+  pub records: Vec<Value>,
+  /// 满足过滤条件的记录总数
+  pub total: usize,
+}
+
+/// 按查询条件读取一页记录
+///
+/// 无排序与过滤时通过 `.shx` 直接定位到页首；否则先扫描 DBF 得到有序的记录序号，
+/// 再按序号随机读取当前页。扫描结果不做缓存，有排序或过滤时每翻一页都会重新读取整个 DBF，
+/// 耗时与记录数成正比。同步执行，调用方应放在 `spawn_blocking` 中
+pub fn query_records(
+  shp_path: &Path,
+  query: &RecordQuery,
+  options: &ConvertOptions,
+  encoding: &DetectedEncoding,
+  srid: Option<u32>,
+) -> Result<RecordPage, Box<dyn std::error::Error + Send + Sync>> {
+  let shx_path = shp_path.with_extension("shx");
+  let record_count = read_shx_record_count(&shx_path).ok_or_else(|| {
+    CustomError(format!(
+      "缺少 .shx 索引文件，无法分页读取: {}",
+      shx_path.display()
+    ))
+  })? as usize;
+
+  let (indices, total) = if query.sort.is_none() && query.filters.is_empty() {
+    let end = query.offset.saturating_add(query.limit).min(record_count);
+    (
+      (query.offset.min(end)..end).collect::<Vec<_>>(),
+      record_count,
+    )
+  } else {
+    let matched = scan_matching_records(&shp_path.with_extension("dbf"), query, encoding)?;
+    let total = matched.len();
+    let page = matched
+      .into_iter()
+      .skip(query.offset)
+      .take(query.limit)
+      .collect();
+    (page, total)
+  };
+
+  let mut reader = open_reader(shp_path, encoding)?;
+  let mut records = Vec::with_capacity(indices.len());
+  for index in indices {
+    reader.seek(index)?;
+    let shape_record = reader
+      .iter_shapes_and_records()
+      .next()
+      .ok_or_else(|| CustomError(format!("记录 {} 不存在", index)))?;
+    let row = if query.omit_geometry {
+      let (_, record) = shape_record?;
+      let mut properties = serde_json::Map::new();
+      properties.insert(FID_FIELD.to_string(), json!(index));
+      insert_row_record(&mut properties, record);
+      Value::Object(properties)
+    } else {
+      shape_record_to_row(index, shape_record, options, srid)?.0
+    };
+    records.push(row);
+  }
+
+  Ok(RecordPage { records, total })
+}
+
+/// 扫描 DBF，返回满足过滤条件并排好序的记录序号
+fn scan_matching_records(
+  dbf_path: &Path,
+  query: &RecordQuery,
+  encoding: &DetectedEncoding,
+) -> Result<Vec<usize>, Box<dyn std::error::Error + Send + Sync>> {
+  let mut reader =
+    dbase::Reader::from_path_with_encoding(dbf_path, EncodingRs::from(encoding.encoding))?;
+
+  let mut field_names: HashSet<String> = reader
+    .fields()
+    .iter()
+    .map(|field| field.name().to_string())
+    .collect();
+  let renamed = renamed_row_fields(reader.fields().iter().map(|field| field.name()));
+  for (name, new_name) in renamed {
+    field_names.remove(&name);
+    field_names.insert(new_name);
+  }
+  field_names.insert(FID_FIELD.to_string());
+  let queried_fields = query
+    .filters
+    .iter()
+    .map(RecordFilter::field)
+    .chain(query.sort.as_ref().map(|sort| sort.field.as_str()));
+  for field in queried_fields {
+    if !field_names.contains(field) {
+      return Err(Box::new(CustomError(format!("字段不存在: {}", field))));
+    }
+  }
+
+  let mut matched: Vec<(usize, Value)> = Vec::new();
+  for (index, record) in reader.iter_records().enumerate() {
+    let record = record?;
+    let mut properties = serde_json::Map::new();
+    properties.insert(FID_FIELD.to_string(), json!(index));
+    insert_row_record(&mut properties, record);
+    if !query
+      .filters
+      .iter()
+      .all(|filter| filter.matches(&properties))
+    {
+      continue;
+    }
+    let sort_key = match &query.sort {
+      Some(sort) => properties.remove(&sort.field).unwrap_or(Value::Null),
+      None => Value::Null,
+    };
+    matched.push((index, sort_key));
+  }
+
+  if let Some(sort) = &query.sort {
+    // 稳定排序，值相同时保持文件顺序；空值始终排在最后
+    matched.sort_by(|(_, a), (_, b)| match (a.is_null(), b.is_null()) {
+      (true, true) => Ordering::Equal,
+      (true, false) => Ordering::Greater,
+      (false, true) => Ordering::Less,
+      (false, false) if sort.descending => compare_values(b, a),
+      (false, false) => compare_values(a, b),
+    });
+  }
+  Ok(matched.into_iter().map(|(index, _)| index).collect())
+}
+
+/// 比较两个属性值，数字按数值、文本按字典序，类型不同时按 空值 < 布尔 < 数字 < 文本
+pub(super) fn compare_values(a: &Value, b: &Value) -> Ordering {
+  match (a, b) {
+    (Value::Number(a), Value::Number(b)) => {
+      let (a, b) = (
+        a.as_f64().unwrap_or(f64::NAN),
+        b.as_f64().unwrap_or(f64::NAN),
+      );
+      a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    }
+    (Value::String(a), Value::String(b)) => a.cmp(b),
+    (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+    _ => type_rank(a).cmp(&type_rank(b)),
+  }
+}
+
+fn type_rank(value: &Value) -> u8 {
+  match value {
+    Value::Null => 0,
+    Value::Bool(_) => 1,
+    Value::Number(_) => 2,
+    Value::String(_) => 3,
+    Value::Array(_) => 4,
+    Value::Object(_) => 5,
+  }
+}
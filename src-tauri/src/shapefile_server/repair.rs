@@ -0,0 +1,508 @@
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::encoding::detect_encoding;
+use super::geometry::{point_in_ring, signed_area};
+use super::raw_shape::RawShape;
+use super::schema::{
+  read_dbf_header, read_shp_file_header, read_u32_be, DbfHeader, SHP_HEADER_SIZE,
+  SHP_RECORD_HEADER_SIZE,
+};
+use super::shapefile_to_geojson::CustomError;
+use super::writer::{copy_dbf_header_flags, encode_field_value, DbfWriter, Extent, ShpWriter};
+use crate::utils;
+use crate::utils::progress::{ProgressPhase, ProgressReporter};
+
+// 修复记录中最多保留的条数，超出后只计数
+const MAX_CHANGES: usize = 1000;
+// 随数据集一起复制的附属文件
+const SIDECAR_EXTENSIONS: [&str; 2] = ["prj", "cpg"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RepairOptions {
+  /// 删除空图形与修复后没有任何点的图形，对应的属性记录一并删除
+  pub remove_null_shapes: bool,
+  /// 输出文件名，缺省为 `原文件名_repaired`
+  pub file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairChange {
+  /// 修复类型，如 `closeRing`、`reorientRing`
+  pub code: &'static str,
+  /// 原数据中从 0 开始的记录序号，文件级修复为空
+  pub record_index: Option<u64>,
+  pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+  pub shp_path: PathBuf,
+  pub record_count: u64,
+  pub removed_count: u64,
+  /// 每种修复的次数
+  pub change_counts: BTreeMap<&'static str, u64>,
+  pub changes: Vec<RepairChange>,
+  /// 修复超过上限，`changes` 未列全
+  pub truncated: bool,
+}
+
+impl RepairReport {
+  fn add(&mut self, code: &'static str, record_index: Option<u64>, message: impl Into<String>) {
+    *self.change_counts.entry(code).or_default() += 1;
+    if self.changes.len() < MAX_CHANGES {
+      self.changes.push(RepairChange {
+        code,
+        record_index,
+        message: message.into(),
+      });
+    } else {
+      self.truncated = true;
+    }
+  }
+}
+
+/// 修复数据集并写入工作空间的 repaired 目录，原文件不做修改
+///
+/// `.shx` 始终按 `.shp` 重新生成，DBF 按图形数截断或补齐。
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn repair_shapefile(
+  input_path: &str,
+  options: &RepairOptions,
+  encoding: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<RepairReport, CustomError> {
+  let base_path = Path::new(input_path);
+  let output_dir = utils::files::get_repaired_path();
+  fs::create_dir_all(&output_dir)?;
+  let name = match options.file_name.as_deref() {
+    // 只取文件名部分，避免写出工作空间
+    Some(name) => Path::new(name)
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .map(str::to_string)
+      .ok_or_else(|| CustomError(format!("无效的输出文件名: {}", name)))?,
+    None => format!(
+      "{}_repaired",
+      base_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("shapefile")
+    ),
+  };
+  repair_to(
+    base_path,
+    &output_dir.join(format!("{}.shp", name)),
+    options,
+    encoding,
+    progress,
+  )
+}
+
+/// 修复 `base_path` 对应的数据集并写入 `output_path`
+fn repair_to(
+  base_path: &Path,
+  output_path: &Path,
+  options: &RepairOptions,
+  encoding: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<RepairReport, CustomError> {
+  let shp_path = base_path.with_extension("shp");
+  let dbf_path = base_path.with_extension("dbf");
+  if fs::canonicalize(output_path).ok() == fs::canonicalize(&shp_path).ok() {
+    return Err(CustomError("输出文件不能覆盖原文件".to_string()));
+  }
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let dbf_header = read_dbf_header(&dbf_path, &encoding)?;
+
+  let mut report = RepairReport {
+    shp_path: output_path.to_path_buf(),
+    ..RepairReport::default()
+  };
+  let result =
+    repair_shp(&shp_path, output_path, options, progress, &mut report).and_then(|kept| {
+      repair_dbf(
+        &dbf_path,
+        &output_path.with_extension("dbf"),
+        &dbf_header,
+        &kept,
+        encoding.encoding,
+        &mut report,
+      )
+    });
+  if let Err(e) = result {
+    for extension in ["shp", "shx", "dbf"] {
+      let _ = fs::remove_file(output_path.with_extension(extension));
+    }
+    return Err(e);
+  }
+  for extension in SIDECAR_EXTENSIONS {
+    let source = base_path.with_extension(extension);
+    if source.exists() {
+      fs::copy(&source, output_path.with_extension(extension))?;
+    }
+  }
+  progress.finish();
+  Ok(report)
+}
+
+/// 按顺序读取 `.shp` 的每条记录，修复后写出新的 `.shp` 与 `.shx`，返回保留的原记录序号
+fn repair_shp(
+  shp_path: &Path,
+  output_path: &Path,
+  options: &RepairOptions,
+  progress: &ProgressReporter,
+  report: &mut RepairReport,
+) -> Result<Vec<u64>, CustomError> {
+  let shp_len = fs::metadata(shp_path)?.len();
+  let mut shp = BufReader::new(File::open(shp_path)?);
+  let shape_type = read_shp_file_header(&mut shp, shp_path)?.shape_type;
+  let mut writer = ShpWriter::create(output_path, shape_type)?;
+
+  progress.start_phase(ProgressPhase::Writing, None);
+  let mut offset = SHP_HEADER_SIZE as u64;
+  let mut index: u64 = 0;
+  let mut kept = Vec::new();
+  let mut extent: Option<Extent> = None;
+  while offset < shp_len {
+    if progress.is_cancelled() {
+      return Err(CustomError("任务已取消".to_string()));
+    }
+    let mut record_header = [0u8; SHP_RECORD_HEADER_SIZE];
+    let content_len = shp
+      .read_exact(&mut record_header)
+      .ok()
+      .map(|_| u64::from(read_u32_be(&record_header, 4)) * 2)
+      .filter(|content_len| offset + SHP_RECORD_HEADER_SIZE as u64 + content_len <= shp_len);
+    let Some(content_len) = content_len else {
+      report.add("dropTruncated", Some(index), "文件末尾的记录不完整，已丢弃");
+      break;
+    };
+    let mut content = vec![0u8; content_len as usize];
+    shp.read_exact(&mut content)?;
+    offset += SHP_RECORD_HEADER_SIZE as u64 + content_len;
+    progress.inc(1);
+
+    let mut shape = match RawShape::parse(&content) {
+      Ok(shape) => shape,
+      Err(e) => {
+        report.add(
+          "nullifyInvalid",
+          Some(index),
+          format!("{}，已替换为空图形", e),
+        );
+        RawShape::parse(&0i32.to_le_bytes()).map_err(CustomError)?
+      }
+    };
+    if !shape.is_null() {
+      repair_parts(&mut shape, index, report);
+      if shape.points.is_empty() {
+        report.add(
+          "nullifyEmpty",
+          Some(index),
+          "图形没有任何点，已替换为空图形",
+        );
+        shape = RawShape::parse(&0i32.to_le_bytes()).map_err(CustomError)?;
+      }
+    }
+    if shape.is_null() && options.remove_null_shapes {
+      report.add("removeNull", Some(index), "已删除空图形及其属性记录");
+      report.removed_count += 1;
+      index += 1;
+      continue;
+    }
+
+    if let Some(bbox) = shape.computed_bbox() {
+      if shape.bbox.is_some_and(|stored| stored != bbox) {
+        report.add("fixBbox", Some(index), "已按坐标重新计算记录范围");
+      }
+      let z = (!shape.z.is_empty()).then(|| {
+        shape
+          .z
+          .iter()
+          .fold([f64::INFINITY, f64::NEG_INFINITY], |[min, max], &z| {
+            [min.min(z), max.max(z)]
+          })
+      });
+      let record_extent = Extent { bbox, z };
+      match extent.as_mut() {
+        Some(current) => current.merge(&record_extent),
+        None => extent = Some(record_extent),
+      }
+    }
+    writer.write_content(&shape.encode())?;
+    kept.push(index);
+    index += 1;
+  }
+
+  if let Some(extent) = extent {
+    writer.set_extent(extent);
+  }
+  let record_count = writer.finish()?;
+  report.record_count = u64::from(record_count);
+  report.add(
+    "rebuildShx",
+    None,
+    format!("已按 .shp 重新生成 .shx（{} 条）", record_count),
+  );
+  Ok(kept)
+}
+
+/// 一个部件的点及对应的 Z、M
+struct Part {
+  points: Vec<[f64; 2]>,
+  z: Vec<f64>,
+  m: Vec<f64>,
+}
+
+/// 去掉空部件，闭合并重新定向面的环；Multipatch 不做处理
+fn repair_parts(shape: &mut RawShape, index: u64, report: &mut RepairReport) {
+  let is_polygon = matches!(shape.shape_type, 5 | 15 | 25);
+  if !is_polygon && !matches!(shape.shape_type, 3 | 13 | 23) {
+    return;
+  }
+  let index = Some(index);
+
+  let mut parts: Vec<Part> = Vec::new();
+  for (part, range) in shape.part_ranges().into_iter().enumerate() {
+    if range.is_empty() {
+      report.add(
+        "removeEmptyPart",
+        index,
+        format!("已删除没有点的部件 {}", part),
+      );
+      continue;
+    }
+    // 没有 Z、M 的类型取到的是空列表
+    let slice = |values: &[f64]| values.get(range.clone()).unwrap_or_default().to_vec();
+    let mut item = Part {
+      points: shape.points[range.clone()].to_vec(),
+      z: slice(&shape.z),
+      m: slice(&shape.m),
+    };
+    if is_polygon && item.points.first() != item.points.last() {
+      item.points.push(item.points[0]);
+      if let Some(&z) = item.z.first() {
+        item.z.push(z);
+      }
+      if let Some(&m) = item.m.first() {
+        item.m.push(m);
+      }
+      report.add("closeRing", index, format!("已闭合环 {}", part));
+    }
+    parts.push(item);
+  }
+
+  if is_polygon {
+    // 被奇数个环包含的是内环，应为逆时针；其余为外环，应为顺时针
+    let depths: Vec<usize> = parts
+      .iter()
+      .enumerate()
+      .map(|(ring, part)| {
+        parts
+          .iter()
+          .enumerate()
+          .filter(|(other, other_part)| {
+            *other != ring
+              && point_in_ring(&other_part.points, part.points[0][0], part.points[0][1])
+          })
+          .count()
+      })
+      .collect();
+    for (ring, part) in parts.iter_mut().enumerate() {
+      let area = signed_area(&part.points);
+      let is_hole = depths[ring] % 2 == 1;
+      if area != 0.0 && (area > 0.0) != is_hole {
+        part.points.reverse();
+        part.z.reverse();
+        part.m.reverse();
+        report.add(
+          "reorientRing",
+          index,
+          format!(
+            "已将{}环 {} 改为{}",
+            if is_hole { "内" } else { "外" },
+            ring,
+            if is_hole { "逆时针" } else { "顺时针" }
+          ),
+        );
+      }
+    }
+  }
+
+  shape.parts.clear();
+  shape.points.clear();
+  shape.z.clear();
+  shape.m.clear();
+  for part in parts {
+    shape.parts.push(shape.points.len() as i32);
+    shape.points.extend(part.points);
+    shape.z.extend(part.z);
+    shape.m.extend(part.m);
+  }
+}
+
+/// 按保留的记录复制属性，缺少的记录以空值补齐，多余的记录删除
+fn repair_dbf(
+  dbf_path: &Path,
+  output_path: &Path,
+  header: &DbfHeader,
+  kept: &[u64],
+  encoding: &'static Encoding,
+  report: &mut RepairReport,
+) -> Result<(), CustomError> {
+  let mut reader = BufReader::new(File::open(dbf_path)?);
+  let file_length = reader.get_ref().metadata()?.len();
+  let mut original_header = vec![0u8; (header.header_length as usize).min(file_length as usize)];
+  reader.read_exact(&mut original_header)?;
+  let header_length = u64::from(header.header_length);
+  let record_length = usize::from(header.record_length);
+  // 文件中实际完整的记录数可能少于文件头声明的数量
+  let available = (file_length.saturating_sub(header_length) / record_length.max(1) as u64)
+    .min(u64::from(header.record_count));
+  if available < u64::from(header.record_count) {
+    report.add(
+      "truncatedDbf",
+      None,
+      format!(
+        "DBF 声明 {} 条记录，实际只有 {} 条完整记录",
+        header.record_count, available
+      ),
+    );
+  }
+
+  // 文件头中的记录长度可能带有填充，输出按字段长度重新计算
+  let output_length = super::writer::record_length(&header.fields);
+  let mut blank = vec![b' '];
+  for field in &header.fields {
+    let value = encode_field_value(field, &serde_json::Value::Null, encoding)
+      .unwrap_or_else(|_| vec![b' '; field.length as usize]);
+    blank.extend(value);
+  }
+  blank.resize(output_length, b' ');
+
+  let mut writer = DbfWriter::create(output_path, &header.fields, encoding)?;
+  let mut record = vec![0u8; record_length];
+  let mut output = blank.clone();
+  let mut next_index = 0;
+  let mut padded = 0;
+  for &index in kept {
+    if index < available {
+      if index != next_index {
+        reader.seek(SeekFrom::Start(
+          header_length + index * record_length as u64,
+        ))?;
+      }
+      reader.read_exact(&mut record)?;
+      next_index = index + 1;
+      let copied = output_length.min(record_length);
+      output[..copied].copy_from_slice(&record[..copied]);
+      writer.write_raw(&output)?;
+    } else {
+      writer.write_raw(&blank)?;
+      padded += 1;
+    }
+  }
+  writer.finish()?;
+  copy_dbf_header_flags(&original_header, output_path)?;
+
+  if padded > 0 {
+    report.add(
+      "padDbf",
+      None,
+      format!("DBF 记录不足，已补齐 {} 条空记录", padded),
+    );
+  }
+  let shape_count = kept.len() as u64 + report.removed_count;
+  if available > shape_count {
+    report.add(
+      "truncateDbf",
+      None,
+      format!("已删除 DBF 中多出的 {} 条记录", available - shape_count),
+    );
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shapefile_server::schema::{field_type_name, DbfField};
+  use crate::shapefile_server::validation::validate_shapefile;
+  use std::io::Write;
+
+  fn square(closed: bool) -> Vec<u8> {
+    let mut points = vec![[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]];
+    if closed {
+      points.push([0.0, 0.0]);
+    }
+    RawShape {
+      shape_type: 5,
+      bbox: Some([0.0, 0.0, 1.0, 1.0]),
+      parts: vec![0],
+      part_types: Vec::new(),
+      points,
+      z: Vec::new(),
+      m: Vec::new(),
+    }
+    .encode()
+  }
+
+  #[test]
+  fn repaired_shapefile_passes_validation() {
+    let dir = std::env::temp_dir().join(format!("shp_repair_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let shp_path = dir.join("broken.shp");
+
+    // 未闭合的环、末尾不完整的记录，DBF 比图形多一条记录
+    let mut writer = ShpWriter::create(&shp_path, 5).unwrap();
+    writer.write_content(&square(false)).unwrap();
+    writer.write_content(&square(true)).unwrap();
+    writer.finish().unwrap();
+    let mut shp = fs::OpenOptions::new().append(true).open(&shp_path).unwrap();
+    shp.write_all(&[0, 0, 0, 3, 0, 0, 0, 64, 5, 0]).unwrap();
+
+    let fields = vec![DbfField {
+      name: "ID".to_string(),
+      field_type: 'N',
+      type_name: field_type_name('N'),
+      length: 4,
+      decimal_count: 0,
+    }];
+    let mut dbf =
+      DbfWriter::create(&shp_path.with_extension("dbf"), &fields, encoding_rs::UTF_8).unwrap();
+    for id in 0..3 {
+      dbf.write(&[serde_json::json!(id)]).unwrap();
+    }
+    dbf.finish().unwrap();
+
+    let progress = ProgressReporter::detached("repair-test");
+    let input = shp_path.to_str().unwrap();
+    let before = validate_shapefile(input, Some("UTF-8"), &progress).unwrap();
+    assert!(before.error_count > 0);
+
+    let output_path = dir.join("repaired.shp");
+    let report = repair_to(
+      &shp_path,
+      &output_path,
+      &RepairOptions::default(),
+      Some("UTF-8"),
+      &progress,
+    )
+    .unwrap();
+    assert_eq!(report.record_count, 2);
+
+    let after =
+      validate_shapefile(output_path.to_str().unwrap(), Some("UTF-8"), &progress).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(after.error_count, 0, "{:?}", after.issues);
+    assert!(after.valid);
+  }
+}
@@ -0,0 +1,240 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use super::encoding::DetectedEncoding;
+use super::shapefile_to_geojson::CustomError;
+
+// .shp/.shx 文件头长度，以及 .shx 中每条索引记录的长度
+pub const SHP_HEADER_SIZE: usize = 100;
+pub const SHX_RECORD_SIZE: u64 = 8;
+// .shp 记录头：记录号与内容长度，均为大端序
+pub const SHP_RECORD_HEADER_SIZE: usize = 8;
+pub const SHP_FILE_CODE: i32 = 9994;
+// DBF 文件头与字段描述的长度，字段描述以 0x0D 结束
+const DBF_HEADER_SIZE: usize = 32;
+const DBF_FIELD_SIZE: usize = 32;
+const DBF_FIELD_TERMINATOR: u8 = 0x0D;
+
+/// `.shp` 文件头中的图形类型与范围
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShpHeader {
+  pub shape_type: &'static str,
+  /// `[minX, minY, maxX, maxY]`
+  pub bbox: [f64; 4],
+  /// `[minZ, maxZ]`，仅 Z 类型有值
+  pub z_range: Option<[f64; 2]>,
+  /// `[minM, maxM]`，仅 M/Z 类型有值
+  pub m_range: Option<[f64; 2]>,
+}
+
+/// `.shp`/`.shx` 文件头的原始值，不校验图形类型
+#[derive(Debug, Clone)]
+pub struct ShpFileHeader {
+  pub file_code: i32,
+  /// 文件头记录的文件长度，单位为字节
+  pub file_length: u64,
+  pub shape_type: i32,
+  /// `[minX, minY, maxX, maxY]`
+  pub bbox: [f64; 4],
+  pub z_range: [f64; 2],
+  pub m_range: [f64; 2],
+}
+
+/// DBF 字段定义
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbfField {
+  pub name: String,
+  /// dBase 类型字符，如 `C`、`N`、`D`
+  pub field_type: char,
+  pub type_name: &'static str,
+  pub length: u8,
+  pub decimal_count: u8,
+}
+
+/// DBF 文件头
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbfHeader {
+  pub record_count: u32,
+  pub fields: Vec<DbfField>,
+  /// 文件头（含字段描述）长度，即第一条记录的偏移
+  #[serde(skip)]
+  pub header_length: u16,
+  /// 每条记录的长度，含删除标记字节
+  #[serde(skip)]
+  pub record_length: u16,
+}
+
+/// 读取 `.shp` 文件头，不读取任何图形
+pub fn read_shp_header(shp_path: &Path) -> Result<ShpHeader, CustomError> {
+  let header = read_shp_file_header(&mut File::open(shp_path)?, shp_path)?;
+  let shape_type = shape_type_name(header.shape_type)
+    .ok_or_else(|| CustomError(format!("未知的图形类型: {}", header.shape_type)))?;
+  let has_z = matches!(header.shape_type, 11 | 13 | 15 | 18 | 31);
+  let has_m = has_z || matches!(header.shape_type, 21 | 23 | 25 | 28);
+  Ok(ShpHeader {
+    shape_type,
+    bbox: header.bbox,
+    z_range: has_z.then_some(header.z_range),
+    m_range: has_m.then_some(header.m_range),
+  })
+}
+
+/// 从 `reader` 的当前位置读取 100 字节的文件头，读取后位于第一条记录处
+pub fn read_shp_file_header<R: Read>(
+  reader: &mut R,
+  shp_path: &Path,
+) -> Result<ShpFileHeader, CustomError> {
+  let mut header = [0u8; SHP_HEADER_SIZE];
+  reader
+    .read_exact(&mut header)
+    .map_err(|_| CustomError(format!("无效的 .shp 文件头: {}", shp_path.display())))?;
+  Ok(ShpFileHeader {
+    file_code: read_i32_be(&header, 0),
+    // 文件长度以 16 位字为单位
+    file_length: u64::from(read_u32_be(&header, 24)) * 2,
+    shape_type: read_i32_le(&header, 32),
+    bbox: [
+      read_f64_le(&header, 36),
+      read_f64_le(&header, 44),
+      read_f64_le(&header, 52),
+      read_f64_le(&header, 60),
+    ],
+    z_range: [read_f64_le(&header, 68), read_f64_le(&header, 76)],
+    m_range: [read_f64_le(&header, 84), read_f64_le(&header, 92)],
+  })
+}
+
+/// 根据 `.shx` 文件大小计算图形记录数，文件缺失时返回 `None`
+pub fn read_shx_record_count(shx_path: &Path) -> Option<u64> {
+  let len = fs::metadata(shx_path).ok()?.len();
+  Some(len.saturating_sub(SHP_HEADER_SIZE as u64) / SHX_RECORD_SIZE)
+}
+
+/// 读取 DBF 文件头与字段定义，字段名按检测到的编码解码
+pub fn read_dbf_header(
+  dbf_path: &Path,
+  encoding: &DetectedEncoding,
+) -> Result<DbfHeader, CustomError> {
+  let invalid = || CustomError(format!("无效的 DBF 文件头: {}", dbf_path.display()));
+  let mut file = File::open(dbf_path)?;
+  let mut header = [0u8; DBF_HEADER_SIZE];
+  file.read_exact(&mut header).map_err(|_| invalid())?;
+  let record_count = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+  let header_length = u16::from_le_bytes([header[8], header[9]]);
+  let record_length = u16::from_le_bytes([header[10], header[11]]);
+
+  let mut descriptors = vec![0u8; (header_length as usize).saturating_sub(DBF_HEADER_SIZE)];
+  file.read_exact(&mut descriptors).map_err(|_| invalid())?;
+
+  let fields: Vec<DbfField> = descriptors
+    .chunks_exact(DBF_FIELD_SIZE)
+    .take_while(|descriptor| descriptor[0] != DBF_FIELD_TERMINATOR)
+    .map(|descriptor| {
+      let name_end = descriptor[..11].iter().position(|&b| b == 0).unwrap_or(11);
+      let (name, _, _) = encoding.encoding.decode(&descriptor[..name_end]);
+      let field_type = descriptor[11] as char;
+      DbfField {
+        name: name.trim().to_string(),
+        field_type,
+        type_name: field_type_name(field_type),
+        length: descriptor[16],
+        decimal_count: descriptor[17],
+      }
+    })
+    .collect();
+
+  let fields_length: usize = fields.iter().map(|field| field.length as usize).sum();
+  if (record_length as usize) < 1 + fields_length {
+    return Err(CustomError(format!(
+      "DBF 记录长度与字段定义不符: {}",
+      dbf_path.display()
+    )));
+  }
+
+  Ok(DbfHeader {
+    record_count,
+    fields,
+    header_length,
+    record_length,
+  })
+}
+
+/// 每个字段在记录内的偏移，第 0 字节为删除标记
+pub fn field_offsets(fields: &[DbfField]) -> Vec<usize> {
+  let mut offset = 1;
+  fields
+    .iter()
+    .map(|field| {
+      let current = offset;
+      offset += field.length as usize;
+      current
+    })
+    .collect()
+}
+
+pub fn shape_type_name(code: i32) -> Option<&'static str> {
+  let name = match code {
+    0 => "NullShape",
+    1 => "Point",
+    3 => "Polyline",
+    5 => "Polygon",
+    8 => "Multipoint",
+    11 => "PointZ",
+    13 => "PolylineZ",
+    15 => "PolygonZ",
+    18 => "MultipointZ",
+    21 => "PointM",
+    23 => "PolylineM",
+    25 => "PolygonM",
+    28 => "MultipointM",
+    31 => "Multipatch",
+    _ => return None,
+  };
+  Some(name)
+}
+
+pub fn field_type_name(field_type: char) -> &'static str {
+  match field_type {
+    'C' => "character",
+    'N' => "numeric",
+    'F' => "float",
+    'L' => "logical",
+    'D' => "date",
+    'M' => "memo",
+    'I' => "integer",
+    'O' => "double",
+    'Y' => "currency",
+    'T' => "dateTime",
+    _ => "unknown",
+  }
+}
+
+/// 以下按偏移读取定长数值，`bytes` 长度不足时 panic
+pub fn read_i32_le(bytes: &[u8], offset: usize) -> i32 {
+  let mut buf = [0u8; 4];
+  buf.copy_from_slice(&bytes[offset..offset + 4]);
+  i32::from_le_bytes(buf)
+}
+
+pub fn read_i32_be(bytes: &[u8], offset: usize) -> i32 {
+  let mut buf = [0u8; 4];
+  buf.copy_from_slice(&bytes[offset..offset + 4]);
+  i32::from_be_bytes(buf)
+}
+
+pub fn read_u32_be(bytes: &[u8], offset: usize) -> u32 {
+  let mut buf = [0u8; 4];
+  buf.copy_from_slice(&bytes[offset..offset + 4]);
+  u32::from_be_bytes(buf)
+}
+
+pub fn read_f64_le(bytes: &[u8], offset: usize) -> f64 {
+  let mut buf = [0u8; 8];
+  buf.copy_from_slice(&bytes[offset..offset + 8]);
+  f64::from_le_bytes(buf)
+}
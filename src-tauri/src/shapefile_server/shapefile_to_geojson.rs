@@ -1,16 +1,26 @@
+use super::dbf::{
+  insert_record, insert_renamed_record, renamed_field_warnings, renamed_fields,
+  FEATURE_RESERVED_PROPERTIES,
+};
+use super::encoding::{detect_encoding, open_reader, DetectedEncoding};
+use super::geojson_writer::{FeatureSink, OutputOptions};
+use super::geometry::{
+  shape_to_geometry, ConversionCounts, ConvertOptions, MeasureMode, RecordOutcome, ShapeOutcome,
+};
+use super::projection::{read_source_crs, Reprojector, SourceCrs, DEFAULT_TARGET_CRS};
+use super::schema::read_dbf_header;
+use super::simplify::{simplify_features, Simplifier, VertexCounts};
+use super::spatial_index::{IndexSource, SpatialIndex};
+use crate::utils::progress::{ProgressPhase, ProgressReporter};
 use futures::stream::{self, StreamExt};
-use geojson::{Feature, FeatureCollection};
-use indicatif::{ProgressBar, ProgressStyle};
-use regex::Regex;
-use serde_json::json;
-use shapefile::{dbase, PolygonRing, Reader, Shape};
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection, Geometry};
+use shapefile::{dbase, Reader, Shape};
 use std::fmt;
-use std::path::Path;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
-pub struct CustomError(String);
+pub struct CustomError(pub String);
 
 impl fmt::Display for CustomError {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -32,193 +42,375 @@ impl From<std::io::Error> for CustomError {
   }
 }
 
+impl From<dbase::Error> for CustomError {
+  fn from(err: dbase::Error) -> Self {
+    CustomError(format!("读取 DBF 失败: {}", err))
+  }
+}
+
+pub struct GeojsonConversion {
+  pub feature_collection: FeatureCollection,
+  pub counts: ConversionCounts,
+  pub vertex_counts: VertexCounts,
+  pub encoding: DetectedEncoding,
+  pub source_crs: SourceCrs,
+  pub target_crs: String,
+}
+
 pub async fn convert_shapefile_to_geojson(
   input_path: &str,
-) -> Result<FeatureCollection, Box<dyn std::error::Error + Send + Sync>> {
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<GeojsonConversion, Box<dyn std::error::Error + Send + Sync>> {
   let base_path = Path::new(input_path);
   let shp_path = base_path.with_extension("shp");
   let dbf_path = base_path.with_extension("dbf");
-  let mut shp_reader = Reader::from_path(shp_path.clone())?;
-  let all_count = count_records(&shp_path, &dbf_path)?;
+  let source_crs = read_source_crs(&base_path.with_extension("prj"))?;
+  let target_crs = target_crs.unwrap_or(DEFAULT_TARGET_CRS).to_string();
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let mut shp_reader = open_reader(&shp_path, &encoding)?;
+  let all_count = count_records(&shp_path, &dbf_path, progress)?;
+  warn_renamed_fields(&dbf_path, &encoding, &options, progress)?;
 
-  let features = Arc::new(Mutex::new(Vec::new()));
-
-  let pb = create_progress_bar(all_count);
+  progress.start_phase(ProgressPhase::Converting, Some(all_count));
 
   let shape_records: Vec<_> = shp_reader.iter_shapes_and_records().collect();
 
-  let tasks = stream::iter(shape_records)
-    .map(|shape_record| {
-      let features = Arc::clone(&features);
-      let pb = pb.clone();
+  // buffered 按输入顺序产出结果，保证要素顺序与文件一致
+  let tasks = stream::iter(shape_records.into_iter().enumerate())
+    .map(|(index, shape_record)| {
+      let progress = progress.clone();
 
-      tokio::spawn(async move { process_shape_record(shape_record, features, pb).await })
+      tokio::spawn(
+        async move { process_shape_record(index, shape_record, options, progress).await },
+      )
     })
-    .buffer_unordered(num_cpus::get());
-
-  tasks.for_each(|_| async {}).await;
+    .buffered(num_cpus::get());
+
+  let (mut features, counts) = tasks
+    .fold(
+      (Vec::new(), ConversionCounts::default()),
+      |(mut features, mut counts), result| async move {
+        match result.ok().and_then(|outcome| outcome.ok()) {
+          Some((feature, outcome)) => {
+            features.push(feature);
+            counts.add(Some(outcome));
+          }
+          None => counts.add(None),
+        }
+        (features, counts)
+      },
+    )
+    .await;
+  if progress.is_cancelled() {
+    return Err(Box::new(CustomError("任务已取消".to_string())));
+  }
 
-  println!("completed");
-  pb.finish_with_message("completed");
+  // CoordTransform 不能跨 await，所有异步任务结束后再统一转换
+  progress.start_phase(ProgressPhase::Reprojecting, Some(features.len() as u64));
+  Reprojector::new(&source_crs, &target_crs)?.transform_features(&mut features)?;
+  let vertex_counts = simplify_features(&mut features, &options)?;
 
   let feature_collection = FeatureCollection {
     bbox: None,
-    features: features.lock().await.clone(),
+    features,
     foreign_members: None,
   };
+  progress.finish();
+
+  Ok(GeojsonConversion {
+    feature_collection,
+    counts,
+    vertex_counts,
+    encoding,
+    source_crs,
+    target_crs,
+  })
+}
+
+pub struct GeojsonExport {
+  pub path: PathBuf,
+  pub feature_count: usize,
+  pub bbox: Option<[f64; 4]>,
+  pub counts: ConversionCounts,
+  pub vertex_counts: VertexCounts,
+  pub encoding: DetectedEncoding,
+  pub source_crs: SourceCrs,
+  pub target_crs: String,
+}
+
+/// 逐条读取、转换并写入文件，不在内存中保留要素
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn export_shapefile_to_geojson(
+  input_path: &str,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+  output: &OutputOptions,
+  progress: &ProgressReporter,
+) -> Result<GeojsonExport, Box<dyn std::error::Error + Send + Sync>> {
+  let base_path = Path::new(input_path);
+  let shp_path = base_path.with_extension("shp");
+  let dbf_path = base_path.with_extension("dbf");
+  let source_crs = read_source_crs(&base_path.with_extension("prj"))?;
+  let target_crs = target_crs.unwrap_or(DEFAULT_TARGET_CRS).to_string();
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let mut shp_reader = open_reader(&shp_path, &encoding)?;
+  let all_count = count_records(&shp_path, &dbf_path, progress)?;
+  warn_renamed_fields(&dbf_path, &encoding, &options, progress)?;
+
+  let reprojector = Reprojector::new(&source_crs, &target_crs)?;
+  let mut simplifier = Simplifier::new(&options)?;
+  if simplifier.needs_topology() {
+    // 保持拓扑需要先知道所有要素的顶点，多读取一遍
+    progress.start_phase(ProgressPhase::Converting, Some(all_count));
+    for (index, shape_record) in open_reader(&shp_path, &encoding)?
+      .iter_shapes_and_records()
+      .enumerate()
+    {
+      if progress.is_cancelled() {
+        return Err(Box::new(CustomError("任务已取消".to_string())));
+      }
+      progress.inc(1);
+      // 与写入时一致，读取文件出错时中止，单条记录无法解析时跳过
+      let shape_record = match shape_record {
+        Err(shapefile::Error::IoError(e)) => return Err(Box::new(e)),
+        shape_record => shape_record,
+      };
+      let Ok((mut feature, _)) = shape_record_to_feature(index, shape_record, &options) else {
+        continue;
+      };
+      if let Some(geometry) = feature.geometry.as_mut() {
+        reprojector.transform_value(&mut geometry.value)?;
+      }
+      simplifier.add_topology(&feature);
+    }
+  }
+  let path = output.output_path(base_path)?;
+  let mut sink = FeatureSink::create(&path, output.format)?;
+  let mut counts = ConversionCounts::default();
+
+  progress.start_phase(ProgressPhase::Writing, Some(all_count));
+  for (index, shape_record) in shp_reader.iter_shapes_and_records().enumerate() {
+    if progress.is_cancelled() {
+      return Err(Box::new(CustomError("任务已取消".to_string())));
+    }
+    progress.inc(1);
+    // 读取文件出错时中止，避免输出不完整的结果；单条记录无法解析时跳过
+    let shape_record = match shape_record {
+      Err(shapefile::Error::IoError(e)) => return Err(Box::new(e)),
+      shape_record => shape_record,
+    };
+    let Ok((mut feature, outcome)) = shape_record_to_feature(index, shape_record, &options) else {
+      counts.add(None);
+      continue;
+    };
+    if let Some(geometry) = feature.geometry.as_mut() {
+      reprojector.transform_value(&mut geometry.value)?;
+    }
+    simplifier.apply(&mut feature);
+    sink.write(&feature)?;
+    counts.add(Some(outcome));
+  }
 
-  Ok(feature_collection)
+  let (feature_count, bbox) = sink.finish()?;
+  progress.finish();
+
+  Ok(GeojsonExport {
+    path,
+    feature_count,
+    bbox,
+    counts,
+    vertex_counts: simplifier.counts(),
+    encoding,
+    source_crs,
+    target_crs,
+  })
 }
 
-fn count_records(
+// 地图瓦片的像素宽度，用于按缩放级别估算像素大小
+const MAP_TILE_SIZE: f64 = 512.0;
+
+/// 查询外包矩形与 `bbox` 相交的要素，`bbox` 与输出坐标均使用目标坐标系
+///
+/// 指定 `zoom` 时跳过在该级别下小于一个像素的线面要素，像素大小按经纬度范围估算。
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn query_shapefile_bbox(
+  input_path: &str,
+  bbox: [f64; 4],
+  zoom: Option<f64>,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+) -> Result<(GeojsonConversion, IndexSource), Box<dyn std::error::Error + Send + Sync>> {
+  let base_path = Path::new(input_path);
+  let shp_path = base_path.with_extension("shp");
+  let source_crs = read_source_crs(&base_path.with_extension("prj"))?;
+  let target_crs = target_crs.unwrap_or(DEFAULT_TARGET_CRS).to_string();
+  let encoding = detect_encoding(&base_path.with_extension("dbf"), encoding)?;
+
+  let bbox = if target_crs == DEFAULT_TARGET_CRS {
+    // 地图视图可能超出经纬度的有效范围
+    [
+      bbox[0].max(-180.0),
+      bbox[1].max(-90.0),
+      bbox[2].min(180.0),
+      bbox[3].min(90.0),
+    ]
+  } else {
+    bbox
+  };
+  let source_bbox = Reprojector::inverse(&source_crs, &target_crs)?.transform_bbox(bbox)?;
+  let min_extent = zoom.map(|zoom| {
+    let pixels = (bbox[2] - bbox[0]) * MAP_TILE_SIZE * 2f64.powf(zoom) / 360.0;
+    (source_bbox[2] - source_bbox[0]) / pixels.max(1.0)
+  });
+
+  let (index, index_source) = SpatialIndex::load_or_build(&shp_path)?;
+  let reprojector = Reprojector::new(&source_crs, &target_crs)?;
+  let mut reader = open_reader(&shp_path, &encoding)?;
+  let mut features = Vec::new();
+  let mut counts = ConversionCounts::default();
+  for (index, bounds) in index.query(source_bbox) {
+    if let Some(min_extent) = min_extent {
+      let (width, height) = (bounds[2] - bounds[0], bounds[3] - bounds[1]);
+      if (width > 0.0 || height > 0.0) && width < min_extent && height < min_extent {
+        continue;
+      }
+    }
+    reader.seek(index)?;
+    let Some(shape_record) = reader.iter_shapes_and_records().next() else {
+      counts.add(None);
+      continue;
+    };
+    let Ok((mut feature, outcome)) = shape_record_to_feature(index, shape_record, &options) else {
+      counts.add(None);
+      continue;
+    };
+    if let Some(geometry) = feature.geometry.as_mut() {
+      reprojector.transform_value(&mut geometry.value)?;
+    }
+    features.push(feature);
+    counts.add(Some(outcome));
+  }
+  let vertex_counts = simplify_features(&mut features, &options)?;
+
+  Ok((
+    GeojsonConversion {
+      feature_collection: FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+      },
+      counts,
+      vertex_counts,
+      encoding,
+      source_crs,
+      target_crs,
+    },
+    index_source,
+  ))
+}
+
+/// 统计记录数，SHP 与 DBF 数量不一致时通过进度事件发出警告
+pub(super) fn count_records(
   shp_path: &Path,
   dbf_path: &Path,
+  progress: &ProgressReporter,
 ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+  progress.start_phase(ProgressPhase::Counting, None);
   let mut shp_reader = Reader::from_path(shp_path)?;
   let mut dbf_reader = dbase::Reader::from_path(dbf_path)?;
   let shp_count = shp_reader.iter_shapes_and_records().count();
   let dbf_count = dbf_reader.iter_records().count();
 
   if shp_count != dbf_count {
-    println!("Warning: SHP data ({} records) and DBF data ({} records) have different numbers of elements.", shp_count, dbf_count);
-  } else {
-    println!(
-      "SHP and DBF data have the same number of elements: {} records",
-      shp_count
-    );
+    progress.warn(format!(
+      "SHP 数据（{} 条）与 DBF 数据（{} 条）的记录数不一致",
+      shp_count, dbf_count
+    ));
   }
 
   Ok(shp_count as u64)
 }
 
-fn create_progress_bar(total: u64) -> ProgressBar {
-  let pb = ProgressBar::new(total);
-  pb.set_style(
-    ProgressStyle::default_bar()
-      .template("{spinner:.green} [{bar:40.cyan/blue}] {msg}")
-      .unwrap()
-      .progress_chars("█▓▒░"),
-  );
-  pb.set_message("Processing...");
-  pb
-}
-
 async fn process_shape_record(
+  index: usize,
   shape_record: Result<(Shape, shapefile::dbase::Record), shapefile::Error>,
-  features: Arc<Mutex<Vec<Feature>>>,
-  pb: ProgressBar,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-  let (shape, record) = shape_record?;
-  let geojson_string = match shape {
-    Shape::Polygon(_) => process_polygon(&shape)?,
-    Shape::Polyline(_) => process_polyline(&shape)?,
-    Shape::Point(_) => process_point(&shape)?,
-    _ => return Ok(()), // Skip unsupported shapes
-  };
-
-  let mut feature: Feature = serde_json::from_str(&geojson_string)?;
-
-  if let Some(props) = &mut feature.properties {
-    let re_numeric = Regex::new(r"Numeric\(Some\(([0-9]+(\.[0-9]+)?)\)\)").unwrap();
-    let re_character = Regex::new(r#"^Character\(Some\("(.+)"\)\)"#).unwrap();
-
-    for (field, value) in record.into_iter() {
-      let value_string = value.to_string();
-      let value_json = match value_string.as_str() {
-        "Numeric(None)" => json!(""),
-        "Character(None)" => json!(null),
-        _ => {
-          if let Some(caps) = re_numeric.captures(&value_string) {
-            let number_str = caps.get(1).map_or("", |m| m.as_str());
-            if let Ok(number) = number_str.parse::<f64>() {
-              json!(number)
-            } else {
-              eprintln!("Failed to parse numeric value: {}", value_string);
-              json!(value_string)
-            }
-          } else if let Some(caps) = re_character.captures(&value_string) {
-            let character_str = caps.get(1).map_or("", |m| m.as_str());
-            json!(character_str)
-          } else {
-            json!(value_string)
-          }
-        }
-      };
-      props.insert(field.to_string(), value_json);
-    }
+  options: ConvertOptions,
+  progress: ProgressReporter,
+) -> Result<(Feature, RecordOutcome), Box<dyn std::error::Error + Send + Sync>> {
+  // 已取消时剩余记录直接跳过，尽快结束整个流
+  if progress.is_cancelled() {
+    return Err(Box::new(CustomError("任务已取消".to_string())));
   }
+  let converted = shape_record_to_feature(index, shape_record, &options);
+  progress.inc(1);
+  converted
+}
 
-  features.lock().await.push(feature);
-  pb.inc(1);
+/// 输出 M 值时提示与 `m` 属性重名而改名的字段
+fn warn_renamed_fields(
+  dbf_path: &Path,
+  encoding: &DetectedEncoding,
+  options: &ConvertOptions,
+  progress: &ProgressReporter,
+) -> Result<(), CustomError> {
+  if options.measure == MeasureMode::Ignore {
+    return Ok(());
+  }
+  let header = read_dbf_header(dbf_path, encoding)?;
+  let renamed = renamed_fields(
+    header.fields.iter().map(|field| field.name.as_str()),
+    &FEATURE_RESERVED_PROPERTIES,
+  );
+  for warning in renamed_field_warnings(&renamed) {
+    progress.warn(warning);
+  }
   Ok(())
 }
 
-fn process_polygon(shape: &Shape) -> Result<String, CustomError> {
-  let polygon = match shape {
-    Shape::Polygon(p) => p,
-    _ => return Err(CustomError("Expected Polygon shape".to_string())),
+/// 要素 `id` 为从 0 开始的记录序号
+fn shape_record_to_feature(
+  index: usize,
+  shape_record: Result<(Shape, shapefile::dbase::Record), shapefile::Error>,
+  options: &ConvertOptions,
+) -> Result<(Feature, RecordOutcome), Box<dyn std::error::Error + Send + Sync>> {
+  let (shape, record) = shape_record.inspect_err(|e| log::warn!("跳过无法读取的记录: {}", e))?;
+  let shape_geometry =
+    shape_to_geometry(&shape, options).inspect_err(|e| log::warn!("跳过无法转换的图形: {}", e))?;
+  let shape_outcome = match shape_geometry.geometry {
+    Some(_) => ShapeOutcome::Converted,
+    None => ShapeOutcome::Null,
   };
 
-  let rings: Vec<Vec<Vec<f64>>> = polygon
-    .rings()
-    .iter()
-    .map(|ring| match ring {
-      PolygonRing::Outer(points) | PolygonRing::Inner(points) => {
-        points.iter().map(|point| vec![point.x, point.y]).collect()
-      }
-    })
-    .collect();
-
-  let feature = json!({
-      "type": "Feature",
-      "geometry": {
-          "type": "Polygon",
-          "coordinates": rings
-      },
-      "properties": {}
-  });
-
-  serde_json::to_string(&feature).map_err(CustomError::from)
-}
-
-fn process_polyline(shape: &Shape) -> Result<String, CustomError> {
-  let polyline = match shape {
-    Shape::Polyline(p) => p,
-    _ => return Err(CustomError("Expected Polyline shape".to_string())),
+  let mut properties = serde_json::Map::new();
+  // 输出 M 值时 DBF 中的 `m` 字段改名，不论本条记录是否有 M 值，保证各要素的属性名一致
+  let lossy = if options.measure == MeasureMode::Ignore {
+    insert_record(&mut properties, record)
+  } else {
+    insert_renamed_record(&mut properties, record, &FEATURE_RESERVED_PROPERTIES)
   };
+  if let Some(measures) = shape_geometry.measures {
+    properties.insert("m".to_string(), measures);
+  }
 
-  let parts: Vec<Vec<Vec<f64>>> = polyline
-    .parts()
-    .iter()
-    .map(|part| part.iter().map(|point| vec![point.x, point.y]).collect())
-    .collect();
-
-  let feature = json!({
-      "type": "Feature",
-      "geometry": {
-          "type": "MultiLineString",
-          "coordinates": parts
-      },
-      "properties": {}
-  });
-
-  serde_json::to_string(&feature).map_err(CustomError::from)
-}
-
-fn process_point(shape: &Shape) -> Result<String, CustomError> {
-  let point = match shape {
-    Shape::Point(p) => p,
-    _ => return Err(CustomError("Expected Point shape".to_string())),
+  let feature = Feature {
+    bbox: None,
+    geometry: shape_geometry.geometry.map(Geometry::new),
+    id: Some(Id::Number(index.into())),
+    properties: Some(properties),
+    foreign_members: None,
   };
 
-  let feature = json!({
-      "type": "Feature",
-      "geometry": {
-          "type": "Point",
-          "coordinates": [point.x, point.y]
-      },
-      "properties": {}
-  });
-
-  serde_json::to_string(&feature).map_err(CustomError::from)
+  Ok((
+    feature,
+    RecordOutcome {
+      shape: shape_outcome,
+      lossy,
+    },
+  ))
 }
@@ -0,0 +1,718 @@
+use geojson::{Feature, Position, Value};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::geometry::{ConvertOptions, MeasureMode};
+use super::shapefile_to_geojson::CustomError;
+
+// f64 的有效数字约 15 位，更多小数位没有意义
+const MAX_PRECISION: u8 = 15;
+
+/// 化简算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimplifyMethod {
+  /// 道格拉斯-普克，容差为点到线段的距离
+  #[default]
+  DouglasPeucker,
+  /// 维斯瓦林加姆-怀亚特，容差为三角形的有效面积
+  VisvalingamWhyatt,
+}
+
+/// 化简选项，容差的单位与输出坐标系一致
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimplifyOptions {
+  #[serde(default)]
+  pub method: SimplifyMethod,
+  pub tolerance: f64,
+  /// 相邻要素的公共边界按相同方式化简，不产生缝隙与重叠，每段边界至少保留一个中间点
+  ///
+  /// 需要先读取一遍全部要素，记录每个不同顶点所在的线与环，每个顶点约占用 80 字节内存；
+  /// 输出到文件时同样全部保留在内存中，千万级顶点的数据需要数 GB 内存
+  #[serde(default)]
+  pub preserve_topology: bool,
+}
+
+/// 处理前后的顶点数
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct VertexCounts {
+  pub before: usize,
+  pub after: usize,
+}
+
+/// 对全部要素化简并按精度取整，返回顶点数
+pub fn simplify_features(
+  features: &mut [Feature],
+  options: &ConvertOptions,
+) -> Result<VertexCounts, CustomError> {
+  let mut simplifier = Simplifier::new(options)?;
+  if simplifier.needs_topology() {
+    for feature in features.iter() {
+      simplifier.add_topology(feature);
+    }
+  }
+  for feature in features.iter_mut() {
+    simplifier.apply(feature);
+  }
+  Ok(simplifier.counts())
+}
+
+/// 逐个要素化简与取整，保持拓扑时需先通过 `add_topology` 加入全部要素
+pub struct Simplifier {
+  simplify: Option<SimplifyOptions>,
+  precision: Option<u8>,
+  measure: MeasureMode,
+  // 顶点坐标所在的线与环的序号，只在保持拓扑时使用，占用的内存与不同顶点的个数成正比
+  nodes: HashMap<(u64, u64), Vec<u32>>,
+  line_count: u32,
+  counts: VertexCounts,
+}
+
+impl Simplifier {
+  pub fn new(options: &ConvertOptions) -> Result<Self, CustomError> {
+    if let Some(simplify) = &options.simplify {
+      if !simplify.tolerance.is_finite() || simplify.tolerance < 0.0 {
+        return Err(CustomError(format!(
+          "无效的化简容差: {}",
+          simplify.tolerance
+        )));
+      }
+    }
+    if options
+      .precision
+      .is_some_and(|precision| precision > MAX_PRECISION)
+    {
+      return Err(CustomError(format!(
+        "坐标精度应在 0 到 {} 之间",
+        MAX_PRECISION
+      )));
+    }
+    Ok(Simplifier {
+      simplify: options.simplify,
+      precision: options.precision,
+      measure: options.measure,
+      nodes: HashMap::new(),
+      line_count: 0,
+      counts: VertexCounts::default(),
+    })
+  }
+
+  pub fn needs_topology(&self) -> bool {
+    self
+      .simplify
+      .is_some_and(|simplify| simplify.preserve_topology)
+  }
+
+  /// 记录要素中每个顶点所在的线与环
+  pub fn add_topology(&mut self, feature: &Feature) {
+    let Some(geometry) = &feature.geometry else {
+      return;
+    };
+    for line in lines(&geometry.value) {
+      let id = self.line_count;
+      self.line_count += 1;
+      for position in line {
+        let owners = self.nodes.entry(coordinate_key(position)).or_default();
+        if owners.last() != Some(&id) {
+          owners.push(id);
+        }
+      }
+    }
+  }
+
+  pub fn apply(&mut self, feature: &mut Feature) {
+    let Some(geometry) = feature.geometry.as_mut() else {
+      return;
+    };
+    // Ordinate 模式下无法放入坐标的 M 值同样以属性输出
+    let measures = match (self.measure, feature.properties.as_mut()) {
+      (MeasureMode::Ignore, _) | (_, None) => None,
+      (_, Some(properties)) => properties.get_mut("m"),
+    };
+    self.apply_value(&mut geometry.value, measures);
+  }
+
+  pub fn counts(&self) -> VertexCounts {
+    self.counts
+  }
+
+  fn apply_value(&mut self, value: &mut Value, measures: Option<&mut serde_json::Value>) {
+    let mut measures = measures.and_then(|measures| measures.as_array_mut());
+    let is_ring = matches!(value, Value::Polygon(_));
+    match value {
+      Value::Point(position) => {
+        self.counts.before += 1;
+        self.round(position);
+        self.counts.after += 1;
+      }
+      Value::MultiPoint(points) => {
+        self.counts.before += points.len();
+        points.iter_mut().for_each(|position| self.round(position));
+        self.counts.after += points.len();
+      }
+      Value::LineString(line) => self.apply_line(line, false, measures),
+      Value::MultiLineString(lines) | Value::Polygon(lines) => {
+        for (index, line) in lines.iter_mut().enumerate() {
+          let line_measures = nested(&mut measures, index);
+          self.apply_line(line, is_ring, line_measures);
+        }
+      }
+      Value::MultiPolygon(polygons) => {
+        for (index, rings) in polygons.iter_mut().enumerate() {
+          let mut polygon_measures = nested(&mut measures, index);
+          for (ring_index, ring) in rings.iter_mut().enumerate() {
+            let ring_measures = nested(&mut polygon_measures, ring_index);
+            self.apply_line(ring, true, ring_measures);
+          }
+        }
+      }
+      Value::GeometryCollection(geometries) => {
+        for geometry in geometries {
+          self.apply_value(&mut geometry.value, None);
+        }
+      }
+    }
+  }
+
+  /// 化简后取整，取整产生的连续重复点一并去掉；环至少保留 4 个点，否则保持原样
+  fn apply_line(
+    &mut self,
+    line: &mut Vec<Position>,
+    is_ring: bool,
+    mut measures: Option<&mut Vec<serde_json::Value>>,
+  ) {
+    self.counts.before += line.len();
+    // M 值与坐标一一对应时才同步删除
+    if measures
+      .as_ref()
+      .is_some_and(|measures| measures.len() != line.len())
+    {
+      measures = None;
+    }
+    let min_len = if is_ring { 4 } else { 2 };
+
+    if let Some(simplify) = self.simplify {
+      let keep = if simplify.preserve_topology && line.len() >= 2 {
+        // 公共边界在相邻要素中必须同样化简，不能因为本环太小而跳过，
+        // 每段保留的中间点保证环化简后仍有至少 4 个点
+        Some(self.topology_mask(line, is_ring, &simplify))
+      } else if line.len() > min_len {
+        Some(simplify_mask(line, &simplify))
+      } else {
+        None
+      };
+      // 输入本身点数不足时保持原样
+      if let Some(keep) = keep.filter(|keep| keep.iter().filter(|keep| **keep).count() >= min_len) {
+        retain_mask(line, &keep);
+        if let Some(measures) = measures.as_deref_mut() {
+          retain_mask(measures, &keep);
+        }
+        if is_ring {
+          close_ring(line, measures.as_deref_mut());
+        }
+      }
+    }
+
+    if self.precision.is_some() {
+      line.iter_mut().for_each(|position| self.round(position));
+      let keep: Vec<bool> = (0..line.len())
+        .map(|index| index == 0 || !same_point(&line[index - 1], &line[index]))
+        .collect();
+      if keep.iter().filter(|keep| **keep).count() >= min_len {
+        retain_mask(line, &keep);
+        if let Some(measures) = measures {
+          retain_mask(measures, &keep);
+        }
+      }
+    }
+    self.counts.after += line.len();
+  }
+
+  /// 按各点所属的线与环将线切分为若干段，段的端点保留，每段按统一的方向化简
+  ///
+  /// 公共边界上的点属于同样的几条线，在每个要素中切出的段相同，化简结果也就相同
+  fn topology_mask(
+    &self,
+    line: &[Position],
+    is_ring: bool,
+    simplify: &SimplifyOptions,
+  ) -> Vec<bool> {
+    let empty = Vec::new();
+    let owners: Vec<&Vec<u32>> = line
+      .iter()
+      .map(|position| self.nodes.get(&coordinate_key(position)).unwrap_or(&empty))
+      .collect();
+    // 环的最后一点与第一点相同，按循环处理
+    let len = if is_ring && same_point(&line[0], &line[line.len() - 1]) {
+      line.len() - 1
+    } else {
+      line.len()
+    };
+    let cyclic = len < line.len();
+    let mut is_node: Vec<bool> = (0..len)
+      .map(|index| {
+        let previous = match index {
+          0 if cyclic => Some(len - 1),
+          0 => None,
+          _ => Some(index - 1),
+        };
+        let next = match index + 1 {
+          next if next < len => Some(next),
+          _ if cyclic => Some(0),
+          _ => None,
+        };
+        [previous, next]
+          .iter()
+          .any(|neighbor| neighbor.is_none_or(|neighbor| owners[neighbor] != owners[index]))
+      })
+      .collect();
+    if !is_node.contains(&true) {
+      // 整个环没有分界点（如岛与对应的洞），从坐标最小的点开始
+      let start = (0..len)
+        .min_by(|a, b| compare_points(&line[*a], &line[*b]))
+        .unwrap_or(0);
+      is_node[start] = true;
+    }
+
+    let mut keep = vec![false; line.len()];
+    let nodes: Vec<usize> = (0..len).filter(|index| is_node[*index]).collect();
+    let chain_count = if cyclic { nodes.len() } else { nodes.len() - 1 };
+    for chain in 0..chain_count {
+      let start = nodes[chain];
+      let end = nodes[(chain + 1) % nodes.len()];
+      let mut indices: Vec<usize> = if end > start {
+        (start..=end).collect()
+      } else {
+        (start..len).chain(0..=end).collect()
+      };
+      let mut points: Vec<Position> = indices.iter().map(|index| line[*index].clone()).collect();
+      let last = points.len() - 1;
+      let reverse = match compare_points(&points[0], &points[last]) {
+        Ordering::Greater => true,
+        Ordering::Equal => last > 1 && compare_points(&points[1], &points[last - 1]).is_gt(),
+        Ordering::Less => false,
+      };
+      if reverse {
+        points.reverse();
+        indices.reverse();
+      }
+      let mut chain_keep = simplify_mask(&points, simplify);
+      keep_interior(&points, &mut chain_keep);
+      for (index, kept) in indices.into_iter().zip(chain_keep) {
+        keep[index] |= kept;
+      }
+    }
+    if cyclic {
+      keep[len] = keep[0];
+    }
+    keep
+  }
+
+  fn round(&self, position: &mut Position) {
+    let Some(precision) = self.precision else {
+      return;
+    };
+    let factor = 10f64.powi(i32::from(precision));
+    // M 值（第四维）保持原样
+    for ordinate in position.iter_mut().take(3) {
+      *ordinate = (*ordinate * factor).round() / factor;
+    }
+  }
+}
+
+/// 取出 M 值中与当前部件对应的数组
+fn nested<'a>(
+  measures: &'a mut Option<&mut Vec<serde_json::Value>>,
+  index: usize,
+) -> Option<&'a mut Vec<serde_json::Value>> {
+  measures
+    .as_deref_mut()
+    .and_then(|measures| measures.get_mut(index))
+    .and_then(|measures| measures.as_array_mut())
+}
+
+/// 几何中的线与环
+fn lines(value: &Value) -> Vec<&Vec<Position>> {
+  match value {
+    Value::Point(_) | Value::MultiPoint(_) => Vec::new(),
+    Value::LineString(line) => vec![line],
+    Value::MultiLineString(lines) | Value::Polygon(lines) => lines.iter().collect(),
+    Value::MultiPolygon(polygons) => polygons.iter().flatten().collect(),
+    Value::GeometryCollection(geometries) => geometries
+      .iter()
+      .flat_map(|geometry| lines(&geometry.value))
+      .collect(),
+  }
+}
+
+fn coordinate_key(position: &Position) -> (u64, u64) {
+  (position[0].to_bits(), position[1].to_bits())
+}
+
+fn same_point(a: &Position, b: &Position) -> bool {
+  a[0] == b[0] && a[1] == b[1]
+}
+
+fn compare_points(a: &Position, b: &Position) -> Ordering {
+  a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1]))
+}
+
+fn retain_mask<T>(items: &mut Vec<T>, keep: &[bool]) {
+  let mut index = 0;
+  items.retain(|_| {
+    index += 1;
+    keep[index - 1]
+  });
+}
+
+/// 环的起点被化简掉时重新闭合
+fn close_ring(ring: &mut Vec<Position>, measures: Option<&mut Vec<serde_json::Value>>) {
+  if ring.len() > 1 && !same_point(&ring[0], &ring[ring.len() - 1]) {
+    ring.push(ring[0].clone());
+    if let Some(measures) = measures {
+      if let Some(first) = measures.first().cloned() {
+        measures.push(first);
+      }
+    }
+  }
+}
+
+/// 段内至少保留一个中间点，首尾相同的闭合段至少保留两个，离首尾连线最远的点优先
+///
+/// 只依据段本身的坐标决定，公共边界在相邻要素中的结果相同，且任何环化简后都不少于 4 个点
+fn keep_interior(points: &[Position], keep: &mut [bool]) {
+  let last = points.len() - 1;
+  let required = if same_point(&points[0], &points[last]) {
+    2
+  } else {
+    1
+  };
+  let kept = keep[1..last].iter().filter(|keep| **keep).count();
+  if kept >= required {
+    return;
+  }
+  let distance = |index: usize| segment_distance(&points[index], &points[0], &points[last]);
+  let mut candidates: Vec<usize> = (1..last).filter(|index| !keep[*index]).collect();
+  candidates.sort_by(|a, b| distance(*b).total_cmp(&distance(*a)).then(a.cmp(b)));
+  for index in candidates.into_iter().take(required - kept) {
+    keep[index] = true;
+  }
+}
+
+/// 返回各点是否保留，首尾两点始终保留
+fn simplify_mask(points: &[Position], simplify: &SimplifyOptions) -> Vec<bool> {
+  match simplify.method {
+    SimplifyMethod::DouglasPeucker => douglas_peucker(points, simplify.tolerance),
+    SimplifyMethod::VisvalingamWhyatt => visvalingam_whyatt(points, simplify.tolerance),
+  }
+}
+
+fn douglas_peucker(points: &[Position], tolerance: f64) -> Vec<bool> {
+  let last = points.len() - 1;
+  let mut keep = vec![false; points.len()];
+  keep[0] = true;
+  keep[last] = true;
+  let mut stack = vec![(0, last)];
+  while let Some((start, end)) = stack.pop() {
+    let farthest = (start + 1..end)
+      .map(|index| {
+        (
+          index,
+          segment_distance(&points[index], &points[start], &points[end]),
+        )
+      })
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some((index, distance)) = farthest {
+      if distance > tolerance {
+        keep[index] = true;
+        stack.push((start, index));
+        stack.push((index, end));
+      }
+    }
+  }
+  keep
+}
+
+fn segment_distance(point: &Position, start: &Position, end: &Position) -> f64 {
+  let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+  let length_squared = dx * dx + dy * dy;
+  let t = if length_squared > 0.0 {
+    (((point[0] - start[0]) * dx + (point[1] - start[1]) * dy) / length_squared).clamp(0.0, 1.0)
+  } else {
+    0.0
+  };
+  let (x, y) = (start[0] + t * dx, start[1] + t * dy);
+  ((point[0] - x).powi(2) + (point[1] - y).powi(2)).sqrt()
+}
+
+/// 有效面积的堆元素，面积相同时按序号排序保证结果稳定
+#[derive(PartialEq)]
+struct Candidate {
+  area: f64,
+  index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+  // BinaryHeap 为最大堆，反向比较使面积最小的先出堆
+  fn cmp(&self, other: &Self) -> Ordering {
+    other
+      .area
+      .total_cmp(&self.area)
+      .then(other.index.cmp(&self.index))
+  }
+}
+
+impl PartialOrd for Candidate {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+fn visvalingam_whyatt(points: &[Position], tolerance: f64) -> Vec<bool> {
+  let len = points.len();
+  let mut keep = vec![true; len];
+  let mut previous: Vec<usize> = (0..len).map(|index| index.saturating_sub(1)).collect();
+  let mut next: Vec<usize> = (0..len).map(|index| index + 1).collect();
+  let mut areas = vec![f64::INFINITY; len];
+  let mut heap = BinaryHeap::new();
+  for index in 1..len - 1 {
+    areas[index] = triangle_area(&points[index - 1], &points[index], &points[index + 1]);
+    heap.push(Candidate {
+      area: areas[index],
+      index,
+    });
+  }
+
+  while let Some(Candidate { area, index }) = heap.pop() {
+    // 跳过已删除或面积已更新的过期元素
+    if !keep[index] || area != areas[index] {
+      continue;
+    }
+    if area >= tolerance {
+      break;
+    }
+    keep[index] = false;
+    let (before, after) = (previous[index], next[index]);
+    next[before] = after;
+    previous[after] = before;
+    for neighbor in [before, after] {
+      if neighbor == 0 || neighbor == len - 1 {
+        continue;
+      }
+      // 邻点的面积不小于刚删除的点，保证按面积从小到大删除
+      let neighbor_area = triangle_area(
+        &points[previous[neighbor]],
+        &points[neighbor],
+        &points[next[neighbor]],
+      )
+      .max(area);
+      areas[neighbor] = neighbor_area;
+      heap.push(Candidate {
+        area: neighbor_area,
+        index: neighbor,
+      });
+    }
+  }
+  keep
+}
+
+fn triangle_area(a: &Position, b: &Position, c: &Position) -> f64 {
+  ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use geojson::Geometry;
+  use serde_json::json;
+
+  fn feature(value: Value, measures: Option<serde_json::Value>) -> Feature {
+    let mut properties = serde_json::Map::new();
+    if let Some(measures) = measures {
+      properties.insert("m".to_string(), measures);
+    }
+    Feature {
+      bbox: None,
+      geometry: Some(Geometry::new(value)),
+      id: None,
+      properties: Some(properties),
+      foreign_members: None,
+    }
+  }
+
+  fn options(method: SimplifyMethod, tolerance: f64, preserve_topology: bool) -> ConvertOptions {
+    ConvertOptions {
+      simplify: Some(SimplifyOptions {
+        method,
+        tolerance,
+        preserve_topology,
+      }),
+      ..ConvertOptions::default()
+    }
+  }
+
+  fn outer_ring(feature: &Feature) -> &Vec<Position> {
+    match &feature.geometry.as_ref().unwrap().value {
+      Value::Polygon(rings) => &rings[0],
+      _ => panic!("应为 Polygon"),
+    }
+  }
+
+  #[test]
+  fn shared_edge_stays_shared() {
+    // 两个面沿 x = 10 附近的折线相邻，两侧的顶点顺序相反
+    let edge = [
+      vec![10.0, 0.0],
+      vec![10.1, 2.0],
+      vec![10.05, 4.0],
+      vec![10.5, 6.0],
+      vec![10.02, 8.0],
+      vec![10.0, 10.0],
+    ];
+    let mut left = vec![vec![0.0, 0.0]];
+    left.extend(edge.iter().cloned());
+    left.extend([vec![0.0, 10.0], vec![0.0, 0.0]]);
+    let mut right = vec![vec![20.0, 0.0], vec![20.0, 10.0]];
+    right.extend(edge.iter().rev().cloned());
+    right.push(vec![20.0, 0.0]);
+
+    for (method, tolerance) in [
+      (SimplifyMethod::DouglasPeucker, 0.2),
+      (SimplifyMethod::VisvalingamWhyatt, 0.5),
+    ] {
+      let mut features = vec![
+        feature(Value::Polygon(vec![left.clone()]), None),
+        feature(Value::Polygon(vec![right.clone()]), None),
+      ];
+      simplify_features(&mut features, &options(method, tolerance, true)).unwrap();
+      let shared = |feature: &Feature| {
+        let mut points: Vec<Position> = outer_ring(feature)
+          .iter()
+          .filter(|position| edge.contains(position))
+          .cloned()
+          .collect();
+        points.sort_by(compare_points);
+        points.dedup();
+        points
+      };
+      let (left_edge, right_edge) = (shared(&features[0]), shared(&features[1]));
+      assert!(left_edge.len() < edge.len());
+      assert_eq!(left_edge, right_edge);
+    }
+  }
+
+  #[test]
+  fn small_neighbor_keeps_shared_chain() {
+    // 狭长三角形与右侧的面共用 (10,0)-(10.1,5)-(10,10) 这段边界，三角形本身无法再化简
+    let triangle = vec![
+      vec![10.0, 0.0],
+      vec![10.1, 5.0],
+      vec![10.0, 10.0],
+      vec![10.0, 0.0],
+    ];
+    let neighbor = vec![
+      vec![10.0, 0.0],
+      vec![20.0, 0.0],
+      vec![20.0, 10.0],
+      vec![10.0, 10.0],
+      vec![10.1, 5.0],
+      vec![10.0, 0.0],
+    ];
+    for (method, tolerance) in [
+      (SimplifyMethod::DouglasPeucker, 1.0),
+      (SimplifyMethod::VisvalingamWhyatt, 10.0),
+    ] {
+      let mut features = vec![
+        feature(Value::Polygon(vec![triangle.clone()]), None),
+        feature(Value::Polygon(vec![neighbor.clone()]), None),
+      ];
+      simplify_features(&mut features, &options(method, tolerance, true)).unwrap();
+      let shared = vec![10.1, 5.0];
+      assert_eq!(outer_ring(&features[0]), &triangle);
+      assert!(outer_ring(&features[1]).contains(&shared));
+    }
+  }
+
+  #[test]
+  fn rings_keep_at_least_four_points() {
+    let square = vec![
+      vec![0.0, 0.0],
+      vec![5.0, 0.01],
+      vec![10.0, 0.0],
+      vec![10.0, 10.0],
+      vec![0.0, 10.0],
+      vec![0.0, 0.0],
+    ];
+    for method in [
+      SimplifyMethod::DouglasPeucker,
+      SimplifyMethod::VisvalingamWhyatt,
+    ] {
+      for preserve_topology in [false, true] {
+        let mut features = vec![feature(Value::Polygon(vec![square.clone()]), None)];
+        simplify_features(&mut features, &options(method, 1000.0, preserve_topology)).unwrap();
+        let ring = outer_ring(&features[0]);
+        assert!(ring.len() >= 4);
+        assert_eq!(ring[0], ring[ring.len() - 1]);
+      }
+    }
+  }
+
+  #[test]
+  fn measures_stay_aligned() {
+    let line = vec![
+      vec![0.0, 0.0],
+      vec![1.0, 0.01],
+      vec![2.0, 0.0],
+      vec![3.0, 5.0],
+      vec![4.0, 0.0],
+    ];
+    let mut features = vec![feature(
+      Value::LineString(line),
+      Some(json!([0.0, 1.0, 2.0, 3.0, 4.0])),
+    )];
+    simplify_features(
+      &mut features,
+      &options(SimplifyMethod::DouglasPeucker, 0.1, false),
+    )
+    .unwrap();
+    let Value::LineString(line) = &features[0].geometry.as_ref().unwrap().value else {
+      panic!("应为 LineString");
+    };
+    let measures = features[0].properties.as_ref().unwrap()["m"]
+      .as_array()
+      .unwrap();
+    assert_eq!(line.len(), 4);
+    assert_eq!(line.len(), measures.len());
+    for (position, measure) in line.iter().zip(measures) {
+      assert_eq!(position[0], measure.as_f64().unwrap());
+    }
+
+    // 面的 M 值按环嵌套，同样随坐标删除
+    let ring = vec![
+      vec![0.0, 0.0],
+      vec![10.0, 0.0],
+      vec![10.0, 10.0],
+      vec![0.0, 10.0],
+      vec![0.0, 5.0],
+      vec![0.0, 0.0],
+    ];
+    let mut features = vec![feature(
+      Value::Polygon(vec![ring]),
+      Some(json!([[0.0, 1.0, 2.0, 3.0, 4.0, 0.0]])),
+    )];
+    simplify_features(
+      &mut features,
+      &options(SimplifyMethod::VisvalingamWhyatt, 1.0, false),
+    )
+    .unwrap();
+    let measures = features[0].properties.as_ref().unwrap()["m"][0]
+      .as_array()
+      .unwrap();
+    assert_eq!(outer_ring(&features[0]).len(), 5);
+    assert_eq!(measures.len(), 5);
+  }
+}
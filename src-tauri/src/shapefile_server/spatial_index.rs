@@ -0,0 +1,275 @@
+use once_cell::sync::Lazy;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use super::schema::{SHP_HEADER_SIZE, SHP_RECORD_HEADER_SIZE, SHX_RECORD_SIZE};
+use super::shapefile_to_geojson::CustomError;
+use crate::utils;
+
+// 索引缓存文件格式：魔数、版本、.shp 与 .shx 的大小和修改时间、记录数，随后为每条记录的序号与范围
+const INDEX_MAGIC: &[u8; 4] = b"SIDX";
+const INDEX_VERSION: u32 = 2;
+const INDEX_HEADER_SIZE: usize = 44;
+
+type IndexEntry = GeomWithData<Rectangle<[f64; 2]>, u32>;
+type IndexCache = HashMap<PathBuf, (SourceStamp, Arc<SpatialIndex>)>;
+
+/// 索引的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexSource {
+  /// 进程内缓存
+  Memory,
+  /// 工作空间中的索引文件
+  Cache,
+  /// 本次扫描 .shp 新建
+  Built,
+}
+
+/// 用于判断缓存是否过期的 .shp 与 .shx 的大小和修改时间
+///
+/// 索引按 .shx 中的偏移读取记录，只替换 .shx 时缓存同样失效
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SourceStamp {
+  shp: FileStamp,
+  shx: FileStamp,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct FileStamp {
+  len: u64,
+  modified: u64,
+}
+
+impl SourceStamp {
+  fn read(shp_path: &Path) -> Result<Self, CustomError> {
+    // 缺少 .shx 时建立索引会报错，这里不提前处理
+    Ok(SourceStamp {
+      shp: FileStamp::read(shp_path)?,
+      shx: FileStamp::read(&shp_path.with_extension("shx")).unwrap_or_default(),
+    })
+  }
+}
+
+impl FileStamp {
+  fn read(path: &Path) -> Result<Self, CustomError> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+      .modified()?
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_nanos() as u64)
+      .unwrap_or_default();
+    Ok(FileStamp {
+      len: metadata.len(),
+      modified,
+    })
+  }
+}
+
+static INDEX_CACHE: Lazy<Mutex<IndexCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 基于每条记录外包矩形的 R 树索引，空图形不进入索引
+pub struct SpatialIndex {
+  tree: RTree<IndexEntry>,
+}
+
+impl SpatialIndex {
+  /// 依次使用进程内缓存、工作空间索引文件，都不可用时扫描 .shp 新建并写入缓存
+  pub fn load_or_build(shp_path: &Path) -> Result<(Arc<Self>, IndexSource), CustomError> {
+    let shp_path = fs::canonicalize(shp_path)?;
+    let stamp = SourceStamp::read(&shp_path)?;
+    if let Some((cached_stamp, index)) = INDEX_CACHE
+      .lock()
+      .ok()
+      .and_then(|cache| cache.get(&shp_path).cloned())
+    {
+      if cached_stamp == stamp {
+        return Ok((index, IndexSource::Memory));
+      }
+    }
+
+    let cache_path = cache_path(&shp_path);
+    let (entries, source) = match read_cache(&cache_path, stamp) {
+      Some(entries) => (entries, IndexSource::Cache),
+      None => {
+        let entries = scan_record_bounds(&shp_path)?;
+        // 写缓存失败不影响本次查询
+        if let Err(e) = write_cache(&cache_path, stamp, &entries) {
+          log::warn!("写入空间索引缓存失败: {}", e);
+        }
+        (entries, IndexSource::Built)
+      }
+    };
+
+    let index = Arc::new(SpatialIndex {
+      tree: RTree::bulk_load(entries),
+    });
+    if let Ok(mut cache) = INDEX_CACHE.lock() {
+      cache.insert(shp_path, (stamp, index.clone()));
+    }
+    Ok((index, source))
+  }
+
+  /// 返回外包矩形与 `bbox` 相交的记录序号及其范围，按序号排序
+  pub fn query(&self, bbox: [f64; 4]) -> Vec<(usize, [f64; 4])> {
+    let envelope = AABB::from_corners([bbox[0], bbox[1]], [bbox[2], bbox[3]]);
+    let mut hits: Vec<(usize, [f64; 4])> = self
+      .tree
+      .locate_in_envelope_intersecting(&envelope)
+      .map(|entry| {
+        let (lower, upper) = (entry.geom().lower(), entry.geom().upper());
+        (
+          entry.data as usize,
+          [lower[0], lower[1], upper[0], upper[1]],
+        )
+      })
+      .collect();
+    hits.sort_unstable_by_key(|(index, _)| *index);
+    hits
+  }
+}
+
+/// 缓存文件名取路径的 FNV-1a 哈希，`DefaultHasher` 的结果在不同 Rust 版本间不保证一致
+fn cache_path(shp_path: &Path) -> PathBuf {
+  let hash = shp_path
+    .to_string_lossy()
+    .bytes()
+    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+      (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+  utils::files::get_spatial_index_path().join(format!("{:016x}.sidx", hash))
+}
+
+/// 通过 .shx 定位每条记录，只读取记录开头的图形类型与范围
+fn scan_record_bounds(shp_path: &Path) -> Result<Vec<IndexEntry>, CustomError> {
+  let shx_path = shp_path.with_extension("shx");
+  let shx = fs::read(&shx_path).map_err(|_| {
+    CustomError(format!(
+      "缺少 .shx 索引文件，无法建立空间索引: {}",
+      shx_path.display()
+    ))
+  })?;
+  let mut shp = BufReader::new(File::open(shp_path)?);
+
+  let mut entries = Vec::new();
+  let records = shx
+    .get(SHP_HEADER_SIZE..)
+    .unwrap_or_default()
+    .chunks_exact(SHX_RECORD_SIZE as usize);
+  for (index, record) in records.enumerate() {
+    // .shx 中的偏移以 16 位字为单位，大端序
+    let offset = u32::from_be_bytes([record[0], record[1], record[2], record[3]]) as u64 * 2;
+    shp.seek(SeekFrom::Start(offset + SHP_RECORD_HEADER_SIZE as u64))?;
+    let mut content = [0u8; 36];
+    let mut read = 0;
+    while read < content.len() {
+      match shp.read(&mut content[read..])? {
+        0 => break,
+        n => read += n,
+      }
+    }
+    if read < 4 {
+      continue;
+    }
+    let value = |position: usize| {
+      let mut buf = [0u8; 8];
+      buf.copy_from_slice(&content[position..position + 8]);
+      f64::from_le_bytes(buf)
+    };
+    let shape_type = i32::from_le_bytes([content[0], content[1], content[2], content[3]]);
+    let bounds = match shape_type {
+      // 点类型没有范围，使用坐标本身
+      1 | 11 | 21 if read >= 20 => [value(4), value(12), value(4), value(12)],
+      3 | 5 | 8 | 13 | 15 | 18 | 23 | 25 | 28 | 31 if read >= 36 => {
+        [value(4), value(12), value(20), value(28)]
+      }
+      _ => continue,
+    };
+    if bounds.iter().all(|value| value.is_finite()) {
+      entries.push(IndexEntry::new(
+        Rectangle::from_corners([bounds[0], bounds[1]], [bounds[2], bounds[3]]),
+        index as u32,
+      ));
+    }
+  }
+  Ok(entries)
+}
+
+fn read_cache(cache_path: &Path, stamp: SourceStamp) -> Option<Vec<IndexEntry>> {
+  let mut reader = BufReader::new(File::open(cache_path).ok()?);
+  let mut header = [0u8; INDEX_HEADER_SIZE];
+  reader.read_exact(&mut header).ok()?;
+  let u32_at = |position: usize| {
+    u32::from_le_bytes(
+      header[position..position + 4]
+        .try_into()
+        .unwrap_or_default(),
+    )
+  };
+  let u64_at = |position: usize| {
+    u64::from_le_bytes(
+      header[position..position + 8]
+        .try_into()
+        .unwrap_or_default(),
+    )
+  };
+  if &header[..4] != INDEX_MAGIC
+    || u32_at(4) != INDEX_VERSION
+    || u64_at(8) != stamp.shp.len
+    || u64_at(16) != stamp.shp.modified
+    || u64_at(24) != stamp.shx.len
+    || u64_at(32) != stamp.shx.modified
+  {
+    return None;
+  }
+
+  let count = u32_at(40) as usize;
+  let mut entries = Vec::with_capacity(count);
+  let mut entry = [0u8; 36];
+  for _ in 0..count {
+    reader.read_exact(&mut entry).ok()?;
+    let value = |position: usize| {
+      f64::from_le_bytes(entry[position..position + 8].try_into().unwrap_or_default())
+    };
+    let index = u32::from_le_bytes(entry[..4].try_into().unwrap_or_default());
+    entries.push(IndexEntry::new(
+      Rectangle::from_corners([value(4), value(12)], [value(20), value(28)]),
+      index,
+    ));
+  }
+  Some(entries)
+}
+
+fn write_cache(
+  cache_path: &Path,
+  stamp: SourceStamp,
+  entries: &[IndexEntry],
+) -> Result<(), CustomError> {
+  if let Some(parent) = cache_path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let mut writer = BufWriter::new(File::create(cache_path)?);
+  writer.write_all(INDEX_MAGIC)?;
+  writer.write_all(&INDEX_VERSION.to_le_bytes())?;
+  for file_stamp in [stamp.shp, stamp.shx] {
+    writer.write_all(&file_stamp.len.to_le_bytes())?;
+    writer.write_all(&file_stamp.modified.to_le_bytes())?;
+  }
+  writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+  for entry in entries {
+    let (lower, upper) = (entry.geom().lower(), entry.geom().upper());
+    writer.write_all(&entry.data.to_le_bytes())?;
+    for value in [lower[0], lower[1], upper[0], upper[1]] {
+      writer.write_all(&value.to_le_bytes())?;
+    }
+  }
+  writer.flush()?;
+  Ok(())
+}
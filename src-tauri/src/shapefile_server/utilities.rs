@@ -1,169 +1,627 @@
-use super::shapefile_to_geojson::convert_shapefile_to_geojson;
+use super::dataset_info::dataset_info;
+use super::dbf::{insert_row_record, renamed_field_warnings, renamed_row_fields};
+use super::dbf_edit::{self, EditSession, RecordEdit};
+use super::dbf_schema::{change_fields, FieldChange};
+use super::encoding::{detect_encoding, open_reader, DetectedEncoding};
+use super::field_stats::{field_statistics, FieldStatsOptions};
+use super::geojson_to_shapefile::{
+  export_features_to_shapefile, parse_features, ShapefileExportOptions,
+};
+use super::geojson_writer::OutputOptions;
+use super::geometry::{
+  collapse_single_line, geometry_to_wkt, shape_to_geometry, ConversionCounts, ConvertOptions,
+  GeometryFormat, RecordOutcome, ShapeOutcome,
+};
+use super::ogr::{convert_layer_to_geojson, list_layers};
+use super::projection::read_source_crs;
+use super::record_query::{query_records, RecordQuery};
+use super::repair::{repair_shapefile, RepairOptions};
+use super::schema::{read_dbf_header, read_shp_header, read_shx_record_count};
+use super::shapefile_to_geojson::{
+  convert_shapefile_to_geojson, export_shapefile_to_geojson, query_shapefile_bbox,
+  GeojsonConversion,
+};
+use super::shapefile_to_geojson::{count_records, CustomError};
+use super::validation::validate_shapefile;
+use super::wkb::{geometry_to_wkb_hex, WkbFlavor};
+use crate::utils::progress::{ProgressPhase, ProgressReporter};
 use crate::utils::response::create_response;
 use futures::{stream, StreamExt};
-use geo_types::{LineString, Point, Polygon};
-use regex::Regex;
-use shapefile::{dbase, PolygonRing, Shape};
-use std::{path::Path, sync::Arc};
-use tokio::sync::Mutex;
-use wkt::Wkt;
-
-pub async fn shapefile_to_geojson(shapefile_path: &str) -> Result<serde_json::Value, String> {
-  let feature_collection = convert_shapefile_to_geojson(shapefile_path)
+use serde_json::json;
+use shapefile::{dbase, Shape};
+use std::path::Path;
+
+pub async fn shapefile_to_geojson(
+  shapefile_path: &str,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<serde_json::Value, String> {
+  let conversion =
+    convert_shapefile_to_geojson(shapefile_path, options, encoding, target_crs, progress)
+      .await
+      .map_err(|e| format!("转换失败: {}", e))?;
+
+  geojson_response(conversion, progress)
+}
+
+/// 列出 OGR 数据源的驱动与图层
+pub async fn ogr_list_layers(path: &str) -> Result<serde_json::Value, String> {
+  let path = path.to_string();
+  let (driver, layers) = tokio::task::spawn_blocking(move || list_layers(&path))
+    .await
+    .map_err(|e| format!("读取任务异常退出: {}", e))?
+    .map_err(|e| format!("读取图层失败: {}", e))?;
+  Ok(create_response(
+    true,
+    Some(json!({
+      "driver": driver,
+      "layers": layers,
+    })),
+    "成功".to_string(),
+  ))
+}
+
+/// 读取矢量或栅格数据的驱动、图层、范围、坐标系与波段信息
+pub async fn dataset_info_summary(path: &str) -> Result<serde_json::Value, String> {
+  let path = path.to_string();
+  let info = tokio::task::spawn_blocking(move || dataset_info(&path))
     .await
-    .map_err(|e| format!("转换失败: {}", e));
+    .map_err(|e| format!("读取任务异常退出: {}", e))?
+    .map_err(|e| format!("读取数据信息失败: {}", e))?;
+  Ok(create_response(true, Some(json!(info)), "成功".to_string()))
+}
 
-  let geojson_output = serde_json::to_string_pretty(&feature_collection);
+/// 读取 OGR 图层，返回结构与 `shapefile_to_geojson` 相同
+pub async fn ogr_to_geojson(
+  path: &str,
+  layer: Option<&str>,
+  options: ConvertOptions,
+  source_crs: Option<&str>,
+  target_crs: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<serde_json::Value, String> {
+  let path = path.to_string();
+  let layer = layer.map(str::to_string);
+  let source_crs = source_crs.map(str::to_string);
+  let target_crs = target_crs.map(str::to_string);
+  let task_progress = progress.clone();
+  let conversion = tokio::task::spawn_blocking(move || {
+    convert_layer_to_geojson(
+      &path,
+      layer.as_deref(),
+      options,
+      source_crs.as_deref(),
+      target_crs.as_deref(),
+      &task_progress,
+    )
+  })
+  .await
+  .map_err(|e| format!("转换任务异常退出: {}", e))?
+  .map_err(|e| format!("转换失败: {}", e))?;
 
-  match geojson_output {
-    Ok(geojson) => Ok(create_response(true, Some(geojson), "成功".to_string())),
-    Err(e) => return Err(format!("序列化失败: {}", e)),
-  }
+  geojson_response(conversion, progress)
+}
+
+fn geojson_response(
+  conversion: GeojsonConversion,
+  progress: &ProgressReporter,
+) -> Result<serde_json::Value, String> {
+  let geojson = serde_json::to_string_pretty(&conversion.feature_collection)
+    .map_err(|e| format!("序列化失败: {}", e))?;
+
+  Ok(create_response(
+    true,
+    Some(json!({
+      "jobId": progress.job_id(),
+      "geojson": geojson,
+      "nullCount": conversion.counts.null_count,
+      "skippedCount": conversion.counts.skipped_count,
+      "vertexCount": conversion.vertex_counts,
+      "encoding": conversion.encoding.to_json(),
+      "crs": {
+        "source": conversion.source_crs,
+        "target": conversion.target_crs,
+      },
+      "warnings": collect_warnings(&conversion.counts, progress),
+    })),
+    "成功".to_string(),
+  ))
+}
+
+/// 转换结果直接写入工作空间文件，仅返回文件路径、要素数量与范围
+pub async fn shapefile_to_geojson_file(
+  shapefile_path: &str,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+  output: OutputOptions,
+  progress: &ProgressReporter,
+) -> Result<serde_json::Value, String> {
+  let shapefile_path = shapefile_path.to_string();
+  let encoding = encoding.map(str::to_string);
+  let target_crs = target_crs.map(str::to_string);
+  let task_progress = progress.clone();
+  let export = tokio::task::spawn_blocking(move || {
+    export_shapefile_to_geojson(
+      &shapefile_path,
+      options,
+      encoding.as_deref(),
+      target_crs.as_deref(),
+      &output,
+      &task_progress,
+    )
+    .map_err(|e| e.to_string())
+  })
+  .await
+  .map_err(|e| format!("转换任务异常退出: {}", e))?
+  .map_err(|e| format!("转换失败: {}", e))?;
+
+  Ok(create_response(
+    true,
+    Some(json!({
+      "jobId": progress.job_id(),
+      "path": export.path,
+      "featureCount": export.feature_count,
+      "bbox": export.bbox,
+      "nullCount": export.counts.null_count,
+      "skippedCount": export.counts.skipped_count,
+      "vertexCount": export.vertex_counts,
+      "encoding": export.encoding.to_json(),
+      "crs": {
+        "source": export.source_crs,
+        "target": export.target_crs,
+      },
+      "warnings": collect_warnings(&export.counts, progress),
+    })),
+    "成功".to_string(),
+  ))
 }
 
 pub async fn shapefile_to_record(
   shapefile_path: &str,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  progress: &ProgressReporter,
 ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
   let base_path = Path::new(shapefile_path);
   let shp_path = base_path.with_extension("shp");
-  let mut shp_reader = shapefile::Reader::from_path(shp_path.clone())?;
+  let dbf_path = base_path.with_extension("dbf");
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  // 属性表保留原始坐标，.prj 仅用于 EWKB 的 SRID
+  let srid = read_source_crs(&base_path.with_extension("prj"))
+    .ok()
+    .and_then(|crs| crs.epsg)
+    .and_then(|epsg| u32::try_from(epsg).ok());
+  let renamed = renamed_fields(&dbf_path, &encoding)?;
+  let mut shp_reader = open_reader(&shp_path, &encoding)?;
+  let all_count = count_records(&shp_path, &dbf_path, progress)?;
   let shape_records: Vec<_> = shp_reader.iter_shapes_and_records().collect();
-  let properties = Arc::new(Mutex::new(Vec::new()));
 
-  let tasks = stream::iter(shape_records.into_iter())
-    .map(|shape_record| {
-      let properties = Arc::clone(&properties);
-      tokio::spawn(async move { process_shape_record(shape_record, properties).await })
+  progress.start_phase(ProgressPhase::Converting, Some(all_count));
+  // buffered 按输入顺序产出结果，保证记录顺序与文件一致
+  let tasks = stream::iter(shape_records.into_iter().enumerate())
+    .map(|(index, shape_record)| {
+      let progress = progress.clone();
+      tokio::spawn(async move {
+        process_shape_record(index, shape_record, options, srid, progress).await
+      })
     })
-    .buffer_unordered(num_cpus::get());
-  tasks.for_each(|_| async {}).await;
+    .buffered(num_cpus::get());
+  let (data, counts) = tasks
+    .fold(
+      (Vec::new(), ConversionCounts::default()),
+      |(mut data, mut counts), result| async move {
+        match result.ok().and_then(|outcome| outcome.ok()) {
+          Some((row, outcome)) => {
+            data.push(row);
+            counts.add(Some(outcome));
+          }
+          None => counts.add(None),
+        }
+        (data, counts)
+      },
+    )
+    .await;
+  if progress.is_cancelled() {
+    return Err("任务已取消".into());
+  }
+  progress.finish();
+  let mut warnings = renamed_field_warnings(&renamed);
+  warnings.extend(collect_warnings(&counts, progress));
 
-  let data = properties.lock().await.clone();
-  Ok(create_response(true, Some(data), "成功".to_string()))
+  Ok(create_response(
+    true,
+    Some(json!({
+      "jobId": progress.job_id(),
+      "records": data,
+      "nullCount": counts.null_count,
+      "skippedCount": counts.skipped_count,
+      "encoding": encoding.to_json(),
+      "warnings": warnings,
+    })),
+    "成功".to_string(),
+  ))
 }
 
-async fn process_shape_record(
-  shape_record: Result<(Shape, dbase::Record), shapefile::Error>,
-  properties: Arc<Mutex<Vec<serde_json::Value>>>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-  let (shape, record) = shape_record?;
+/// 按范围查询要素，空间索引在首次查询时建立并缓存
+pub async fn shapefile_bbox_query(
+  shapefile_path: &str,
+  bbox: [f64; 4],
+  zoom: Option<f64>,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+  target_crs: Option<&str>,
+) -> Result<serde_json::Value, String> {
+  let shapefile_path = shapefile_path.to_string();
+  let encoding = encoding.map(str::to_string);
+  let target_crs = target_crs.map(str::to_string);
+  let (conversion, index_source) = tokio::task::spawn_blocking(move || {
+    query_shapefile_bbox(
+      &shapefile_path,
+      bbox,
+      zoom,
+      options,
+      encoding.as_deref(),
+      target_crs.as_deref(),
+    )
+    .map_err(|e| e.to_string())
+  })
+  .await
+  .map_err(|e| format!("查询任务异常退出: {}", e))?
+  .map_err(|e| format!("查询失败: {}", e))?;
 
-  let mut propertie = serde_json::Map::new();
-  let re_numeric = Regex::new(r"Numeric\(Some\(([0-9]+(\.[0-9]+)?)\)\)").unwrap();
-  let re_character = Regex::new(r#"^Character\(Some\("(.+)"\)\)"#).unwrap();
-  let wkt_value = match shape {
-    Shape::Point(_) => process_point_wkt(&shape),
-    Shape::Polyline(_) => process_line_wkt(&shape),
-    Shape::Polygon(_) => process_polygon_wkt(&shape),
-    _ => Err("Unsupported shape type".to_string()),
-  };
+  let geojson = serde_json::to_string(&conversion.feature_collection)
+    .map_err(|e| format!("序列化失败: {}", e))?;
+  Ok(create_response(
+    true,
+    Some(json!({
+      "geojson": geojson,
+      "featureCount": conversion.feature_collection.features.len(),
+      "index": index_source,
+      "nullCount": conversion.counts.null_count,
+      "skippedCount": conversion.counts.skipped_count,
+      "vertexCount": conversion.vertex_counts,
+      "encoding": conversion.encoding.to_json(),
+      "crs": {
+        "source": conversion.source_crs,
+        "target": conversion.target_crs,
+      },
+      "warnings": conversion.counts.warnings(),
+    })),
+    "成功".to_string(),
+  ))
+}
 
-  let wkt_string = match wkt_value {
-    Ok(wkt) => wkt.to_string(),
-    Err(e) => format!("Error converting shape to WKT: {}", e),
-  };
-  propertie.insert("wkt".to_string(), serde_json::Value::String(wkt_string));
-
-  for (field, value) in record.into_iter() {
-    let value_string = value.to_string();
-    let value_json = match value_string.as_str() {
-      "Numeric(None)" => serde_json::Value::Null,
-      "Character(None)" => serde_json::Value::Null,
-      _ => {
-        if let Some(caps) = re_numeric.captures(&value_string) {
-          let number_str = caps.get(1).map_or("", |m| m.as_str());
-          if let Ok(number) = number_str.parse::<f64>() {
-            serde_json::json!(number)
-          } else {
-            serde_json::json!(value_string)
-          }
-        } else if let Some(caps) = re_character.captures(&value_string) {
-          let string = caps.get(1).map_or("", |m| m.as_str());
-          serde_json::json!(string)
-        } else {
-          serde_json::json!(value_string)
-        }
+/// 检查数据集并返回问题列表，每个问题带有级别与记录序号
+pub async fn shapefile_validate(
+  shapefile_path: &str,
+  encoding: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<serde_json::Value, String> {
+  let shapefile_path = shapefile_path.to_string();
+  let encoding = encoding.map(str::to_string);
+  let task_progress = progress.clone();
+  let report = tokio::task::spawn_blocking(move || {
+    validate_shapefile(&shapefile_path, encoding.as_deref(), &task_progress)
+  })
+  .await
+  .map_err(|e| format!("检查任务异常退出: {}", e))?
+  .map_err(|e| format!("检查失败: {}", e))?;
+  Ok(create_response(
+    true,
+    Some(json!(report)),
+    "成功".to_string(),
+  ))
+}
+
+/// 修复数据集并写入工作空间，返回输出路径与修复记录
+pub async fn shapefile_repair(
+  shapefile_path: &str,
+  options: RepairOptions,
+  encoding: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<serde_json::Value, String> {
+  let shapefile_path = shapefile_path.to_string();
+  let encoding = encoding.map(str::to_string);
+  let task_progress = progress.clone();
+  let report = tokio::task::spawn_blocking(move || {
+    repair_shapefile(
+      &shapefile_path,
+      &options,
+      encoding.as_deref(),
+      &task_progress,
+    )
+  })
+  .await
+  .map_err(|e| format!("修复任务异常退出: {}", e))?
+  .map_err(|e| format!("修复失败: {}", e))?;
+  Ok(create_response(
+    true,
+    Some(json!(report)),
+    "成功".to_string(),
+  ))
+}
+
+/// 将 GeoJSON 导出为 shapefile，`geojson` 与 `geojson_path` 二选一
+pub async fn geojson_to_shapefile(
+  geojson: Option<serde_json::Value>,
+  geojson_path: Option<String>,
+  options: ShapefileExportOptions,
+) -> Result<serde_json::Value, String> {
+  let export = tokio::task::spawn_blocking(move || {
+    let geojson = match (geojson, geojson_path) {
+      (Some(geojson), _) => geojson,
+      (None, Some(path)) => {
+        let text =
+          std::fs::read_to_string(&path).map_err(|e| format!("读取 GeoJSON 文件失败: {}", e))?;
+        serde_json::from_str(&text).map_err(|e| format!("解析 GeoJSON 文件失败: {}", e))?
       }
+      (None, None) => return Err("缺少 geojson 或 geojsonPath".to_string()),
     };
-    propertie.insert(field.to_string(), value_json);
-  }
-  properties
-    .lock()
+    let features = parse_features(geojson).map_err(|e| e.to_string())?;
+    export_features_to_shapefile(features, &options).map_err(|e| e.to_string())
+  })
+  .await
+  .map_err(|e| format!("导出任务异常退出: {}", e))?
+  .map_err(|e| format!("导出失败: {}", e))?;
+
+  Ok(create_response(
+    true,
+    Some(json!({
+      "files": export.files,
+      "fields": export.fields,
+      "warnings": export.warnings,
+    })),
+    "成功".to_string(),
+  ))
+}
+
+/// 在阻塞线程中执行属性编辑操作，返回会话信息
+async fn run_edit<F>(operation: F) -> Result<serde_json::Value, String>
+where
+  F: FnOnce() -> Result<EditSession, CustomError> + Send + 'static,
+{
+  let session = tokio::task::spawn_blocking(operation)
     .await
-    .push(serde_json::Value::Object(propertie));
-  Ok(())
-}
-
-fn process_polygon_wkt(shape: &Shape) -> Result<Wkt<f64>, String> {
-  let wkt_value = match shape {
-    Shape::Polygon(polygon) => {
-      // 分离外环和内环
-      let mut exterior = None;
-      let mut interiors = Vec::new();
-
-      for ring in polygon.rings() {
-        match ring {
-          PolygonRing::Outer(points) => {
-            // 创建外环
-            let coords = points
-              .iter()
-              .map(|p| geo_types::Coord { x: p.x, y: p.y })
-              .collect();
-            let exterior_ring = LineString::new(coords);
-            exterior = Some(exterior_ring);
-          }
-          PolygonRing::Inner(points) => {
-            // 创建内环
-            let coords = points
-              .iter()
-              .map(|p| geo_types::Coord { x: p.x, y: p.y })
-              .collect();
-            interiors.push(LineString::new(coords));
-          }
-        };
-      }
+    .map_err(|e| format!("编辑任务异常退出: {}", e))?
+    .map_err(|e| e.to_string())?;
+  Ok(create_response(
+    true,
+    Some(json!(session)),
+    "成功".to_string(),
+  ))
+}
 
-      // 验证外环是否存在
-      match exterior {
-        Some(ext) => wkt::ToWkt::to_wkt(&Polygon::new(ext, interiors)),
-        None => return Err("Warning: Invalid polygon detected - missing outer ring".to_string()),
-      }
-    }
-    _ => return Err("Expected Polygon shape".to_string()),
-  };
-  Ok(wkt_value)
-}
-
-fn process_line_wkt(shape: &Shape) -> Result<Wkt<f64>, String> {
-  let wkt_value = match shape {
-    Shape::Polyline(polyline) => {
-      let points = polyline
-        .parts()
-        .iter()
-        .flat_map(|part| {
-          part.iter().map(|point| geo_types::Coord {
-            x: point.x,
-            y: point.y,
-          })
-        })
-        .collect();
-      let line = LineString::new(points);
-      wkt::ToWkt::to_wkt(&line)
-    }
-    _ => return Err("Expected Polygon shape".to_string()),
-  };
+pub async fn dbf_edit_begin(
+  shapefile_path: String,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  run_edit(move || dbf_edit::begin_edit(&shapefile_path, encoding.as_deref())).await
+}
+
+pub async fn dbf_edit_update(
+  session_id: String,
+  edits: Vec<RecordEdit>,
+) -> Result<serde_json::Value, String> {
+  run_edit(move || dbf_edit::update_records(&session_id, &edits)).await
+}
+
+pub async fn dbf_edit_commit(session_id: String) -> Result<serde_json::Value, String> {
+  run_edit(move || dbf_edit::commit_edit(&session_id)).await
+}
+
+pub async fn dbf_edit_rollback(session_id: String) -> Result<serde_json::Value, String> {
+  run_edit(move || dbf_edit::rollback_edit(&session_id)).await
+}
+
+pub fn dbf_edit_sessions() -> Result<serde_json::Value, String> {
+  let sessions = dbf_edit::list_sessions().map_err(|e| e.to_string())?;
+  Ok(create_response(
+    true,
+    Some(json!(sessions)),
+    "成功".to_string(),
+  ))
+}
 
-  Ok(wkt_value)
+/// 修改 DBF 字段结构，返回新的字段定义
+pub async fn dbf_change_fields(
+  shapefile_path: String,
+  changes: Vec<FieldChange>,
+  encoding: Option<String>,
+) -> Result<serde_json::Value, String> {
+  let header = tokio::task::spawn_blocking(move || {
+    change_fields(&shapefile_path, &changes, encoding.as_deref())
+  })
+  .await
+  .map_err(|e| format!("修改字段任务异常退出: {}", e))?
+  .map_err(|e| format!("修改字段失败: {}", e))?;
+  Ok(create_response(
+    true,
+    Some(json!({
+      "recordCount": header.record_count,
+      "fields": header.fields,
+    })),
+    "成功".to_string(),
+  ))
 }
 
-fn process_point_wkt(shape: &Shape) -> Result<Wkt<f64>, String> {
-  let wkt_value = match shape {
-    Shape::Point(point) => {
-      let geo_point = Point::new(point.x, point.y);
-      wkt::ToWkt::to_wkt(&geo_point)
-    }
-    _ => return Err("Expected Point shape".to_string()),
+/// 分页读取属性表，按条件排序与过滤
+pub async fn shapefile_record_page(
+  shapefile_path: &str,
+  query: RecordQuery,
+  options: ConvertOptions,
+  encoding: Option<&str>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+  let base_path = Path::new(shapefile_path);
+  let shp_path = base_path.with_extension("shp");
+  let dbf_path = base_path.with_extension("dbf");
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let renamed = renamed_fields(&dbf_path, &encoding)?;
+  let srid = read_source_crs(&base_path.with_extension("prj"))
+    .ok()
+    .and_then(|crs| crs.epsg)
+    .and_then(|epsg| u32::try_from(epsg).ok());
+
+  let page_query = query.clone();
+  let page = tokio::task::spawn_blocking(move || {
+    query_records(&shp_path, &page_query, &options, &encoding, srid)
+  })
+  .await??;
+
+  Ok(create_response(
+    true,
+    Some(json!({
+      "records": page.records,
+      "total": page.total,
+      "offset": query.offset,
+      "limit": query.limit,
+      "encoding": encoding.to_json(),
+      "warnings": renamed_field_warnings(&renamed),
+    })),
+    "成功".to_string(),
+  ))
+}
+
+/// 统计字段的值并计算分级断点，用于专题图配色
+pub async fn shapefile_field_stats(
+  shapefile_path: &str,
+  options: FieldStatsOptions,
+  encoding: Option<&str>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+  let dbf_path = Path::new(shapefile_path).with_extension("dbf");
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let (record_count, fields) =
+    tokio::task::spawn_blocking(move || field_statistics(&dbf_path, &options, &encoding)).await??;
+
+  Ok(create_response(
+    true,
+    Some(json!({
+      "recordCount": record_count,
+      "fields": fields,
+      "encoding": encoding.to_json(),
+    })),
+    "成功".to_string(),
+  ))
+}
+
+/// 读取图层结构，只解析 .shp/.shx/.dbf 文件头与 .prj
+pub fn shapefile_schema(
+  shapefile_path: &str,
+  encoding: Option<&str>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+  let base_path = Path::new(shapefile_path);
+  let dbf_path = base_path.with_extension("dbf");
+  let shp_header = read_shp_header(&base_path.with_extension("shp"))?;
+  let encoding = detect_encoding(&dbf_path, encoding)?;
+  let dbf_header = read_dbf_header(&dbf_path, &encoding)?;
+
+  let mut warnings = Vec::new();
+  match read_shx_record_count(&base_path.with_extension("shx")) {
+    None => warnings.push("缺少 .shx 索引文件".to_string()),
+    Some(shp_count) if shp_count != u64::from(dbf_header.record_count) => warnings.push(format!(
+      "SHP 数据（{} 条）与 DBF 数据（{} 条）的记录数不一致",
+      shp_count, dbf_header.record_count
+    )),
+    Some(_) => {}
+  }
+  let crs = read_source_crs(&base_path.with_extension("prj"))
+    .inspect_err(|e| warnings.push(e.to_string()))
+    .ok();
+
+  Ok(create_response(
+    true,
+    Some(json!({
+      "shapeType": shp_header.shape_type,
+      "bbox": shp_header.bbox,
+      "zRange": shp_header.z_range,
+      "mRange": shp_header.m_range,
+      "recordCount": dbf_header.record_count,
+      "fields": dbf_header.fields,
+      "encoding": encoding.to_json(),
+      "crs": crs,
+      "warnings": warnings,
+    })),
+    "成功".to_string(),
+  ))
+}
+
+async fn process_shape_record(
+  index: usize,
+  shape_record: Result<(Shape, dbase::Record), shapefile::Error>,
+  options: ConvertOptions,
+  srid: Option<u32>,
+  progress: ProgressReporter,
+) -> Result<(serde_json::Value, RecordOutcome), Box<dyn std::error::Error + Send + Sync>> {
+  if progress.is_cancelled() {
+    return Err("任务已取消".into());
+  }
+  let row = shape_record_to_row(index, shape_record, &options, srid);
+  progress.inc(1);
+  row
+}
+
+fn collect_warnings(counts: &ConversionCounts, progress: &ProgressReporter) -> Vec<String> {
+  let mut warnings = progress.warnings();
+  warnings.extend(counts.warnings());
+  warnings
+}
+
+/// 与属性表生成的列重名的 DBF 字段
+fn renamed_fields(
+  dbf_path: &Path,
+  encoding: &DetectedEncoding,
+) -> Result<Vec<(String, String)>, CustomError> {
+  let header = read_dbf_header(dbf_path, encoding)?;
+  Ok(renamed_row_fields(
+    header.fields.iter().map(|field| field.name.as_str()),
+  ))
+}
+
+/// 将一条记录转换为属性表行，`fid` 为从 0 开始的记录序号
+///
+/// 与 `fid`、`m` 及几何列重名的字段改名输出，见 `renamed_row_fields`
+pub(super) fn shape_record_to_row(
+  index: usize,
+  shape_record: Result<(Shape, dbase::Record), shapefile::Error>,
+  options: &ConvertOptions,
+  srid: Option<u32>,
+) -> Result<(serde_json::Value, RecordOutcome), Box<dyn std::error::Error + Send + Sync>> {
+  let (shape, record) = shape_record.inspect_err(|e| log::warn!("跳过无法读取的记录: {}", e))?;
+
+  let mut propertie = serde_json::Map::new();
+  propertie.insert("fid".to_string(), json!(index));
+  let shape_geometry =
+    shape_to_geometry(&shape, options).inspect_err(|e| log::warn!("跳过无法转换的图形: {}", e))?;
+  let shape_geometry = collapse_single_line(shape_geometry);
+  let shape_outcome = match shape_geometry.geometry {
+    Some(_) => ShapeOutcome::Converted,
+    None => ShapeOutcome::Null,
   };
 
-  Ok(wkt_value)
+  let geometry_value = match &shape_geometry.geometry {
+    Some(geometry) => serde_json::Value::String(match options.geometry_format {
+      GeometryFormat::Wkt => geometry_to_wkt(geometry),
+      GeometryFormat::Wkb => geometry_to_wkb_hex(geometry, WkbFlavor::Iso, None),
+      GeometryFormat::Ewkb => geometry_to_wkb_hex(geometry, WkbFlavor::Extended, srid),
+    }),
+    None => serde_json::Value::Null,
+  };
+  propertie.insert(
+    options.geometry_format.column_name().to_string(),
+    geometry_value,
+  );
+  if let Some(measures) = shape_geometry.measures {
+    propertie.insert("m".to_string(), measures);
+  }
+
+  let lossy = insert_row_record(&mut propertie, record);
+  Ok((
+    serde_json::Value::Object(propertie),
+    RecordOutcome {
+      shape: shape_outcome,
+      lossy,
+    },
+  ))
 }
@@ -0,0 +1,588 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use super::encoding::detect_encoding;
+use super::geometry::{point_in_ring, signed_area};
+use super::raw_shape::RawShape;
+use super::schema::{
+  read_dbf_header, read_shp_file_header, read_u32_be, SHP_FILE_CODE, SHP_HEADER_SIZE,
+  SHP_RECORD_HEADER_SIZE, SHX_RECORD_SIZE,
+};
+use super::shapefile_to_geojson::CustomError;
+use crate::utils::progress::{ProgressPhase, ProgressReporter};
+
+// 报告中最多保留的问题条数，超出后只计数
+const MAX_ISSUES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+  /// 数据损坏或不符合规范，多数软件无法正确读取
+  Error,
+  /// 可以读取，但结果可能与预期不符
+  Warning,
+  /// 不影响读取的提示，如缺少 `.prj`
+  Info,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+  pub severity: Severity,
+  /// 问题类型，如 `unclosedRing`、`selfIntersection`
+  pub code: &'static str,
+  /// 从 0 开始的记录序号，文件级问题为空
+  pub record_index: Option<u64>,
+  pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+  /// 没有错误级别的问题
+  pub valid: bool,
+  pub record_count: u64,
+  pub error_count: u64,
+  pub warning_count: u64,
+  pub info_count: u64,
+  pub issues: Vec<ValidationIssue>,
+  /// 问题超过上限，`issues` 未列全
+  pub truncated: bool,
+}
+
+impl ValidationReport {
+  fn add(
+    &mut self,
+    severity: Severity,
+    code: &'static str,
+    record_index: Option<u64>,
+    message: impl Into<String>,
+  ) {
+    match severity {
+      Severity::Error => self.error_count += 1,
+      Severity::Warning => self.warning_count += 1,
+      Severity::Info => self.info_count += 1,
+    }
+    if self.issues.len() < MAX_ISSUES {
+      self.issues.push(ValidationIssue {
+        severity,
+        code,
+        record_index,
+        message: message.into(),
+      });
+    } else {
+      self.truncated = true;
+    }
+  }
+}
+
+/// 检查 `.shp`/`.shx`/`.dbf` 的一致性与每条记录的几何有效性
+///
+/// 同步执行，调用方应放在 `spawn_blocking` 中
+pub fn validate_shapefile(
+  input_path: &str,
+  encoding: Option<&str>,
+  progress: &ProgressReporter,
+) -> Result<ValidationReport, CustomError> {
+  let base_path = Path::new(input_path);
+  let shp_path = base_path.with_extension("shp");
+  let mut report = ValidationReport::default();
+
+  let shp_len = fs::metadata(&shp_path)?.len();
+  let mut shp = BufReader::new(File::open(&shp_path)?);
+  let header = read_shp_file_header(&mut shp, &shp_path)?;
+  let header_shape_type = header.shape_type;
+  if header.file_code != SHP_FILE_CODE {
+    report.add(
+      Severity::Error,
+      "invalidHeader",
+      None,
+      ".shp 文件标识不是 9994",
+    );
+  }
+  let declared_len = header.file_length;
+  if declared_len != shp_len {
+    report.add(
+      Severity::Warning,
+      "fileLength",
+      None,
+      format!(
+        ".shp 文件头记录的长度为 {} 字节，实际为 {} 字节",
+        declared_len, shp_len
+      ),
+    );
+  }
+  let header_bbox = header.bbox;
+
+  let shx = read_shx(&base_path.with_extension("shx"), &mut report);
+  let shx_entries: Vec<(u64, u64)> = shx
+    .as_deref()
+    .map(|shx| {
+      shx
+        .chunks_exact(SHX_RECORD_SIZE as usize)
+        .map(|entry| {
+          (
+            u64::from(read_u32_be(entry, 0)) * 2,
+            u64::from(read_u32_be(entry, 4)) * 2,
+          )
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+
+  progress.start_phase(
+    ProgressPhase::Validating,
+    shx.is_some().then_some(shx_entries.len() as u64),
+  );
+  let mut offset = SHP_HEADER_SIZE as u64;
+  let mut record_count: u64 = 0;
+  let mut data_bbox: Option<[f64; 4]> = None;
+  while offset < shp_len {
+    if progress.is_cancelled() {
+      return Err(CustomError("任务已取消".to_string()));
+    }
+    let index = record_count;
+    let mut record_header = [0u8; SHP_RECORD_HEADER_SIZE];
+    if shp.read_exact(&mut record_header).is_err() {
+      report.add(
+        Severity::Error,
+        "truncatedRecord",
+        Some(index),
+        "文件末尾的记录头不完整",
+      );
+      break;
+    }
+    let record_number = read_u32_be(&record_header, 0);
+    let content_len = u64::from(read_u32_be(&record_header, 4)) * 2;
+    if offset + SHP_RECORD_HEADER_SIZE as u64 + content_len > shp_len {
+      report.add(
+        Severity::Error,
+        "truncatedRecord",
+        Some(index),
+        format!("记录内容长度为 {} 字节，超出文件末尾", content_len),
+      );
+      break;
+    }
+    let mut content = vec![0u8; content_len as usize];
+    shp.read_exact(&mut content)?;
+    record_count += 1;
+    progress.inc(1);
+
+    if u64::from(record_number) != index + 1 {
+      report.add(
+        Severity::Warning,
+        "recordNumber",
+        Some(index),
+        format!("记录号为 {}，应为 {}", record_number, index + 1),
+      );
+    }
+    if shx.is_some() {
+      match shx_entries.get(index as usize) {
+        Some(&(shx_offset, shx_len)) if shx_offset != offset || shx_len != content_len => {
+          report.add(
+            Severity::Error,
+            "shxMismatch",
+            Some(index),
+            format!(
+              ".shx 记录的偏移 {}、长度 {} 与 .shp 中的偏移 {}、长度 {} 不一致",
+              shx_offset, shx_len, offset, content_len
+            ),
+          );
+        }
+        _ => {}
+      }
+    }
+    offset += SHP_RECORD_HEADER_SIZE as u64 + content_len;
+
+    let shape = match RawShape::parse(&content) {
+      Ok(shape) => shape,
+      Err(e) => {
+        report.add(Severity::Error, "invalidRecord", Some(index), e);
+        continue;
+      }
+    };
+    if shape.is_null() {
+      report.add(Severity::Warning, "nullShape", Some(index), "空图形");
+      continue;
+    }
+    if shape.shape_type != header_shape_type {
+      report.add(
+        Severity::Error,
+        "shapeTypeMismatch",
+        Some(index),
+        format!(
+          "图形类型 {} 与文件头中的 {} 不一致",
+          shape.shape_type, header_shape_type
+        ),
+      );
+    }
+    if check_shape(&shape, index, &mut report) {
+      if let Some(bbox) = shape.computed_bbox() {
+        data_bbox = Some(match data_bbox {
+          None => bbox,
+          Some(current) => merge_bbox(current, bbox),
+        });
+      }
+    }
+  }
+  report.record_count = record_count;
+
+  if let Some(data_bbox) = data_bbox {
+    if bbox_differs(&header_bbox, &data_bbox) {
+      report.add(
+        Severity::Warning,
+        "headerBboxMismatch",
+        None,
+        format!(
+          "文件头范围 {:?} 与实际坐标范围 {:?} 不一致",
+          header_bbox, data_bbox
+        ),
+      );
+    }
+  }
+  if shx.is_some() && shx_entries.len() as u64 != record_count {
+    report.add(
+      Severity::Error,
+      "shxMismatch",
+      None,
+      format!(
+        ".shx 索引 {} 条，.shp 记录 {} 条",
+        shx_entries.len(),
+        record_count
+      ),
+    );
+  }
+  check_dbf(base_path, encoding, record_count, &mut report);
+  check_sidecars(base_path, encoding, &mut report);
+
+  report.valid = report.error_count == 0;
+  progress.finish();
+  Ok(report)
+}
+
+/// 读取 `.shx`，缺失或文件头与长度不符时记录问题
+fn read_shx(shx_path: &Path, report: &mut ValidationReport) -> Option<Vec<u8>> {
+  let Ok(shx) = fs::read(shx_path) else {
+    report.add(Severity::Error, "missingShx", None, "缺少 .shx 索引文件");
+    return None;
+  };
+  if shx.len() < SHP_HEADER_SIZE {
+    report.add(Severity::Error, "shxMismatch", None, ".shx 文件头不完整");
+    return None;
+  }
+  let declared_len = u64::from(read_u32_be(&shx, 24)) * 2;
+  if declared_len != shx.len() as u64 {
+    report.add(
+      Severity::Warning,
+      "fileLength",
+      None,
+      format!(
+        ".shx 文件头记录的长度为 {} 字节，实际为 {} 字节",
+        declared_len,
+        shx.len()
+      ),
+    );
+  }
+  if (shx.len() - SHP_HEADER_SIZE) % SHX_RECORD_SIZE as usize != 0 {
+    report.add(
+      Severity::Error,
+      "shxMismatch",
+      None,
+      ".shx 长度不是完整的索引记录",
+    );
+  }
+  Some(shx[SHP_HEADER_SIZE..].to_vec())
+}
+
+fn check_dbf(
+  base_path: &Path,
+  encoding: Option<&str>,
+  shp_count: u64,
+  report: &mut ValidationReport,
+) {
+  let dbf_path = base_path.with_extension("dbf");
+  if !dbf_path.exists() {
+    report.add(Severity::Error, "missingDbf", None, "缺少 .dbf 属性文件");
+    return;
+  }
+  let header =
+    detect_encoding(&dbf_path, encoding).and_then(|encoding| read_dbf_header(&dbf_path, &encoding));
+  match header {
+    Ok(header) if u64::from(header.record_count) != shp_count => report.add(
+      Severity::Error,
+      "recordCountMismatch",
+      None,
+      format!(
+        "SHP 数据（{} 条）与 DBF 数据（{} 条）的记录数不一致",
+        shp_count, header.record_count
+      ),
+    ),
+    Ok(_) => {}
+    Err(e) => report.add(Severity::Error, "invalidDbf", None, e.to_string()),
+  }
+}
+
+/// 缺少 `.prj` 时坐标系未知，缺少 `.cpg` 且未指定编码时按文件头推断编码
+fn check_sidecars(base_path: &Path, encoding: Option<&str>, report: &mut ValidationReport) {
+  if !base_path.with_extension("prj").exists() {
+    report.add(
+      Severity::Info,
+      "missingPrj",
+      None,
+      "缺少 .prj 文件，坐标系未知",
+    );
+  }
+  if encoding.is_none() && !base_path.with_extension("cpg").exists() {
+    report.add(
+      Severity::Info,
+      "missingCpg",
+      None,
+      "缺少 .cpg 文件，属性编码按 DBF 文件头推断",
+    );
+  }
+}
+
+/// 检查一条非空记录，坐标无效时返回 `false`
+fn check_shape(shape: &RawShape, index: u64, report: &mut ValidationReport) -> bool {
+  let index = Some(index);
+  let invalid_coordinates = shape
+    .points
+    .iter()
+    .flatten()
+    .chain(&shape.z)
+    .filter(|value| !value.is_finite())
+    .count();
+  if invalid_coordinates > 0 {
+    report.add(
+      Severity::Error,
+      "invalidCoordinate",
+      index,
+      format!("{} 个坐标值为 NaN 或无穷大", invalid_coordinates),
+    );
+    return false;
+  }
+  if shape.points.is_empty() {
+    report.add(Severity::Warning, "emptyPart", index, "图形没有任何点");
+    return true;
+  }
+
+  if let (Some(bbox), Some(computed)) = (shape.bbox, shape.computed_bbox()) {
+    if bbox_differs(&bbox, &computed) {
+      report.add(
+        Severity::Warning,
+        "bboxMismatch",
+        index,
+        format!("记录范围 {:?} 与坐标范围 {:?} 不一致", bbox, computed),
+      );
+    }
+  }
+
+  let is_polyline = matches!(shape.shape_type, 3 | 13 | 23);
+  let is_polygon = matches!(shape.shape_type, 5 | 15 | 25);
+  if !is_polyline && !is_polygon && shape.shape_type != 31 {
+    return true;
+  }
+  if shape.parts.first() != Some(&0)
+    || shape.parts.windows(2).any(|pair| pair[0] > pair[1])
+    || shape
+      .parts
+      .last()
+      .is_some_and(|&last| last as usize > shape.points.len())
+  {
+    report.add(
+      Severity::Error,
+      "invalidParts",
+      index,
+      format!("部件起始序号 {:?} 无效", shape.parts),
+    );
+    return true;
+  }
+
+  let mut rings = Vec::new();
+  for (part, range) in shape.part_ranges().into_iter().enumerate() {
+    let points = &shape.points[range];
+    if points.is_empty() {
+      report.add(
+        Severity::Error,
+        "emptyPart",
+        index,
+        format!("部件 {} 没有任何点", part),
+      );
+      continue;
+    }
+    if is_polyline && points.len() < 2 {
+      report.add(
+        Severity::Warning,
+        "tooFewPoints",
+        index,
+        format!("线的部件 {} 只有 {} 个点", part, points.len()),
+      );
+    }
+    if is_polygon && check_ring(points, part, index, report) {
+      rings.push((part, points));
+    }
+  }
+  if is_polygon {
+    check_orientation(&rings, index, report);
+  }
+  true
+}
+
+/// 检查环的点数、闭合与自相交，可用于方向检查时返回 `true`
+fn check_ring(
+  points: &[[f64; 2]],
+  part: usize,
+  index: Option<u64>,
+  report: &mut ValidationReport,
+) -> bool {
+  if points.len() < 4 {
+    report.add(
+      Severity::Error,
+      "tooFewPoints",
+      index,
+      format!("环 {} 只有 {} 个点，至少需要 4 个", part, points.len()),
+    );
+    return false;
+  }
+  if points.first() != points.last() {
+    report.add(
+      Severity::Error,
+      "unclosedRing",
+      index,
+      format!("环 {} 首尾点不一致", part),
+    );
+  }
+  let self_intersects = ring_self_intersects(points);
+  if self_intersects {
+    report.add(
+      Severity::Error,
+      "selfIntersection",
+      index,
+      format!("环 {} 自相交", part),
+    );
+  }
+  if signed_area(points) == 0.0 {
+    // 自相交的环（如“8”字形）面积也可能为 0，只报告一次
+    if !self_intersects {
+      report.add(
+        Severity::Error,
+        "degenerateRing",
+        index,
+        format!("环 {} 面积为 0", part),
+      );
+    }
+    return false;
+  }
+  true
+}
+
+/// shapefile 规定外环为顺时针、内环为逆时针，逆时针的环必须位于某个顺时针的环内
+fn check_orientation(
+  rings: &[(usize, &[[f64; 2]])],
+  index: Option<u64>,
+  report: &mut ValidationReport,
+) {
+  let (outers, holes): (Vec<_>, Vec<_>) = rings
+    .iter()
+    .partition(|(_, points)| signed_area(points) < 0.0);
+  if outers.is_empty() && !holes.is_empty() {
+    report.add(
+      Severity::Warning,
+      "ringOrientation",
+      index,
+      "所有环均为逆时针，外环应为顺时针",
+    );
+    return;
+  }
+  for (part, hole) in holes {
+    let contained = outers.iter().any(|(_, outer)| {
+      hole
+        .iter()
+        .any(|point| point_in_ring(outer, point[0], point[1]))
+    });
+    if !contained {
+      report.add(
+        Severity::Warning,
+        "ringOrientation",
+        index,
+        format!("环 {} 为逆时针但不在任何外环内，方向可能错误", part),
+      );
+    }
+  }
+}
+
+/// 判断环的非相邻边是否相交，按 x 排序后只比较范围重叠的边
+fn ring_self_intersects(points: &[[f64; 2]]) -> bool {
+  // 去掉连续重复点，否则零长度边两侧的边会被误判为相交
+  let mut ring: Vec<[f64; 2]> = Vec::with_capacity(points.len() + 1);
+  for point in points {
+    if ring.last() != Some(point) {
+      ring.push(*point);
+    }
+  }
+  if ring.first() != ring.last() {
+    ring.push(ring[0]);
+  }
+  let count = ring.len() - 1;
+  if count < 3 {
+    return false;
+  }
+
+  let min_x = |segment: usize| ring[segment][0].min(ring[segment + 1][0]);
+  let max_x = |segment: usize| ring[segment][0].max(ring[segment + 1][0]);
+  let mut order: Vec<usize> = (0..count).collect();
+  order.sort_by(|&a, &b| min_x(a).total_cmp(&min_x(b)));
+  for (position, &a) in order.iter().enumerate() {
+    for &b in &order[position + 1..] {
+      if min_x(b) > max_x(a) {
+        break;
+      }
+      let adjacent = (a + 1) % count == b || (b + 1) % count == a;
+      if !adjacent && segments_intersect(ring[a], ring[a + 1], ring[b], ring[b + 1]) {
+        return true;
+      }
+    }
+  }
+  false
+}
+
+fn segments_intersect(p1: [f64; 2], p2: [f64; 2], q1: [f64; 2], q2: [f64; 2]) -> bool {
+  let orientation = |a: [f64; 2], b: [f64; 2], c: [f64; 2]| {
+    let value = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+    value.partial_cmp(&0.0).map_or(0, |ordering| ordering as i8)
+  };
+  let on_segment = |a: [f64; 2], b: [f64; 2], c: [f64; 2]| {
+    c[0] >= a[0].min(b[0])
+      && c[0] <= a[0].max(b[0])
+      && c[1] >= a[1].min(b[1])
+      && c[1] <= a[1].max(b[1])
+  };
+  let (d1, d2) = (orientation(q1, q2, p1), orientation(q1, q2, p2));
+  let (d3, d4) = (orientation(p1, p2, q1), orientation(p1, p2, q2));
+  if d1 * d2 < 0 && d3 * d4 < 0 {
+    return true;
+  }
+  (d1 == 0 && on_segment(q1, q2, p1))
+    || (d2 == 0 && on_segment(q1, q2, p2))
+    || (d3 == 0 && on_segment(p1, p2, q1))
+    || (d4 == 0 && on_segment(p1, p2, q2))
+}
+
+fn merge_bbox(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+  [
+    a[0].min(b[0]),
+    a[1].min(b[1]),
+    a[2].max(b[2]),
+    a[3].max(b[3]),
+  ]
+}
+
+// 范围按相对误差比较，避免浮点舍入造成误报
+fn bbox_differs(a: &[f64; 4], b: &[f64; 4]) -> bool {
+  a.iter().zip(b).any(|(a, b)| {
+    let tolerance = 1e-9 * a.abs().max(b.abs()).max(1.0);
+    (a - b).abs() > tolerance
+  })
+}
@@ -0,0 +1,246 @@
+use geojson::{Position, Value};
+
+use super::geometry::coordinate_dimension;
+
+const EWKB_Z_FLAG: u32 = 0x8000_0000;
+const EWKB_M_FLAG: u32 = 0x4000_0000;
+const EWKB_SRID_FLAG: u32 = 0x2000_0000;
+
+/// 二进制几何的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WkbFlavor {
+  /// ISO/OGC WKB，Z/M 通过类型码加 1000/3000 表示
+  Iso,
+  /// PostGIS EWKB，Z/M/SRID 通过类型码高位标志表示
+  Extended,
+}
+
+/// 将 GeoJSON 几何编码为小端序 WKB，`srid` 仅在 EWKB 中写入
+pub fn geometry_to_wkb(value: &Value, flavor: WkbFlavor, srid: Option<u32>) -> Vec<u8> {
+  let mut buf = Vec::new();
+  let dimension = coordinate_dimension(value);
+  write_geometry(&mut buf, value, flavor, srid, dimension);
+  buf
+}
+
+/// 编码为 WKB 并输出为大写十六进制字符串
+pub fn geometry_to_wkb_hex(value: &Value, flavor: WkbFlavor, srid: Option<u32>) -> String {
+  geometry_to_wkb(value, flavor, srid)
+    .iter()
+    .map(|byte| format!("{:02X}", byte))
+    .collect()
+}
+
+fn write_geometry(
+  buf: &mut Vec<u8>,
+  value: &Value,
+  flavor: WkbFlavor,
+  srid: Option<u32>,
+  dimension: usize,
+) {
+  let base_type: u32 = match value {
+    Value::Point(_) => 1,
+    Value::LineString(_) => 2,
+    Value::Polygon(_) => 3,
+    Value::MultiPoint(_) => 4,
+    Value::MultiLineString(_) => 5,
+    Value::MultiPolygon(_) => 6,
+    Value::GeometryCollection(_) => 7,
+  };
+  write_header(buf, base_type, flavor, srid, dimension);
+
+  // 子几何不再重复写入 SRID
+  match value {
+    Value::Point(position) => write_position(buf, position, dimension),
+    Value::LineString(line) => write_positions(buf, line, dimension),
+    Value::Polygon(rings) => write_rings(buf, rings, dimension),
+    Value::MultiPoint(points) => {
+      write_u32(buf, points.len() as u32);
+      for point in points {
+        write_header(buf, 1, flavor, None, dimension);
+        write_position(buf, point, dimension);
+      }
+    }
+    Value::MultiLineString(lines) => {
+      write_u32(buf, lines.len() as u32);
+      for line in lines {
+        write_header(buf, 2, flavor, None, dimension);
+        write_positions(buf, line, dimension);
+      }
+    }
+    Value::MultiPolygon(polygons) => {
+      write_u32(buf, polygons.len() as u32);
+      for rings in polygons {
+        write_header(buf, 3, flavor, None, dimension);
+        write_rings(buf, rings, dimension);
+      }
+    }
+    Value::GeometryCollection(geometries) => {
+      write_u32(buf, geometries.len() as u32);
+      for geometry in geometries {
+        let member_dimension = coordinate_dimension(&geometry.value);
+        write_geometry(buf, &geometry.value, flavor, None, member_dimension);
+      }
+    }
+  }
+}
+
+fn write_header(
+  buf: &mut Vec<u8>,
+  base_type: u32,
+  flavor: WkbFlavor,
+  srid: Option<u32>,
+  dimension: usize,
+) {
+  // 小端序
+  buf.push(1);
+  match flavor {
+    WkbFlavor::Iso => {
+      let offset = match dimension {
+        3 => 1000,
+        4 => 3000,
+        _ => 0,
+      };
+      write_u32(buf, base_type + offset);
+    }
+    WkbFlavor::Extended => {
+      let mut type_code = base_type;
+      if dimension >= 3 {
+        type_code |= EWKB_Z_FLAG;
+      }
+      if dimension == 4 {
+        type_code |= EWKB_M_FLAG;
+      }
+      if srid.is_some() {
+        type_code |= EWKB_SRID_FLAG;
+      }
+      write_u32(buf, type_code);
+      if let Some(srid) = srid {
+        write_u32(buf, srid);
+      }
+    }
+  }
+}
+
+fn write_rings(buf: &mut Vec<u8>, rings: &[Vec<Position>], dimension: usize) {
+  write_u32(buf, rings.len() as u32);
+  for ring in rings {
+    write_positions(buf, ring, dimension);
+  }
+}
+
+fn write_positions(buf: &mut Vec<u8>, positions: &[Position], dimension: usize) {
+  write_u32(buf, positions.len() as u32);
+  for position in positions {
+    write_position(buf, position, dimension);
+  }
+}
+
+// 空点按惯例写入 NaN 坐标
+fn write_position(buf: &mut Vec<u8>, position: &Position, dimension: usize) {
+  for index in 0..dimension {
+    let ordinate = match position.get(index) {
+      Some(ordinate) => *ordinate,
+      None if position.is_empty() => f64::NAN,
+      None => 0.0,
+    };
+    buf.extend_from_slice(&ordinate.to_le_bytes());
+  }
+}
+
+fn write_u32(buf: &mut Vec<u8>, value: u32) {
+  buf.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 小端序 f64 的十六进制
+  const ZERO: &str = "0000000000000000";
+  const ONE: &str = "000000000000F03F";
+  const TWO: &str = "0000000000000040";
+
+  fn hex(value: &Value, flavor: WkbFlavor, srid: Option<u32>) -> String {
+    geometry_to_wkb_hex(value, flavor, srid)
+  }
+
+  #[test]
+  fn point() {
+    let point = Value::Point(vec![1.0, 2.0]);
+    let expected = format!("0101000000{}{}", ONE, TWO);
+    assert_eq!(hex(&point, WkbFlavor::Iso, None), expected);
+    assert_eq!(hex(&point, WkbFlavor::Extended, None), expected);
+  }
+
+  #[test]
+  fn line_string() {
+    let line = Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]);
+    assert_eq!(
+      hex(&line, WkbFlavor::Iso, None),
+      format!("010200000002000000{}{}{}{}", ZERO, ZERO, ONE, ONE)
+    );
+  }
+
+  #[test]
+  fn multi_line_string_repeats_member_headers() {
+    let lines = Value::MultiLineString(vec![
+      vec![vec![0.0, 0.0], vec![1.0, 1.0]],
+      vec![vec![2.0, 2.0], vec![1.0, 0.0]],
+    ]);
+    let expected = [
+      "010500000002000000".to_string(),
+      format!("010200000002000000{}{}{}{}", ZERO, ZERO, ONE, ONE),
+      format!("010200000002000000{}{}{}{}", TWO, TWO, ONE, ZERO),
+    ]
+    .concat();
+    assert_eq!(hex(&lines, WkbFlavor::Iso, None), expected);
+  }
+
+  #[test]
+  fn polygon_with_z() {
+    let polygon = Value::Polygon(vec![vec![
+      vec![0.0, 0.0, 1.0],
+      vec![1.0, 0.0, 1.0],
+      vec![0.0, 1.0, 1.0],
+      vec![0.0, 0.0, 1.0],
+    ]]);
+    let coordinates = [
+      [ZERO, ZERO, ONE],
+      [ONE, ZERO, ONE],
+      [ZERO, ONE, ONE],
+      [ZERO, ZERO, ONE],
+    ]
+    .concat()
+    .concat();
+    // ISO 类型码 1003，EWKB 为 3 加 Z 标志
+    assert_eq!(
+      hex(&polygon, WkbFlavor::Iso, None),
+      format!("01EB0300000100000004000000{}", coordinates)
+    );
+    assert_eq!(
+      hex(&polygon, WkbFlavor::Extended, None),
+      format!("01030000800100000004000000{}", coordinates)
+    );
+  }
+
+  #[test]
+  fn ewkb_srid_only_on_outer_geometry() {
+    let point = Value::Point(vec![1.0, 2.0]);
+    assert_eq!(
+      hex(&point, WkbFlavor::Extended, Some(4326)),
+      format!("0101000020E6100000{}{}", ONE, TWO)
+    );
+    // ISO WKB 不写入 SRID
+    assert_eq!(
+      hex(&point, WkbFlavor::Iso, Some(4326)),
+      format!("0101000000{}{}", ONE, TWO)
+    );
+
+    let points = Value::MultiPoint(vec![vec![1.0, 2.0]]);
+    assert_eq!(
+      hex(&points, WkbFlavor::Extended, Some(4326)),
+      format!("0104000020E6100000010000000101000000{}{}", ONE, TWO)
+    );
+  }
+}
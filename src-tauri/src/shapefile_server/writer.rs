@@ -0,0 +1,682 @@
+use encoding_rs::Encoding;
+use geojson::{Position, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::geometry::signed_area;
+use super::schema::{DbfField, SHP_FILE_CODE, SHP_HEADER_SIZE};
+use super::shapefile_to_geojson::CustomError;
+
+const SHP_VERSION: i32 = 1000;
+const DBF_VERSION: u8 = 0x03;
+const DBF_FIELD_TERMINATOR: u8 = 0x0D;
+const DBF_END_OF_FILE: u8 = 0x1A;
+// 重写 DBF 时需要沿用的版本号与语言驱动（LDID）位置，LDID 影响编码识别
+const DBF_VERSION_OFFSET: usize = 0;
+const DBF_LANGUAGE_DRIVER_OFFSET: usize = 29;
+/// 写入 Z 类型图形时使用的 M 空值，小于 -1e38 的 M 视为无数据
+const NO_DATA_MEASURE: f64 = -1e39;
+
+/// 可写入的图形类别，每种类别对应一个 shapefile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShapeKind {
+  Point,
+  Multipoint,
+  Polyline,
+  Polygon,
+}
+
+impl ShapeKind {
+  /// GeometryCollection 无法写入 shapefile，返回 `None`
+  pub fn of(value: &Value) -> Option<Self> {
+    match value {
+      Value::Point(_) => Some(ShapeKind::Point),
+      Value::MultiPoint(_) => Some(ShapeKind::Multipoint),
+      Value::LineString(_) | Value::MultiLineString(_) => Some(ShapeKind::Polyline),
+      Value::Polygon(_) | Value::MultiPolygon(_) => Some(ShapeKind::Polygon),
+      Value::GeometryCollection(_) => None,
+    }
+  }
+
+  pub fn shape_type(&self, has_z: bool) -> i32 {
+    match (self, has_z) {
+      (ShapeKind::Point, false) => 1,
+      (ShapeKind::Polyline, false) => 3,
+      (ShapeKind::Polygon, false) => 5,
+      (ShapeKind::Multipoint, false) => 8,
+      (ShapeKind::Point, true) => 11,
+      (ShapeKind::Polyline, true) => 13,
+      (ShapeKind::Polygon, true) => 15,
+      (ShapeKind::Multipoint, true) => 18,
+    }
+  }
+
+  /// 拆分为多个文件时的文件名后缀
+  pub fn suffix(&self) -> &'static str {
+    match self {
+      ShapeKind::Point => "point",
+      ShapeKind::Multipoint => "multipoint",
+      ShapeKind::Polyline => "line",
+      ShapeKind::Polygon => "polygon",
+    }
+  }
+}
+
+/// 图形范围，`z` 仅 Z 类型有值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extent {
+  pub bbox: [f64; 4],
+  pub z: Option<[f64; 2]>,
+}
+
+impl Extent {
+  fn of_positions<'a>(positions: impl IntoIterator<Item = &'a Position>, has_z: bool) -> Self {
+    let mut bbox = [
+      f64::INFINITY,
+      f64::INFINITY,
+      f64::NEG_INFINITY,
+      f64::NEG_INFINITY,
+    ];
+    let mut z = [f64::INFINITY, f64::NEG_INFINITY];
+    for position in positions {
+      bbox[0] = bbox[0].min(position[0]);
+      bbox[1] = bbox[1].min(position[1]);
+      bbox[2] = bbox[2].max(position[0]);
+      bbox[3] = bbox[3].max(position[1]);
+      let value = position_z(position);
+      z[0] = z[0].min(value);
+      z[1] = z[1].max(value);
+    }
+    Extent {
+      bbox,
+      z: has_z.then_some(z),
+    }
+  }
+
+  pub fn merge(&mut self, other: &Extent) {
+    self.bbox[0] = self.bbox[0].min(other.bbox[0]);
+    self.bbox[1] = self.bbox[1].min(other.bbox[1]);
+    self.bbox[2] = self.bbox[2].max(other.bbox[2]);
+    self.bbox[3] = self.bbox[3].max(other.bbox[3]);
+    if let (Some(z), Some(other_z)) = (self.z.as_mut(), other.z) {
+      z[0] = z[0].min(other_z[0]);
+      z[1] = z[1].max(other_z[1]);
+    }
+  }
+}
+
+/// 同时写入 `.shp` 与 `.shx`，文件头在 `finish` 时回填
+pub struct ShpWriter {
+  shp: BufWriter<File>,
+  shx: BufWriter<File>,
+  shape_type: i32,
+  extent: Option<Extent>,
+  // 以 16 位字为单位的当前偏移
+  offset: u32,
+  record_count: u32,
+}
+
+impl ShpWriter {
+  pub fn create(shp_path: &Path, shape_type: i32) -> Result<Self, CustomError> {
+    let mut shp = BufWriter::new(File::create(shp_path)?);
+    let mut shx = BufWriter::new(File::create(shp_path.with_extension("shx"))?);
+    shp.write_all(&[0u8; SHP_HEADER_SIZE])?;
+    shx.write_all(&[0u8; SHP_HEADER_SIZE])?;
+    Ok(ShpWriter {
+      shp,
+      shx,
+      shape_type,
+      extent: None,
+      offset: (SHP_HEADER_SIZE / 2) as u32,
+      record_count: 0,
+    })
+  }
+
+  /// 写入一条记录，`geometry` 为 `None` 时写入空图形
+  pub fn write(&mut self, geometry: Option<&Value>) -> Result<(), CustomError> {
+    let has_z = matches!(self.shape_type, 11 | 13 | 15 | 18);
+    let (content, extent) = match geometry {
+      Some(value) => encode_shape(value, self.shape_type, has_z)?,
+      None => (0i32.to_le_bytes().to_vec(), None),
+    };
+    self.write_content(&content)?;
+    if let Some(extent) = extent {
+      match self.extent.as_mut() {
+        Some(current) => current.merge(&extent),
+        None => self.extent = Some(extent),
+      }
+    }
+    Ok(())
+  }
+
+  /// 写入已编码的记录内容（不含记录头）
+  pub fn write_content(&mut self, content: &[u8]) -> Result<(), CustomError> {
+    self.record_count += 1;
+    let content_words = (content.len() / 2) as i32;
+    self
+      .shp
+      .write_all(&(self.record_count as i32).to_be_bytes())?;
+    self.shp.write_all(&content_words.to_be_bytes())?;
+    self.shp.write_all(content)?;
+    self.shx.write_all(&(self.offset as i32).to_be_bytes())?;
+    self.shx.write_all(&content_words.to_be_bytes())?;
+    self.offset += 4 + content_words as u32;
+    Ok(())
+  }
+
+  /// 设置文件头中的范围，用于复制已有文件时沿用原范围
+  pub fn set_extent(&mut self, extent: Extent) {
+    self.extent = Some(extent);
+  }
+
+  /// 回填文件头并返回记录数
+  pub fn finish(mut self) -> Result<u32, CustomError> {
+    let extent = self.extent.unwrap_or(Extent {
+      bbox: [0.0; 4],
+      z: None,
+    });
+    let shx_words = (SHP_HEADER_SIZE / 2) as u32 + self.record_count * 4;
+    for (writer, words) in [(&mut self.shp, self.offset), (&mut self.shx, shx_words)] {
+      writer.flush()?;
+      writer.seek(SeekFrom::Start(0))?;
+      writer.write_all(&shp_file_header(words, self.shape_type, &extent))?;
+      writer.flush()?;
+    }
+    Ok(self.record_count)
+  }
+}
+
+/// 生成 `.shp`/`.shx` 文件头，`file_words` 为以 16 位字计的文件长度
+pub fn shp_file_header(file_words: u32, shape_type: i32, extent: &Extent) -> [u8; SHP_HEADER_SIZE] {
+  let mut header = [0u8; SHP_HEADER_SIZE];
+  header[0..4].copy_from_slice(&SHP_FILE_CODE.to_be_bytes());
+  header[24..28].copy_from_slice(&(file_words as i32).to_be_bytes());
+  header[28..32].copy_from_slice(&SHP_VERSION.to_le_bytes());
+  header[32..36].copy_from_slice(&shape_type.to_le_bytes());
+  let z = extent.z.unwrap_or([0.0, 0.0]);
+  let values = [
+    extent.bbox[0],
+    extent.bbox[1],
+    extent.bbox[2],
+    extent.bbox[3],
+    z[0],
+    z[1],
+    0.0,
+    0.0,
+  ];
+  for (index, value) in values.iter().enumerate() {
+    let offset = 36 + index * 8;
+    header[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+  }
+  header
+}
+
+/// 将 GeoJSON 几何编码为 shapefile 记录内容，面的外环输出为顺时针、内环为逆时针
+fn encode_shape(
+  value: &Value,
+  shape_type: i32,
+  has_z: bool,
+) -> Result<(Vec<u8>, Option<Extent>), CustomError> {
+  let parts: Vec<Vec<Position>> = match value {
+    Value::Point(position) => vec![vec![position.clone()]],
+    Value::MultiPoint(points) => vec![points.clone()],
+    Value::LineString(line) => vec![line.clone()],
+    Value::MultiLineString(lines) => lines.clone(),
+    Value::Polygon(rings) => oriented_rings(rings),
+    Value::MultiPolygon(polygons) => polygons
+      .iter()
+      .flat_map(|rings| oriented_rings(rings))
+      .collect(),
+    Value::GeometryCollection(_) => {
+      return Err(CustomError(
+        "GeometryCollection 无法写入 shapefile".to_string(),
+      ))
+    }
+  };
+  let parts: Vec<Vec<Position>> = parts
+    .into_iter()
+    .map(|part| {
+      part
+        .into_iter()
+        .filter(|position| position.len() >= 2)
+        .collect::<Vec<_>>()
+    })
+    .filter(|part| !part.is_empty())
+    .collect();
+  if parts.is_empty() {
+    return Ok((0i32.to_le_bytes().to_vec(), None));
+  }
+
+  let extent = Extent::of_positions(parts.iter().flatten(), has_z);
+  let mut content = Vec::new();
+  content.extend(shape_type.to_le_bytes());
+  if ShapeKind::of(value) == Some(ShapeKind::Point) {
+    let position = &parts[0][0];
+    content.extend(position[0].to_le_bytes());
+    content.extend(position[1].to_le_bytes());
+    if has_z {
+      content.extend(position_z(position).to_le_bytes());
+      content.extend(NO_DATA_MEASURE.to_le_bytes());
+    }
+    return Ok((content, Some(extent)));
+  }
+
+  for value in extent.bbox {
+    content.extend(value.to_le_bytes());
+  }
+  let point_count: usize = parts.iter().map(Vec::len).sum();
+  let is_multipoint = ShapeKind::of(value) == Some(ShapeKind::Multipoint);
+  if !is_multipoint {
+    content.extend((parts.len() as i32).to_le_bytes());
+  }
+  content.extend((point_count as i32).to_le_bytes());
+  if !is_multipoint {
+    let mut start = 0i32;
+    for part in &parts {
+      content.extend(start.to_le_bytes());
+      start += part.len() as i32;
+    }
+  }
+  for position in parts.iter().flatten() {
+    content.extend(position[0].to_le_bytes());
+    content.extend(position[1].to_le_bytes());
+  }
+  if let Some(z) = extent.z {
+    content.extend(z[0].to_le_bytes());
+    content.extend(z[1].to_le_bytes());
+    for position in parts.iter().flatten() {
+      content.extend(position_z(position).to_le_bytes());
+    }
+    content.extend(NO_DATA_MEASURE.to_le_bytes());
+    content.extend(NO_DATA_MEASURE.to_le_bytes());
+    for _ in 0..point_count {
+      content.extend(NO_DATA_MEASURE.to_le_bytes());
+    }
+  }
+  Ok((content, Some(extent)))
+}
+
+fn position_z(position: &Position) -> f64 {
+  position.get(2).copied().unwrap_or(0.0)
+}
+
+fn oriented_rings(rings: &[Vec<Position>]) -> Vec<Vec<Position>> {
+  rings
+    .iter()
+    .enumerate()
+    .map(|(index, ring)| {
+      let mut ring = ring.clone();
+      if ring.len() > 1 && ring.first() != ring.last() {
+        ring.push(ring[0].clone());
+      }
+      // 鞋带公式的面积为正表示逆时针
+      let clockwise = signed_area(&ring) < 0.0;
+      if clockwise != (index == 0) {
+        ring.reverse();
+      }
+      ring
+    })
+    .collect()
+}
+
+/// 逐条写入 DBF 记录，记录数在 `finish` 时回填
+pub struct DbfWriter {
+  writer: BufWriter<File>,
+  fields: Vec<DbfField>,
+  encoding: &'static Encoding,
+  record_count: u32,
+}
+
+impl DbfWriter {
+  pub fn create(
+    dbf_path: &Path,
+    fields: &[DbfField],
+    encoding: &'static Encoding,
+  ) -> Result<Self, CustomError> {
+    let mut writer = BufWriter::new(File::create(dbf_path)?);
+    writer.write_all(&dbf_file_header(fields, encoding, 0)?)?;
+    Ok(DbfWriter {
+      writer,
+      fields: fields.to_vec(),
+      encoding,
+      record_count: 0,
+    })
+  }
+
+  /// 按字段定义编码并写入一条记录，`values` 与字段一一对应
+  pub fn write(&mut self, values: &[serde_json::Value]) -> Result<(), CustomError> {
+    let mut record = Vec::with_capacity(record_length(&self.fields));
+    record.push(b' ');
+    for (field, value) in self.fields.iter().zip(values) {
+      record.extend(encode_field_value(field, value, self.encoding)?);
+    }
+    self.write_raw(&record)
+  }
+
+  /// 写入已编码的记录（含删除标记字节）
+  pub fn write_raw(&mut self, record: &[u8]) -> Result<(), CustomError> {
+    self.writer.write_all(record)?;
+    self.record_count += 1;
+    Ok(())
+  }
+
+  pub fn finish(mut self) -> Result<u32, CustomError> {
+    self.writer.write_all(&[DBF_END_OF_FILE])?;
+    self.writer.flush()?;
+    self.writer.seek(SeekFrom::Start(4))?;
+    self.writer.write_all(&self.record_count.to_le_bytes())?;
+    self.writer.flush()?;
+    Ok(self.record_count)
+  }
+}
+
+pub fn record_length(fields: &[DbfField]) -> usize {
+  1 + fields
+    .iter()
+    .map(|field| field.length as usize)
+    .sum::<usize>()
+}
+
+/// 生成 DBF 文件头与字段描述
+pub fn dbf_file_header(
+  fields: &[DbfField],
+  encoding: &'static Encoding,
+  record_count: u32,
+) -> Result<Vec<u8>, CustomError> {
+  let header_length = 32 + 32 * fields.len() + 1;
+  let record_length = record_length(fields);
+  if record_length > u16::MAX as usize {
+    return Err(CustomError("记录长度超过 DBF 上限".to_string()));
+  }
+  let mut header = vec![0u8; 32];
+  header[0] = DBF_VERSION;
+  header[1..4].copy_from_slice(&dbf_update_date());
+  header[4..8].copy_from_slice(&record_count.to_le_bytes());
+  header[8..10].copy_from_slice(&(header_length as u16).to_le_bytes());
+  header[10..12].copy_from_slice(&(record_length as u16).to_le_bytes());
+
+  for field in fields {
+    let (name, _, _) = encoding.encode(&field.name);
+    if name.is_empty() || name.len() > 10 {
+      return Err(CustomError(format!(
+        "字段名长度必须为 1-10 字节: {}",
+        field.name
+      )));
+    }
+    let mut descriptor = [0u8; 32];
+    descriptor[..name.len()].copy_from_slice(&name);
+    descriptor[11] = field.field_type as u8;
+    descriptor[16] = field.length;
+    descriptor[17] = field.decimal_count;
+    header.extend(descriptor);
+  }
+  header.push(DBF_FIELD_TERMINATOR);
+  Ok(header)
+}
+
+/// 将原文件头中的版本号与语言驱动写入新生成的 DBF，并同步到磁盘
+pub fn copy_dbf_header_flags(source_header: &[u8], dbf_path: &Path) -> Result<(), CustomError> {
+  let mut file = OpenOptions::new().write(true).open(dbf_path)?;
+  for offset in [DBF_VERSION_OFFSET, DBF_LANGUAGE_DRIVER_OFFSET] {
+    if let Some(&value) = source_header.get(offset) {
+      file.seek(SeekFrom::Start(offset as u64))?;
+      file.write_all(&[value])?;
+    }
+  }
+  file.sync_all()?;
+  Ok(())
+}
+
+/// DBF 文件头中的更新日期（年份自 1900 起）
+pub fn dbf_update_date() -> [u8; 3] {
+  let (year, month, day) = today();
+  [(year - 1900).clamp(0, 255) as u8, month as u8, day as u8]
+}
+
+/// 按字段定义将 JSON 值编码为定长字节，值不合法或超出宽度时返回错误
+///
+/// 支持 C/N/F/L/D 类型，null 写为空白（逻辑型为 `?`）
+pub fn encode_field_value(
+  field: &DbfField,
+  value: &serde_json::Value,
+  encoding: &'static Encoding,
+) -> Result<Vec<u8>, CustomError> {
+  let width = field.length as usize;
+  let invalid =
+    |reason: &str| CustomError(format!("字段 {} 的值 {} {}", field.name, value, reason));
+  if value.is_null() {
+    let fill = if field.field_type == 'L' { b'?' } else { b' ' };
+    return Ok(vec![fill; width]);
+  }
+
+  match field.field_type {
+    'C' => {
+      let text = match value {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+      };
+      let (bytes, _, _) = encoding.encode(&text);
+      if bytes.len() > width {
+        return Err(invalid(&format!("超过字段长度 {}", width)));
+      }
+      let mut bytes = bytes.into_owned();
+      bytes.resize(width, b' ');
+      Ok(bytes)
+    }
+    'N' | 'F' => {
+      let number = match value {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(text) => text.trim().parse::<f64>().ok(),
+        _ => None,
+      }
+      .filter(|number| number.is_finite())
+      .ok_or_else(|| invalid("不是有效的数字"))?;
+      let decimals = field.decimal_count as usize;
+      if decimals == 0 && number.fract() != 0.0 {
+        return Err(invalid("不是整数"));
+      }
+      // 小数位放不下时降低精度，整数部分放不下才视为超出宽度
+      let mut precision = decimals;
+      let mut text = format!("{:>width$.precision$}", number);
+      while text.len() > width && precision > 0 {
+        precision -= 1;
+        text = format!("{:>width$.precision$}", number);
+      }
+      if text.len() > width {
+        return Err(invalid(&format!("超出字段宽度 {}", width)));
+      }
+      Ok(text.into_bytes())
+    }
+    'L' => match value {
+      serde_json::Value::Bool(true) => Ok(vec![b'T']),
+      serde_json::Value::Bool(false) => Ok(vec![b'F']),
+      _ => Err(invalid("不是布尔值")),
+    },
+    'D' => {
+      let digits: String = value
+        .as_str()
+        .map(|text| text.chars().filter(|c| *c != '-').collect())
+        .unwrap_or_default();
+      let valid = digits.len() == 8
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && (1..=12).contains(&digits[4..6].parse::<u32>().unwrap_or(0))
+        && (1..=31).contains(&digits[6..8].parse::<u32>().unwrap_or(0));
+      if !valid {
+        return Err(invalid("不是有效的日期（YYYY-MM-DD）"));
+      }
+      Ok(digits.into_bytes())
+    }
+    other => Err(CustomError(format!(
+      "不支持写入 {} 类型的字段: {}",
+      other, field.name
+    ))),
+  }
+}
+
+// 当前 UTC 日期，仅用于 DBF 文件头的更新日期
+fn today() -> (i64, u32, u32) {
+  let days = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs() as i64 / 86_400)
+    .unwrap_or_default();
+  // Howard Hinnant 的 civil_from_days 算法
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shapefile_server::schema::field_type_name;
+  use serde_json::json;
+  use shapefile::dbase::{Date, FieldValue};
+  use shapefile::Shape;
+  use std::fs;
+
+  fn field(name: &str, field_type: char, length: u8, decimal_count: u8) -> DbfField {
+    DbfField {
+      name: name.to_string(),
+      field_type,
+      type_name: field_type_name(field_type),
+      length,
+      decimal_count,
+    }
+  }
+
+  fn be_i32(bytes: &[u8], position: usize) -> i32 {
+    i32::from_be_bytes(bytes[position..position + 4].try_into().unwrap())
+  }
+
+  #[test]
+  fn round_trip_with_shapefile_reader() {
+    let dir = std::env::temp_dir().join(format!("shp_writer_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let shp_path = dir.join("round_trip.shp");
+
+    let mut shp_writer = ShpWriter::create(&shp_path, 3).unwrap();
+    shp_writer
+      .write(Some(&Value::LineString(vec![
+        vec![0.0, 0.0],
+        vec![2.0, 1.0],
+      ])))
+      .unwrap();
+    shp_writer.write(None).unwrap();
+    shp_writer
+      .write(Some(&Value::MultiLineString(vec![
+        vec![vec![-1.0, 3.0], vec![4.0, 5.0], vec![6.0, -2.0]],
+        vec![vec![0.0, 0.0], vec![1.0, 1.0]],
+      ])))
+      .unwrap();
+    assert_eq!(shp_writer.finish().unwrap(), 3);
+
+    let fields = vec![
+      field("COUNT", 'N', 5, 0),
+      field("RATIO", 'N', 8, 3),
+      field("SCORE", 'F', 10, 2),
+      field("OK", 'L', 1, 0),
+      field("DAY", 'D', 8, 0),
+      field("NAME", 'C', 6, 0),
+    ];
+    let dbf_path = shp_path.with_extension("dbf");
+    let mut dbf_writer = DbfWriter::create(&dbf_path, &fields, encoding_rs::UTF_8).unwrap();
+    dbf_writer
+      .write(&[
+        json!(42),
+        json!(1.5),
+        json!(-2.25),
+        json!(true),
+        json!("2024-02-29"),
+        json!("道路"),
+      ])
+      .unwrap();
+    dbf_writer.write(&vec![serde_json::Value::Null; 6]).unwrap();
+    dbf_writer
+      .write(&[
+        json!("7"),
+        json!(0.12345),
+        json!(3),
+        json!(false),
+        json!("20240101"),
+        json!("abc"),
+      ])
+      .unwrap();
+    assert_eq!(dbf_writer.finish().unwrap(), 3);
+
+    // .shx 的偏移依次指向 .shp 中的记录头，长度与记录头一致
+    let shp = fs::read(&shp_path).unwrap();
+    let shx = fs::read(shp_path.with_extension("shx")).unwrap();
+    assert_eq!(be_i32(&shx, 24) as usize * 2, shx.len());
+    assert_eq!(be_i32(&shp, 24) as usize * 2, shp.len());
+    assert_eq!(shx.len(), SHP_HEADER_SIZE + 3 * 8);
+    let mut expected_offset = (SHP_HEADER_SIZE / 2) as i32;
+    for (index, entry) in shx[SHP_HEADER_SIZE..].chunks_exact(8).enumerate() {
+      let (offset, length) = (be_i32(entry, 0), be_i32(entry, 4));
+      assert_eq!(offset, expected_offset);
+      let record_start = offset as usize * 2;
+      assert_eq!(be_i32(&shp, record_start), index as i32 + 1);
+      assert_eq!(be_i32(&shp, record_start + 4), length);
+      expected_offset += 4 + length;
+    }
+
+    let mut reader = shapefile::Reader::from_path(&shp_path).unwrap();
+    let bbox = &reader.header().bbox;
+    assert_eq!(
+      [bbox.min.x, bbox.min.y, bbox.max.x, bbox.max.y],
+      [-1.0, -2.0, 6.0, 5.0]
+    );
+    let rows: Vec<_> = reader
+      .iter_shapes_and_records()
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(rows.len(), 3);
+    assert!(matches!(rows[1].0, Shape::NullShape));
+    match &rows[2].0 {
+      Shape::Polyline(polyline) => assert_eq!(polyline.parts().len(), 2),
+      shape => panic!("应为 Polyline: {}", shape),
+    }
+
+    let expected = [
+      [
+        FieldValue::Numeric(Some(42.0)),
+        FieldValue::Numeric(Some(1.5)),
+        FieldValue::Float(Some(-2.25)),
+        FieldValue::Logical(Some(true)),
+        FieldValue::Date(Some(Date::new(29, 2, 2024))),
+        FieldValue::Character(Some("道路".to_string())),
+      ],
+      [
+        FieldValue::Numeric(None),
+        FieldValue::Numeric(None),
+        FieldValue::Float(None),
+        FieldValue::Logical(None),
+        FieldValue::Date(None),
+        FieldValue::Character(None),
+      ],
+      [
+        FieldValue::Numeric(Some(7.0)),
+        FieldValue::Numeric(Some(0.123)),
+        FieldValue::Float(Some(3.0)),
+        FieldValue::Logical(Some(false)),
+        FieldValue::Date(Some(Date::new(1, 1, 2024))),
+        FieldValue::Character(Some("abc".to_string())),
+      ],
+    ];
+    for ((_, record), values) in rows.iter().zip(expected) {
+      for (field, value) in fields.iter().zip(values) {
+        assert_eq!(record.get(&field.name), Some(&value), "字段 {}", field.name);
+      }
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
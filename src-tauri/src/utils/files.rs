@@ -16,6 +16,46 @@ pub fn get_mbtiles_path() -> path::PathBuf {
   workspace_path.join("mbtiles")
 }
 
+pub fn get_geojson_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("geojson")
+}
+
+/// GeoJSON 导出的 shapefile 目录
+pub fn get_shapefile_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("shapefile")
+}
+
+/// 属性编辑会话的备份目录
+pub fn get_dbf_backup_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("dbf_backup")
+}
+
+/// 修复后的 shapefile 输出目录
+pub fn get_repaired_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("repaired")
+}
+
+/// 保存的切片配置目录
+pub fn get_tiling_profile_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("tiling_profiles")
+}
+
+pub fn get_spatial_index_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("spatial_index")
+}
+
+/// 解压等操作使用的临时目录，启动时清空
+pub fn get_temp_path() -> path::PathBuf {
+  let workspace_path = get_workspace_path();
+  workspace_path.join("tmp")
+}
+
 pub fn create_mbtiles_workspace() -> std::io::Result<()> {
   let workspace_path = path::Path::new("workspace");
   if !workspace_path.exists() {
@@ -39,4 +79,11 @@ pub fn init_workspace() {
       log::error!("Failed to initialize workspace: {}", e);
     }
   }
+  // 清理上次运行异常退出时遗留的临时文件
+  let temp_path = get_temp_path();
+  if temp_path.exists() {
+    if let Err(e) = fs::remove_dir_all(&temp_path) {
+      log::error!("Failed to clean temp directory: {}", e);
+    }
+  }
 }
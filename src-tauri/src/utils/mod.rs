@@ -1,5 +1,6 @@
 pub mod disk;
 pub mod files;
 pub mod log;
+pub mod progress;
 pub mod response;
 pub mod window;
@@ -0,0 +1,210 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// 前端监听的进度事件名
+pub const PROGRESS_EVENT: &str = "job-progress";
+
+static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 生成进程内唯一的任务 id
+pub fn new_job_id() -> String {
+  let millis = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_millis())
+    .unwrap_or_default();
+  let counter = JOB_COUNTER.fetch_add(1, Ordering::Relaxed);
+  format!("job-{}-{}", millis, counter)
+}
+
+/// 任务阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProgressPhase {
+  Pending,
+  Counting,
+  Converting,
+  Reprojecting,
+  Writing,
+  Tiling,
+  Validating,
+  Completed,
+  Failed,
+  Cancelled,
+}
+
+/// 发送给前端的进度事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+  pub job_id: String,
+  pub phase: ProgressPhase,
+  pub processed: u64,
+  pub total: Option<u64>,
+  pub warnings: Vec<String>,
+  pub message: Option<String>,
+}
+
+struct ProgressState {
+  phase: ProgressPhase,
+  total: Option<u64>,
+  warnings: Vec<String>,
+  message: Option<String>,
+}
+
+/// 通过 AppHandle 发送进度事件，可在线程间克隆共享
+///
+/// 计数每增加约 1% 发送一次，避免事件过多阻塞 IPC；
+/// 同时携带取消标记，耗时循环通过 `is_cancelled` 检查是否需要提前退出
+#[derive(Clone)]
+pub struct ProgressReporter {
+  /// 为空时只记录进度，不发送事件
+  app_handle: Option<AppHandle>,
+  job_id: Arc<str>,
+  processed: Arc<AtomicU64>,
+  cancelled: Arc<AtomicBool>,
+  state: Arc<Mutex<ProgressState>>,
+}
+
+impl ProgressReporter {
+  pub fn new(app_handle: &AppHandle, job_id: String) -> Self {
+    Self::with_handle(Some(app_handle.clone()), job_id)
+  }
+
+  /// 不发送事件的进度，用于测试
+  #[cfg(test)]
+  pub fn detached(job_id: &str) -> Self {
+    Self::with_handle(None, job_id.to_string())
+  }
+
+  fn with_handle(app_handle: Option<AppHandle>, job_id: String) -> Self {
+    ProgressReporter {
+      app_handle,
+      job_id: job_id.into(),
+      processed: Arc::new(AtomicU64::new(0)),
+      cancelled: Arc::new(AtomicBool::new(false)),
+      state: Arc::new(Mutex::new(ProgressState {
+        phase: ProgressPhase::Pending,
+        total: None,
+        warnings: Vec::new(),
+        message: None,
+      })),
+    }
+  }
+
+  pub fn job_id(&self) -> &str {
+    &self.job_id
+  }
+
+  /// 进入新阶段并重置计数
+  pub fn start_phase(&self, phase: ProgressPhase, total: Option<u64>) {
+    if self.is_cancelled() {
+      return;
+    }
+    self.processed.store(0, Ordering::Relaxed);
+    if let Ok(mut state) = self.state.lock() {
+      state.phase = phase;
+      state.total = total;
+    }
+    self.emit();
+  }
+
+  pub fn inc(&self, delta: u64) {
+    let processed = self.processed.fetch_add(delta, Ordering::Relaxed) + delta;
+    let total = self.state.lock().ok().and_then(|state| state.total);
+    let step = total.map_or(1000, |total| (total / 100).max(1));
+    if processed / step != (processed - delta) / step || Some(processed) == total {
+      self.emit();
+    }
+  }
+
+  /// 直接设置计数，用于按百分比汇报的外部进程
+  pub fn set_processed(&self, processed: u64) {
+    self.processed.store(processed, Ordering::Relaxed);
+    self.emit();
+  }
+
+  pub fn warn(&self, message: String) {
+    log::warn!("[{}] {}", self.job_id, message);
+    if let Ok(mut state) = self.state.lock() {
+      state.warnings.push(message);
+    }
+    self.emit();
+  }
+
+  pub fn warnings(&self) -> Vec<String> {
+    self
+      .state
+      .lock()
+      .map(|state| state.warnings.clone())
+      .unwrap_or_default()
+  }
+
+  pub fn finish(&self) {
+    if self.is_cancelled() {
+      return;
+    }
+    if let Ok(mut state) = self.state.lock() {
+      state.phase = ProgressPhase::Completed;
+      if let Some(total) = state.total {
+        self.processed.store(total, Ordering::Relaxed);
+      }
+    }
+    self.emit();
+  }
+
+  pub fn fail(&self, message: String) {
+    if self.is_cancelled() {
+      return;
+    }
+    if let Ok(mut state) = self.state.lock() {
+      state.phase = ProgressPhase::Failed;
+      state.message = Some(message);
+    }
+    self.emit();
+  }
+
+  /// 标记任务已取消，之后的阶段变化不再生效
+  pub fn cancel(&self) {
+    if self.cancelled.swap(true, Ordering::Relaxed) {
+      return;
+    }
+    if let Ok(mut state) = self.state.lock() {
+      state.phase = ProgressPhase::Cancelled;
+      state.message = Some("任务已取消".to_string());
+    }
+    self.emit();
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+
+  pub fn snapshot(&self) -> ProgressEvent {
+    let state = self.state.lock().ok();
+    ProgressEvent {
+      job_id: self.job_id.to_string(),
+      phase: state
+        .as_ref()
+        .map_or(ProgressPhase::Pending, |state| state.phase),
+      processed: self.processed.load(Ordering::Relaxed),
+      total: state.as_ref().and_then(|state| state.total),
+      warnings: state
+        .as_ref()
+        .map(|state| state.warnings.clone())
+        .unwrap_or_default(),
+      message: state.as_ref().and_then(|state| state.message.clone()),
+    }
+  }
+
+  fn emit(&self) {
+    let Some(app_handle) = &self.app_handle else {
+      return;
+    };
+    if let Err(e) = app_handle.emit(PROGRESS_EVENT, self.snapshot()) {
+      log::error!("发送进度事件失败: {}", e);
+    }
+  }
+}
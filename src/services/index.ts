@@ -1,2 +1,3 @@
 export * from './disk';
+export * from './job';
 export * from './shapefile';
@@ -0,0 +1,79 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import type { ApiResult } from '@/types';
+
+export type ProgressPhase =
+  | 'pending'
+  | 'counting'
+  | 'converting'
+  | 'reprojecting'
+  | 'writing'
+  | 'tiling'
+  | 'validating'
+  | 'completed'
+  | 'failed'
+  | 'cancelled';
+
+export type ProgressEvent = {
+  jobId: string;
+  phase: ProgressPhase;
+  processed: number;
+  total: number | null;
+  warnings: string[];
+  message: string | null;
+};
+
+/** 监听任务进度，传入 jobId 时只接收该任务的事件 */
+export const listenJobProgress = (handler: (event: ProgressEvent) => void, jobId?: string) => {
+  return listen<ProgressEvent>('job-progress', ({ payload }) => {
+    if (!jobId || payload.jobId === jobId) handler(payload);
+  });
+};
+
+export type JobKind =
+  | 'shapefileToGeojson'
+  | 'shapefileToRecord'
+  | 'createServer'
+  | 'validateShapefile'
+  | 'repairShapefile'
+  | 'ogrToGeojson'
+  | 'vectorTranslate';
+
+export type JobSummary = {
+  jobId: string;
+  kind: JobKind;
+  createdAt: number;
+  finished: boolean;
+  progress: ProgressEvent;
+};
+
+export const jobList = () => {
+  return invoke<ApiResult<JobSummary[]>>('job_list');
+};
+
+export const jobStatus = (jobId: string) => {
+  return invoke<ApiResult<JobSummary>>('job_status', { jobId });
+};
+
+/** 任务完成后返回原命令的结果，未完成或失败时抛出错误 */
+export const jobResult = (jobId: string) => {
+  return invoke<ApiResult>('job_result', { jobId });
+};
+
+export const jobCancel = (jobId: string) => {
+  return invoke<ApiResult<JobSummary>>('job_cancel', { jobId });
+};
+
+/** 清除已结束的任务及其结果 */
+export const jobClear = () => {
+  return invoke<ApiResult<number>>('job_clear');
+};
+
+/** 轮询直到任务结束并返回结果，任务失败或取消时抛出错误信息 */
+export const waitForJob = async (jobId: string, interval = 500) => {
+  for (;;) {
+    const status = await jobStatus(jobId);
+    if (status.data.finished) return jobResult(jobId);
+    await new Promise((resolve) => setTimeout(resolve, interval));
+  }
+};
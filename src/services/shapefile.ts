@@ -1,14 +1,538 @@
 import { invoke } from '@tauri-apps/api/core';
 import type { ApiResult } from '@/types';
 
-export const shapefileToServer = (shapefilePath: string) => {
-  return invoke<ApiResult>('create_server', { inputPath: shapefilePath });
+/**
+ * shapefilePath 可以是 .zip，压缩包包含多个数据集时通过 innerPath 指定
+ *
+ * 切片配置优先使用 profile，其次为保存的 profileName，都缺省时使用 1-18 级的默认配置
+ */
+export const shapefileToServer = (
+  shapefilePath: string,
+  jobId?: string,
+  innerPath?: string,
+  profile?: TilingProfile,
+  profileName?: string
+) => {
+  return invoke<ApiResult>('create_server', {
+    inputPath: shapefilePath,
+    jobId,
+    innerPath,
+    profile,
+    profileName
+  });
 };
 
-export const shapefileToGeojson = (shapefilePath: string) => {
-  return invoke<ApiResult>('shapefile_to_geojson', { shapefilePath });
+export type ZipDataset = {
+  innerPath: string;
+  name: string;
+  members: string[];
+  missing: string[];
 };
 
-export const shapefileToRecord = (shapefilePath: string) => {
-  return invoke<ApiResult>('shapefile_to_record', { shapefilePath });
+/** 列出压缩包中的 shapefile 数据集 */
+export const zipListDatasets = (zipPath: string) => {
+  return invoke<ApiResult<ZipDataset[]>>('zip_list_datasets', { zipPath });
+};
+
+export type MeasureMode = 'ignore' | 'property' | 'ordinate';
+
+export type GeometryFormat = 'wkt' | 'wkb' | 'ewkb';
+
+export type SimplifyOptions = {
+  /** 默认 douglasPeucker，容差为距离；visvalingamWhyatt 的容差为面积 */
+  method?: 'douglasPeucker' | 'visvalingamWhyatt';
+  /** 单位与输出坐标系一致 */
+  tolerance: number;
+  /**
+   * 相邻要素的公共边界按相同方式化简
+   *
+   * 需要在内存中记录全部顶点，每个顶点约 80 字节，输出到文件时同样如此
+   */
+  preserveTopology?: boolean;
+};
+
+export type ConvertOptions = {
+  measure?: MeasureMode;
+  geometryFormat?: GeometryFormat;
+  /** 以下仅用于 GeoJSON 输出，结果中的 vertexCount 为处理前后的顶点数 */
+  simplify?: SimplifyOptions;
+  /** 坐标保留的小数位数，0 到 15 */
+  precision?: number;
+};
+
+export type OutputOptions = {
+  format?: 'geojson' | 'geojsonSeq';
+  fileName?: string;
+};
+
+export type EncodingInfo = {
+  name: string;
+  source: 'override' | 'cpg' | 'languageDriver' | 'default';
+};
+
+type ConversionSummary = {
+  jobId: string;
+  nullCount: number;
+  skippedCount: number;
+  vertexCount: { before: number; after: number };
+  encoding: EncodingInfo;
+  crs: { source: NonNullable<LayerInfo['crs']>; target: string };
+  warnings: string[];
+};
+
+/** 结果包含 GeoJSON 文本与统计信息，不再是单独的 GeoJSON 字符串 */
+export type GeojsonResult = ConversionSummary & { geojson: string };
+
+/** 指定 output 时写入文件，只返回文件路径 */
+export type GeojsonFileResult = ConversionSummary & {
+  path: string;
+  featureCount: number;
+  bbox: [number, number, number, number] | null;
+};
+
+/** 以后台任务运行，结果通过 jobResult 获取，为 GeojsonResult 或 GeojsonFileResult */
+export const shapefileToGeojson = (
+  shapefilePath: string,
+  options?: ConvertOptions,
+  encoding?: string,
+  targetCrs?: string,
+  output?: OutputOptions,
+  jobId?: string,
+  innerPath?: string
+) => {
+  return invoke<ApiResult>('shapefile_to_geojson', {
+    shapefilePath,
+    options,
+    encoding,
+    targetCrs,
+    output,
+    jobId,
+    innerPath
+  });
+};
+
+export const shapefileToRecord = (
+  shapefilePath: string,
+  options?: ConvertOptions,
+  encoding?: string,
+  jobId?: string,
+  innerPath?: string
+) => {
+  return invoke<ApiResult>('shapefile_to_record', {
+    shapefilePath,
+    options,
+    encoding,
+    jobId,
+    innerPath
+  });
+};
+
+export type RecordFilter =
+  | { op: 'equals'; field: string; value: string | number | boolean | null }
+  | { op: 'range'; field: string; min?: string | number; max?: string | number }
+  | { op: 'contains'; field: string; value: string; ignoreCase?: boolean };
+
+export type RecordQuery = {
+  offset?: number;
+  limit?: number;
+  sort?: { field: string; descending?: boolean };
+  filters?: RecordFilter[];
+  omitGeometry?: boolean;
+};
+
+/** 分页读取属性表，需要 .shx 索引文件；有排序或过滤时每页都会扫描整个 DBF */
+export const shapefileRecordPage = (
+  shapefilePath: string,
+  query?: RecordQuery,
+  options?: ConvertOptions,
+  encoding?: string
+) => {
+  return invoke<ApiResult>('shapefile_record_page', { shapefilePath, query, options, encoding });
+};
+
+export type DbfField = {
+  name: string;
+  fieldType: string;
+  typeName: string;
+  length: number;
+  decimalCount: number;
+};
+
+/** 只读取文件头，返回图形类型、范围、记录数、字段定义、编码与坐标系 */
+export const shapefileSchema = (shapefilePath: string, encoding?: string) => {
+  return invoke<ApiResult>('shapefile_schema', { shapefilePath, encoding });
+};
+
+/** 查询与当前视图范围相交的要素，bbox 为 [minX, minY, maxX, maxY]，默认使用经纬度 */
+export const shapefileBboxQuery = (
+  shapefilePath: string,
+  bbox: [number, number, number, number],
+  zoom?: number,
+  options?: ConvertOptions,
+  encoding?: string,
+  targetCrs?: string
+) => {
+  return invoke<ApiResult>('shapefile_bbox_query', {
+    shapefilePath,
+    bbox,
+    zoom,
+    options,
+    encoding,
+    targetCrs
+  });
+};
+
+export type ShapefileExportOptions = {
+  fileName?: string;
+  sourceCrs?: string;
+  targetCrs?: string;
+  encoding?: string;
+  /** 覆盖同名文件，缺省时在文件名后追加 _1、_2… */
+  overwrite?: boolean;
+};
+
+export type FieldMapping = DbfField & { property: string };
+
+/** 将 GeoJSON 导出为 shapefile，geojson 与 geojsonPath 二选一，多种几何类型时按类型拆分 */
+export const geojsonToShapefile = (
+  geojson?: object,
+  geojsonPath?: string,
+  options?: ShapefileExportOptions
+) => {
+  return invoke<ApiResult>('geojson_to_shapefile', { geojson, geojsonPath, options });
+};
+
+export type EditSession = {
+  sessionId: string;
+  dbfPath: string;
+  encoding: string;
+  createdAt: number;
+  editedCount: number;
+};
+
+export type RecordEdit = {
+  index: number;
+  values: Record<string, string | number | boolean | null>;
+};
+
+/** 备份 DBF 并开始属性编辑会话 */
+export const dbfEditBegin = (shapefilePath: string, encoding?: string) => {
+  return invoke<ApiResult<EditSession>>('dbf_edit_begin', { shapefilePath, encoding });
+};
+
+/** 修改记录的字段值，按字段类型与长度校验，全部通过才写入 */
+export const dbfEditUpdate = (sessionId: string, edits: RecordEdit[]) => {
+  return invoke<ApiResult<EditSession>>('dbf_edit_update', { sessionId, edits });
+};
+
+export const dbfEditCommit = (sessionId: string) => {
+  return invoke<ApiResult<EditSession>>('dbf_edit_commit', { sessionId });
+};
+
+export const dbfEditRollback = (sessionId: string) => {
+  return invoke<ApiResult<EditSession>>('dbf_edit_rollback', { sessionId });
+};
+
+/** 未结束的编辑会话，包括上次运行中断的会话 */
+export const dbfEditSessions = () => {
+  return invoke<ApiResult<EditSession[]>>('dbf_edit_sessions');
+};
+
+export type FieldChange =
+  | {
+      op: 'add';
+      name: string;
+      fieldType: 'C' | 'N' | 'F' | 'L' | 'D';
+      length: number;
+      decimalCount?: number;
+      value?: string | number | boolean | null;
+    }
+  | { op: 'drop'; name: string }
+  | { op: 'rename'; name: string; newName: string }
+  | {
+      op: 'alter';
+      name: string;
+      fieldType?: 'C' | 'N' | 'F' | 'L' | 'D';
+      length?: number;
+      decimalCount?: number;
+    };
+
+/** 按顺序应用字段修改并重写 DBF，已有值按新定义转换，校验通过后才替换原文件 */
+export const dbfChangeFields = (shapefilePath: string, changes: FieldChange[], encoding?: string) => {
+  return invoke<ApiResult>('dbf_change_fields', { shapefilePath, changes, encoding });
+};
+
+export type ValidationIssue = {
+  severity: 'error' | 'warning' | 'info';
+  code: string;
+  recordIndex: number | null;
+  message: string;
+};
+
+export type ValidationReport = {
+  valid: boolean;
+  recordCount: number;
+  errorCount: number;
+  warningCount: number;
+  infoCount: number;
+  issues: ValidationIssue[];
+  truncated: boolean;
+};
+
+/** 检查 .shp/.shx/.dbf 的一致性与几何有效性，以后台任务运行，结果通过 jobResult 获取 */
+export const shapefileValidate = (
+  shapefilePath: string,
+  encoding?: string,
+  jobId?: string,
+  innerPath?: string
+) => {
+  return invoke<ApiResult>('shapefile_validate', { shapefilePath, encoding, jobId, innerPath });
+};
+
+export type RepairOptions = {
+  /** 删除空图形及其属性记录 */
+  removeNullShapes?: boolean;
+  /** 输出文件名，缺省为 `原文件名_repaired` */
+  fileName?: string;
+};
+
+export type RepairChange = {
+  code: string;
+  recordIndex: number | null;
+  message: string;
+};
+
+export type RepairReport = {
+  shpPath: string;
+  recordCount: number;
+  removedCount: number;
+  changeCounts: Record<string, number>;
+  changes: RepairChange[];
+  truncated: boolean;
+};
+
+/** 修复数据集并写入工作空间的 repaired 目录，原文件不做修改，结果通过 jobResult 获取 */
+export const shapefileRepair = (
+  shapefilePath: string,
+  options?: RepairOptions,
+  encoding?: string,
+  jobId?: string,
+  innerPath?: string
+) => {
+  return invoke<ApiResult>('shapefile_repair', {
+    shapefilePath,
+    options,
+    encoding,
+    jobId,
+    innerPath
+  });
+};
+
+export type FieldStatsOptions = {
+  /** 统计的字段，不区分大小写，缺省为全部字段 */
+  fields?: string[];
+  /** 分级数，默认 5 */
+  classCount?: number;
+  /** 直方图分组数，默认 10 */
+  histogramBins?: number;
+  /** 返回的不同值个数上限，默认 100 */
+  maxDistinct?: number;
+};
+
+/** 分级边界，依次为最小值、各级之间的断点、最大值 */
+export type ClassBreaks = {
+  equalInterval: number[];
+  quantile: number[];
+  jenks: number[];
+  stdDev: number[];
+};
+
+export type FieldStats = {
+  name: string;
+  fieldType: string;
+  count: number;
+  nullCount: number;
+  min: string | number | boolean | null;
+  max: string | number | boolean | null;
+  /** 以下仅数值字段有 */
+  mean: number | null;
+  stdDev: number | null;
+  histogram: { min: number; max: number; count: number }[] | null;
+  breaks: ClassBreaks | null;
+  distinctCount: number;
+  distinctValues: { value: string | number | boolean; count: number }[];
+  distinctTruncated: boolean;
+};
+
+/** 统计字段的值并计算分级断点，用于专题图配色 */
+export const shapefileFieldStats = (
+  shapefilePath: string,
+  options?: FieldStatsOptions,
+  encoding?: string
+) => {
+  return invoke<ApiResult>('shapefile_field_stats', { shapefilePath, options, encoding });
+};
+
+export type LayerField = {
+  name: string;
+  fieldType: string;
+  width: number;
+  precision: number;
+};
+
+export type LayerInfo = {
+  name: string;
+  /** 驱动无法快速统计时为空 */
+  featureCount: number | null;
+  geometryType: string;
+  fields: LayerField[];
+  crs: { name: string | null; epsg: number | null; wkt: string } | null;
+  /** [minX, minY, maxX, maxY] */
+  extent: [number, number, number, number] | null;
+  extentWgs84: [number, number, number, number] | null;
+};
+
+/** 列出 GeoPackage、FileGDB、KML、GPX、CSV 等 OGR 数据源中的图层 */
+export const ogrListLayers = (path: string) => {
+  return invoke<ApiResult>('ogr_list_layers', { path });
+};
+
+/** 将 OGR 图层转换为 GeoJSON，缺省读取第一个图层，结果通过 jobResult 获取，格式与 shapefileToGeojson 一致 */
+export const ogrToGeojson = (
+  path: string,
+  layer?: string,
+  options?: ConvertOptions,
+  sourceCrs?: string,
+  targetCrs?: string,
+  jobId?: string
+) => {
+  return invoke<ApiResult>('ogr_to_geojson', {
+    path,
+    layer,
+    options,
+    sourceCrs,
+    targetCrs,
+    jobId
+  });
+};
+
+export type BandInfo = {
+  /** 从 1 开始 */
+  index: number;
+  dataType: string;
+  colorInterpretation: string;
+  description: string;
+  noData: number | null;
+  /** 各级金字塔的 [宽, 高] */
+  overviews: [number, number][];
+};
+
+export type RasterInfo = {
+  width: number;
+  height: number;
+  crs: LayerInfo['crs'];
+  geoTransform: [number, number, number, number, number, number] | null;
+  extent: [number, number, number, number] | null;
+  extentWgs84: [number, number, number, number] | null;
+  bands: BandInfo[];
+};
+
+export type DatasetInfo = {
+  path: string;
+  driver: string;
+  driverLongName: string;
+  /** 矢量图层，栅格数据为空数组 */
+  layers: LayerInfo[];
+  /** 栅格信息，矢量数据为 null */
+  raster: RasterInfo | null;
+};
+
+/** 读取任意矢量或栅格数据的概要，相当于 ogrinfo / gdalinfo */
+export const datasetInfo = (path: string) => {
+  return invoke<ApiResult>('dataset_info', { path });
+};
+
+export type TranslateOptions = {
+  /** GDAL 驱动名称，如 MBTiles、GPKG、GeoJSON、FlatGeobuf */
+  format: string;
+  targetCrs?: string;
+  /** 源坐标系，指定后覆盖源数据自带的坐标系 */
+  sourceCrs?: string;
+  /** 只转换指定图层，缺省转换全部图层 */
+  layers?: string[];
+  /** 数据集创建选项，如 MINZOOM=0 */
+  datasetOptions?: string[];
+  layerOptions?: string[];
+  /** 目标文件已存在时覆盖 */
+  overwrite?: boolean;
+};
+
+/**
+ * 使用 GDAL 转换矢量数据格式，驱动缺失时直接返回错误，结果通过 jobResult 获取
+ *
+ * outputPath 必须位于工作空间内
+ */
+export const vectorTranslate = (
+  inputPath: string,
+  outputPath: string,
+  options: TranslateOptions,
+  jobId?: string,
+  innerPath?: string
+) => {
+  return invoke<ApiResult>('vector_translate', {
+    inputPath,
+    outputPath,
+    options,
+    jobId,
+    innerPath
+  });
+};
+
+/** 切片配置，对应 GDAL MBTiles/MVT 驱动的创建选项 */
+export type TilingProfile = {
+  /** 默认 1 */
+  minZoom?: number;
+  /** 默认 18，最大 22 */
+  maxZoom?: number;
+  /** 瓦片中的图层名，缺省沿用文件名 */
+  layerName?: string;
+  /** 保留的属性字段，缺省保留全部字段 */
+  attributes?: string[];
+  /** 低于最大级别时的简化容差，单位为瓦片像素 */
+  simplification?: number;
+  /** 最大级别的简化容差，缺省与 simplification 相同 */
+  simplificationMaxZoom?: number;
+  /** 单个瓦片的最大字节数，默认 500000 */
+  maxSize?: number;
+  /** 单个瓦片的最大要素数，默认 200000 */
+  maxFeatures?: number;
+  /** 默认 mbtiles，directory 输出 {z}/{x}/{y} 瓦片目录 */
+  format?: 'mbtiles' | 'directory';
+  /** 瓦片目录的文件扩展名，默认 pbf */
+  tileExtension?: 'pbf' | 'mvt';
+  /** 是否对瓦片进行 gzip 压缩，默认 true */
+  compress?: boolean;
+  /** 经纬度范围 [minX, minY, maxX, maxY]，仅写入 MBTiles 元数据 */
+  bounds?: [number, number, number, number];
+  name?: string;
+  description?: string;
+};
+
+export type SavedTilingProfile = {
+  name: string;
+  profile: TilingProfile;
+};
+
+/** 列出保存的切片配置 */
+export const tilingProfileList = () => {
+  return invoke<ApiResult<SavedTilingProfile[]>>('tiling_profile_list');
+};
+
+/** 保存切片配置，同名配置会被覆盖 */
+export const tilingProfileSave = (name: string, profile: TilingProfile) => {
+  return invoke<ApiResult<SavedTilingProfile>>('tiling_profile_save', { name, profile });
+};
+
+export const tilingProfileDelete = (name: string) => {
+  return invoke<ApiResult>('tiling_profile_delete', { name });
 };